@@ -1,34 +1,863 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use ink_lang as ink;
+use ink_prelude::{string::String, vec::Vec};
+
+type AccountId = <ink_env::DefaultEnvironment as ink_env::Environment>::AccountId;
+type Balance = <ink_env::DefaultEnvironment as ink_env::Environment>::Balance;
+
+/// Errors of the standard `PSP22` interface
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP22Error {
+    /// Return for any other error, carrying the name of the token's own error
+    Custom(String),
+    /// Return if the balance cannot fulfill a request
+    InsufficientBalance,
+    /// Return if the allowance cannot fulfill a request
+    InsufficientAllowance,
+    /// Return if the recipient is the zero address
+    ZeroRecipientAddress,
+    /// Return if the sender is the zero address
+    ZeroSenderAddress,
+    /// Return if a receiving contract rejected a safe transfer
+    SafeTransferCheckFailed(String),
+}
+
+/// Standard PSP22 fungible-token interface, so other contracts can call any
+/// compliant token through the same `PSP22::*` selectors
+#[ink::trait_definition]
+pub trait PSP22 {
+    /// Returns the total token supply.
+    #[ink(message)]
+    fn total_supply(&self) -> Balance;
+
+    /// Returns the account balance for the specified `owner`.
+    #[ink(message)]
+    fn balance_of(&self, owner: AccountId) -> Balance;
+
+    /// Returns the amount `spender` is still allowed to withdraw from `owner`.
+    #[ink(message)]
+    fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance;
+
+    /// Transfers `value` tokens from the caller to `to`; `data` is ignored.
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, value: Balance, data: Vec<u8>) -> Result<(), PSP22Error>;
+
+    /// Transfers `value` tokens from `from` to `to` using the caller's allowance; `data` is ignored.
+    #[ink(message)]
+    fn transfer_from(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        value: Balance,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22Error>;
+
+    /// Allows `spender` to withdraw from the caller's account up to `value`.
+    #[ink(message)]
+    fn approve(&mut self, spender: AccountId, value: Balance) -> Result<(), PSP22Error>;
+
+    /// Raises the allowance of `spender` for the caller by `delta_value`.
+    #[ink(message)]
+    fn increase_allowance(
+        &mut self,
+        spender: AccountId,
+        delta_value: Balance,
+    ) -> Result<(), PSP22Error>;
+
+    /// Lowers the allowance of `spender` for the caller by `delta_value`.
+    #[ink(message)]
+    fn decrease_allowance(
+        &mut self,
+        spender: AccountId,
+        delta_value: Balance,
+    ) -> Result<(), PSP22Error>;
+}
 
 #[ink::contract]
 mod erc20 {
-    use ink_storage::{traits::SpreadAllocate, Mapping};
+    use super::{PSP22Error, PSP22};
+    use ink_env::call::{build_call, Call, ExecutionInput, Selector};
+    use ink_prelude::{format, string::String, vec::Vec};
+    #[cfg(feature = "std")]
+    use ink_storage::traits::StorageLayout;
+    use ink_storage::{
+        traits::{PackedLayout, SpreadAllocate, SpreadLayout},
+        Mapping,
+    };
 
     /// Specify the ERC-20 error type
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
     pub enum Error {
         /// Return if the balance cannot fulfill a request
-        InsufficientBalance,
+        InsufficientBalance {
+            /// Tokens the request needs
+            required: Balance,
+            /// Tokens the account holds
+            available: Balance,
+        },
         /// Return if the allowance cannot fulfill a request
-        InsufficientAllowance,
+        InsufficientAllowance {
+            /// Allowance the request needs
+            required: Balance,
+            /// Allowance the spender holds
+            available: Balance,
+        },
+        /// Return if the caller is not the contract owner
+        NotOwner,
+        /// Return if a distribution has no eligible holders to accrue it
+        NoEligibleHolders,
+        /// Return if either side of a transfer is on the denylist
+        Blacklisted,
+        /// Return if split weights do not sum to exactly 10000 basis points
+        InvalidWeights,
+        /// Return if ownership is handed over again before the cooldown has passed
+        OwnershipChangeTooSoon,
+        /// Return if a renounce is finalized before its grace period has passed
+        RenounceNotReady,
+        /// Return if dividends are claimed again before the claim cooldown has passed
+        ClaimCooldownActive,
+        /// Return if a call to another token contract held by the treasury failed
+        TreasuryCallFailed,
+        /// Return if a large transfer goes to a recipient that has not opted in to them
+        RecipientNotOptedIn,
+        /// Return if forwarding native currency failed
+        NativeTransferFailed,
+        /// Return if an account exceeds its activity cap for the current day
+        DailyActivityCapExceeded,
+        /// Return if the activity window is set to zero
+        InvalidActivityWindow,
+        /// Return if delegated spending is frozen by the owner
+        AllowancesFrozen,
+        /// Return if a tainted account sends anywhere but the quarantine address
+        TaintedFunds,
+        /// Return if tokens would be sent to the token contract itself
+        TransferToContract,
+        /// Return if a transfer is smaller than the flat fee it would be charged
+        AmountBelowFee,
+        /// Return if tokens would be sent to a contract whose code hash is not allowlisted
+        CodeHashNotAllowed,
+        /// Return if an owner in allowlist mode approves a spender not on their allowlist
+        SpenderNotAllowed,
+        /// Return if the recipient holds less than the minimum recipient balance
+        RecipientBalanceTooLow,
+        /// Return if the call checking the sender's partner NFTs failed
+        NftCheckFailed,
+        /// Return if a fee discount above 100% is configured
+        InvalidFeeDiscount,
+        /// Return if a pair of accounts has used up the transfers allowed between them
+        PairTransferLimitReached,
+        /// Return if transfers between the sender's and recipient's regions are not allowed
+        RegionRestricted,
+        /// Return if tokens are redeemed while wind-down is not active
+        WindDownNotActive,
+        /// Return if the contract's native reserves cannot cover a redemption
+        InsufficientReserves,
+        /// Return if an operation would overflow the total supply or its dividend accounting
+        Overflow,
+        /// Return if a scheduled transfer is executed before it is due
+        NotYetDue,
+        /// Return if no pending scheduled or conditional transfer has the given id
+        UnknownSchedule,
+        /// Return if anyone but its scheduler cancels a scheduled transfer
+        NotScheduler,
+        /// Return if the oracle reports a transfer's condition as not met
+        ConditionNotMet,
+        /// Return if the call to the condition oracle failed or no oracle is set
+        OracleCallFailed,
+        /// Return if a mint would take the minter past its lifetime mint cap
+        LifetimeMintCapExceeded,
+        /// Return if tokens are moved or approved while the contract is paused
+        ContractPaused,
+        /// Return if anyone but the pending owner accepts ownership
+        NotPendingOwner,
+        /// Return if the recipients and values of a batch differ in length
+        LengthMismatch,
+        /// Return if redemptions are requested or processed while no redemption rate is set
+        RedemptionsNotEnabled,
+        /// Return if an optional message is called that this build was compiled without
+        FeatureNotSupported,
+        /// Return if a permit is submitted after its deadline
+        PermitExpired,
+        /// Return if a fee split above 100% is configured
+        InvalidFeeSplit,
+        /// Return if a fee holiday does not end after it starts
+        InvalidFeeHoliday,
+        /// Return if the circuit breaker is configured with a zero threshold or window
+        InvalidCircuitBreaker,
+        /// Return if the fee could not be collected in the fee token, or no fee token is set
+        FeeTokenPaymentFailed,
+        /// Return if funds are seized from an account that is not blacklisted
+        NotBlacklisted,
+        /// Return if an account has no seized funds in escrow
+        NothingSeized,
+        /// Return if seized funds are released while their appeal window is still open
+        AppealPending,
+        /// Return if seized funds are returned after their appeal window closed
+        AppealWindowClosed,
+        /// Return if a provenance reference points at a transfer id not handed out yet
+        UnknownProvenanceRef,
+        /// Return if interest is switched on with a zero rate
+        InvalidInterestRate,
+        /// Return if the fee rebate rate exceeds 10000 basis points
+        InvalidRebateRate,
+        /// Return if a categorized transfer exceeds the sender's remaining budget in that category
+        CategoryBudgetExceeded,
+        /// Return if an account that is not thawed acts, or has tokens moved, during a global freeze
+        GloballyFrozen,
+        /// Return if demurrage is switched on with a zero rate or the contract account as its pool
+        InvalidDemurrage,
+        /// Return if no disputable transfer has the given id
+        DisputeNotFound,
+        /// Return if anyone but its sender reverses a disputable transfer
+        NotSender,
+        /// Return if a disputable transfer is finalized while its dispute window is open
+        DisputeWindowOpen,
+        /// Return if a disputable transfer is reversed after its dispute window closed
+        DisputeWindowClosed,
+        /// Return if the raffle's share of fees exceeds 10000 basis points
+        InvalidRaffleShare,
+        /// Return if an account enters the raffle twice or it already has `MAX_RAFFLE_ENTRANTS` entrants
+        RaffleEntryRejected,
+        /// Return if a raffle is drawn while its prize pool is empty
+        EmptyPrizePool,
+        /// Return if an account of a non-transferable share class sends tokens
+        ClassNotTransferable,
+        /// Return if tokens are minted into a class other than the one the recipient holds
+        ClassMismatch,
+        /// Return if the caller is neither the owner nor holds the role a message requires
+        NotAuthorized,
+        /// Return if tokens are sent or minted to the all-zeroes account
+        ZeroAddress,
+        /// Return if a receiving contract rejected or trapped on a transfer with data
+        TransferRejected,
+        /// Return if past votes are looked up for the current or a future block
+        FutureLookup,
+        /// Return if a vesting schedule is empty, has no duration or its cliff outlasts it
+        InvalidVestingSchedule,
+        /// Return if none of the caller's vesting schedules has tokens to release
+        NothingVested,
+        /// Return if the percentage transfer fee is set above 100%
+        InvalidFeeRate,
+        /// Return if the runtime refused to switch the contract to new code
+        UpgradeFailed,
+        /// Return if storage is already laid out for the running code
+        AlreadyMigrated,
+        /// Return if a Merkle airdrop claim's proof does not lead to the airdrop root
+        InvalidAirdropProof,
+        /// Return if staking rewards are switched on with a zero rate
+        InvalidRewardRate,
+        /// Return if no pending proposal has the given id
+        UnknownProposal,
+        /// Return if a proposal is confirmed after its expiry
+        ProposalExpired,
+        /// Return if an admin confirms the same proposal twice
+        AlreadyConfirmed,
+        /// Return if the multisig is configured to need no confirmations
+        InvalidThreshold,
+        /// Return if a guarded message is entered again while it is still running
+        ReentrantCall,
+        /// Return if a privileged call must go through the multisig since it needs confirmations
+        MultisigRequired,
+        /// Return if a share class's voting rights change after tokens were minted into it
+        ClassInUse,
+        /// Return if a snapshot id has not been taken yet
+        UnknownSnapshot,
+        /// Return if a permit's signature does not come from the owner
+        InvalidSignature,
+        /// Return if a party has not accepted the current terms of service
+        TosNotAccepted,
+        /// Return if an account accepts a terms-of-service version other than the current one
+        UnknownTosVersion,
+        /// Return if an account held too little at a snapshot to claim its reward
+        NotEligible,
+        /// Return if an account claims a snapshot's reward a second time
+        AlreadyClaimed,
+        /// Return if a transfer would leave the recipient less than the minimum it must receive
+        SlippageExceeded,
+        /// Return if a claim expects a different reward than the one configured
+        RewardMismatch,
+        /// Return if a mint would take the total supply past the cap
+        CapExceeded,
+        /// Return if the cap is not lowered, or lowered below the total supply
+        InvalidCap,
+        /// Return if the sender and recipient belong to different groups
+        CrossGroupTransfer,
+        /// Return if a transfer would dip into the locked part of the sender's balance
+        AmountLocked,
+        /// Return if the first-come airdrop has no claims left, or the Merkle airdrop pool too few tokens
+        AirdropExhausted,
+        /// Return if a recipient's Merkle proof does not lead to the recipient root
+        RecipientNotProven,
+        /// Return if a stake is too small to mint a single share, or no shares are unstaked
+        StakeTooSmall,
+        /// Return if an account unstakes more shares than it holds
+        InsufficientShares,
+        /// Return if a transfer is not yet allowed in the current launch phase
+        LaunchRestricted,
+        /// Return if the launch phase is advanced past the last phase
+        InvalidLaunchPhase,
+        /// Return if no open swap has the given id
+        SwapNotFound,
+        /// Return if anyone but a swap's counterparty accepts it, or anyone but its creator cancels it
+        WrongCounterparty,
+        /// Return if the counterparty's side of a swap could not be transferred
+        SwapCallFailed,
     }
 
     /// Specify the ERC-20 result type
     pub type Result<T> = core::result::Result<T, Error>;
 
+    /// Vesting schedule as `(start, cliff, duration, amount, released)`
+    pub type VestingSchedule = (Timestamp, Timestamp, Timestamp, Balance, Balance);
+
+    impl From<Error> for PSP22Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InsufficientBalance { .. } => PSP22Error::InsufficientBalance,
+                Error::InsufficientAllowance { .. } => PSP22Error::InsufficientAllowance,
+                Error::ZeroAddress => PSP22Error::ZeroRecipientAddress,
+                error => PSP22Error::Custom(format!("{:?}", error)),
+            }
+        }
+    }
+
+    /// Outcome of previewing a batch of transfers with `preview_transfer_batch`
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct BatchPreview {
+        /// Per-entry result: the amount the recipient would receive, or the error
+        pub results: Vec<Result<Balance>>,
+        /// Balance the sender would be left with after the whole batch
+        pub final_balance: Balance,
+    }
+
+    /// Kind of queued or escrowed item listed by `pending_operations`
+    #[derive(
+        Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub enum PendingOpKind {
+        /// A transfer waiting for its execution time
+        ScheduledTransfer,
+        /// A transfer waiting for its oracle condition
+        ConditionalTransfer,
+        /// A redemption waiting in the FIFO queue
+        Redemption,
+        /// A token-for-token swap waiting for its counterparty
+        Swap,
+        /// A transfer locked at its recipient until its dispute window closes
+        DisputableTransfer,
+    }
+
+    /// Queued or escrowed item an account is party to, listed by `pending_operations`
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct PendingOp {
+        /// Queue the item is in
+        pub kind: PendingOpKind,
+        /// Id of the item within its queue
+        pub id: u64,
+        /// Tokens escrowed for the item
+        pub amount: Balance,
+        /// Earliest time the item can execute, if it has to wait for one
+        pub executable_at: Option<Timestamp>,
+    }
+
+    /// Safety-relevant state aggregated by `health` for monitoring
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Health {
+        /// Whether the contract is paused
+        pub paused: bool,
+        /// Whether the native reserves cover the whole supply at the active redemption rate
+        pub backing_ratio_ok: bool,
+        /// Native currency held by the contract
+        pub reserves: Balance,
+        /// Whether no further tokens can be minted
+        pub minting_disabled: bool,
+    }
+
+    /// Role granting access to a privileged message besides the owner
+    #[derive(
+        Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub enum Role {
+        /// May call `mint`
+        Minter,
+        /// May call `pause` and `unpause`
+        Pauser,
+        /// May propose and confirm multisig actions
+        Admin,
+    }
+
+    /// Privileged call executed by the multisig once enough admins confirmed it
+    #[derive(
+        Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub enum Action {
+        /// Mint tokens like `mint`
+        Mint(AccountId, Balance),
+        /// Pause the contract like `pause`
+        Pause,
+        /// Lift a pause like `unpause`
+        Unpause,
+        /// Switch to new code like `upgrade`
+        Upgrade(Hash),
+        /// Change the confirmations and lifetime like `set_multisig_config`
+        SetMultisigConfig(u32, Timestamp),
+    }
+
+    /// Kind of balance change reported by a `TaxableEvent`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum TaxableKind {
+        /// Tokens sent by the account
+        TransferOut,
+        /// Tokens received by the account
+        TransferIn,
+        /// Tokens minted to the account
+        Mint,
+        /// Tokens burned from the account
+        Burn,
+    }
+
+    /// Selector of `PSP22::balance_of` on other token contracts
+    const PSP22_BALANCE_OF_SELECTOR: [u8; 4] = [0x65, 0x68, 0x38, 0x2f];
+
+    /// Selector of `PSP22::transfer` on other token contracts
+    const PSP22_TRANSFER_SELECTOR: [u8; 4] = [0xdb, 0x20, 0xf9, 0xf5];
+
+    /// Selector of `PSP22::transfer_from` on other token contracts
+    const PSP22_TRANSFER_FROM_SELECTOR: [u8; 4] = [0x54, 0xb3, 0xc7, 0x6e];
+
+    /// Selector of `PSP22Receiver::before_received(operator, from, value, data)` on receiving contracts
+    const PSP22_BEFORE_RECEIVED_SELECTOR: [u8; 4] =
+        ink_lang::selector_bytes!("PSP22Receiver::before_received");
+
+    /// Selector of `record_balance_change(account, old, new)` on the mirror contract
+    const RECORD_BALANCE_CHANGE_SELECTOR: [u8; 4] =
+        ink_lang::selector_bytes!("record_balance_change");
+
+    /// Selector of `PSP34::balance_of` on partner NFT contracts
+    const PSP34_BALANCE_OF_SELECTOR: [u8; 4] = [0xcd, 0xe7, 0xe5, 0x5f];
+
+    /// Version of this contract's interface, bumped on breaking changes
+    const CONTRACT_VERSION: u32 = 1;
+
+    /// Version of the storage layout this code expects, bumped whenever `migrate` gains a step
+    const STORAGE_VERSION: u32 = 1;
+
+    /// Selectors of the optional messages enabled by the `mintable` feature
+    const MINTABLE_SELECTORS: [[u8; 4]; 4] = [
+        ink_lang::selector_bytes!("mint"),
+        ink_lang::selector_bytes!("mint_with_class"),
+        ink_lang::selector_bytes!("claim_first_come"),
+        ink_lang::selector_bytes!("claim_rebate"),
+    ];
+
+    /// Selectors of the optional messages enabled by the `burnable` feature
+    const BURNABLE_SELECTORS: [[u8; 4]; 2] = [
+        ink_lang::selector_bytes!("burn"),
+        ink_lang::selector_bytes!("burn_from"),
+    ];
+
+    /// Selectors of the optional messages enabled by the `pausable` feature
+    const PAUSABLE_SELECTORS: [[u8; 4]; 2] = [
+        ink_lang::selector_bytes!("pause"),
+        ink_lang::selector_bytes!("unpause"),
+    ];
+
+    /// `account_flags` bit: the account is currently blacklisted
+    pub const FLAG_BLACKLISTED: u32 = 1 << 0;
+    /// `account_flags` bit: the account is tainted and may only send to the quarantine address
+    pub const FLAG_TAINTED: u32 = 1 << 1;
+    /// `account_flags` bit: the account accepts transfers above the large-transfer threshold
+    pub const FLAG_LARGE_TRANSFER_OPTIN: u32 = 1 << 2;
+    /// `account_flags` bit: the account's balance changes are reported with `TaxableEvent`
+    pub const FLAG_TAX_REPORTING: u32 = 1 << 3;
+    /// `account_flags` bit: the account only approves spenders on its allowlist
+    pub const FLAG_SPENDER_ALLOWLIST: u32 = 1 << 4;
+    /// `account_flags` bit: the account is the owner
+    pub const FLAG_OWNER: u32 = 1 << 5;
+    /// `account_flags` bit: the account is the treasury
+    pub const FLAG_TREASURY: u32 = 1 << 6;
+    /// `account_flags` bit: the account is the quarantine address
+    pub const FLAG_QUARANTINE: u32 = 1 << 7;
+
+    /// Feature flag gating the flat transfer fee
+    pub const FEATURE_FEES: [u8; 4] = *b"fees";
+    /// Feature flag requiring both parties to have accepted the current terms of service
+    pub const FEATURE_TOS: [u8; 4] = *b"tos_";
+    /// Feature flag restricting transfers by launch phase
+    pub const FEATURE_LAUNCH: [u8; 4] = *b"lnch";
+    /// Launch phase in which only the owner can send tokens
+    pub const LAUNCH_PHASE_CLOSED: u8 = 0;
+    /// Launch phase in which transfers are capped unless the sender is on the launch allowlist
+    pub const LAUNCH_PHASE_CAPPED: u8 = 1;
+    /// Launch phase in which transfers are unrestricted
+    pub const LAUNCH_PHASE_OPEN: u8 = 2;
+    /// Feature flag keeping transfers within the sender's and recipient's group
+    pub const FEATURE_GROUPS: [u8; 4] = *b"grps";
+
+    /// Selector of `is_met(condition_id) -> bool` on the condition oracle
+    const CONDITION_IS_MET_SELECTOR: [u8; 4] = [0x2b, 0x8b, 0x56, 0x08];
+
+    /// Basis points making up a whole, used for weights and rates
+    const BPS_DENOMINATOR: u32 = 10_000;
+
+    /// Number of transfers kept by the `recent-transfers` log
+    #[cfg(feature = "recent-transfers")]
+    const RECENT_TRANSFERS_LEN: u32 = 16;
+
+    /// The all-zeroes account, which can neither receive transfers nor mints
+    const ZERO_ADDRESS: [u8; 32] = [0; 32];
+
+    /// Most accounts that may be entered in the raffle at once
+    const MAX_RAFFLE_ENTRANTS: usize = 100;
+
+    /// Number of provenance records kept before the oldest are overwritten
+    const PROVENANCE_LEN: u64 = 64;
+
+    /// Default time a renounce must stay pending: one day in milliseconds
+    const DEFAULT_RENOUNCE_GRACE_PERIOD: Timestamp = 24 * 60 * 60 * 1000;
+
+    /// Default time a blacklisted account has to appeal a seizure: thirty days
+    const DEFAULT_APPEAL_PERIOD: Timestamp = 30 * 24 * 60 * 60 * 1000;
+
+    /// Default time admins have to confirm a multisig proposal: seven days
+    const DEFAULT_PROPOSAL_LIFETIME: Timestamp = 7 * 24 * 60 * 60 * 1000;
+
+    /// Default time after which an unsettled conditional transfer can be refunded: one week
+    const DEFAULT_CONDITION_TIMEOUT: Timestamp = 7 * 24 * 60 * 60 * 1000;
+
+    /// Default length of an activity window: one day in milliseconds
+    const DEFAULT_ACTIVITY_WINDOW: Timestamp = 24 * 60 * 60 * 1000;
+
+    /// Fixed-point scale applied to `dividends_per_share`
+    const DIVIDEND_PRECISION: Balance = 1_000_000_000_000;
+
+    /// Fixed-point scale applied to `share_price`
+    const SHARE_PRICE_PRECISION: Balance = 1_000_000_000_000;
+
+    /// Fixed-point scale of the interest rate: a rate of this much accrues 100% per millisecond
+    const INTEREST_RATE_PRECISION: Balance = 1_000_000_000_000_000_000;
+
+    /// Fixed-point scale of the demurrage rate: a rate of this much decays 100% per millisecond
+    const DEMURRAGE_RATE_PRECISION: Balance = 1_000_000_000_000_000_000;
+
     /// Creates storage for a simple ERC20 token contract.
     #[ink(storage)]
     #[derive(SpreadAllocate)]
     pub struct Erc20 {
         /// Total token supply
         total_supply: Balance,
+        /// Name of the token, if set
+        name: Option<String>,
+        /// Symbol of the token, if set
+        symbol: Option<String>,
+        /// Number of decimals used to display amounts
+        decimals: u8,
         /// Mapping from owner to number of owned tokens
         balances: Mapping<AccountId, Balance>,
         /// Balances that can be transferred by non-owners: (owner, spender) -> allowed
         allowances: Mapping<(AccountId, AccountId), Balance>,
+        /// Allowance as last granted by `approve`: (owner, spender) -> granted
+        original_allowances: Mapping<(AccountId, AccountId), Balance>,
+        /// When each allowance was last set or spent from: (owner, spender) -> timestamp
+        allowance_updated_at: Mapping<(AccountId, AccountId), Timestamp>,
+        /// Account allowed to perform privileged operations, set to the deployer;
+        /// `None` once ownership has been renounced
+        owner: Option<AccountId>,
+        /// Accumulated dividends per held token, scaled by `DIVIDEND_PRECISION`
+        dividends_per_share: Balance,
+        /// Dividends already accounted for per account, scaled by `DIVIDEND_PRECISION`
+        dividend_debt: Mapping<AccountId, Balance>,
+        /// Dividends settled on balance changes but not yet claimed
+        pending_dividends: Mapping<AccountId, Balance>,
+        /// Minimum time between two paid-out dividend claims of the same account
+        claim_cooldown: Timestamp,
+        /// Time of each account's last paid-out dividend claim
+        last_claim_at: Mapping<AccountId, Timestamp>,
+        /// Most a single claim pays out; the remainder is carried forward
+        max_reward_per_claim: Option<Balance>,
+        /// Accounts barred from sending or receiving tokens, until an optional expiry
+        blacklisted: Mapping<AccountId, Option<Timestamp>>,
+        /// Account proposed as the next owner, until it accepts
+        pending_owner: Option<AccountId>,
+        /// Minimum time that must pass between two ownership transfers
+        ownership_cooldown: Timestamp,
+        /// Time of the last ownership transfer, if any
+        last_ownership_change: Option<Timestamp>,
+        /// How long a renounce must stay pending before it can be finalized
+        renounce_grace_period: Timestamp,
+        /// Time at which the pending renounce was initiated, if any
+        renounce_initiated_at: Option<Timestamp>,
+        /// Code hashes of vetted contracts that may receive tokens in strict mode
+        allowed_code_hashes: Mapping<Hash, ()>,
+        /// Restricts transfers to contracts to those with an allowlisted code hash
+        code_hash_strict: bool,
+        /// Version of the storage layout, brought up to `STORAGE_VERSION` by `migrate`
+        storage_version: u32,
+        /// Accounts that accept transfers above `large_transfer_threshold`
+        large_transfer_optin: Mapping<AccountId, ()>,
+        /// Transfers above this value require the recipient to have opted in
+        large_transfer_threshold: Option<Balance>,
+        /// Balance a recipient must already hold to receive transfers, or `None` for no gate
+        min_recipient_balance: Option<Balance>,
+        /// Region code each tagged account is located in
+        region_of: Mapping<AccountId, u16>,
+        /// Region pairs transfers may cross: (from region, to region) -> ()
+        allowed_region_pairs: Mapping<(u16, u16), ()>,
+        /// Blocks transfers where the sender or recipient has no region tag
+        untagged_restricted: bool,
+        /// Current launch phase, see `LAUNCH_PHASE_CLOSED` and the following phases
+        launch_phase: u8,
+        /// Most a single transfer may move in the capped launch phase
+        launch_transfer_cap: Balance,
+        /// Senders exempt from the transfer cap in the capped launch phase
+        launch_allowlist: Mapping<AccountId, ()>,
+        /// Part of each account's balance that cannot be transferred
+        locked_amount: Mapping<AccountId, Balance>,
+        /// Group each account belongs to; absent means group 0, which is ungrouped
+        group_of: Mapping<AccountId, u32>,
+        /// Funds seized from blacklisted accounts, held by the contract: account -> (amount, appeal window end)
+        seized: Mapping<AccountId, (Balance, Timestamp)>,
+        /// How long a blacklisted account has to appeal a seizure
+        appeal_period: Timestamp,
+        /// Current terms-of-service version
+        tos_version: u16,
+        /// Latest terms-of-service version each account accepted
+        accepted_tos: Mapping<AccountId, u16>,
+        /// Most transfers allowed between a pair of accounts: (from, to) -> limit
+        pair_transfer_limit: Mapping<(AccountId, AccountId), u32>,
+        /// Transfers made between limited pairs of accounts: (from, to) -> count
+        pair_transfer_count: Mapping<(AccountId, AccountId), u32>,
+        /// Most an account may move per activity window, or `None` for no cap
+        daily_activity_cap: Option<Balance>,
+        /// Length of the window `daily_activity_cap` applies to
+        activity_window: Timestamp,
+        /// Activity per account: account -> (window index, amount moved in it)
+        daily_activity: Mapping<AccountId, (u64, Balance)>,
+        /// Flat fee deducted from every transfer and sent to the treasury
+        flat_fee: Balance,
+        /// Fee charged on top of the flat fee, in basis points of the amount sent
+        fee_bps: u16,
+        /// Senders the owner lets move tokens without paying transfer fees
+        fee_exempt: Mapping<AccountId, ()>,
+        /// Optional behaviors the owner has switched on, keyed by feature id
+        feature_flags: Mapping<[u8; 4], bool>,
+        /// Portion of each collected fee that is burned rather than sent to the treasury, in basis points
+        fee_split_bps: u16,
+        /// Partner NFT contract whose holders get a discount on the flat fee
+        nft_contract: Option<AccountId>,
+        /// Discount on the flat fee for partner NFT holders, in basis points
+        nft_fee_discount: u32,
+        /// Account collecting transfer fees, set to the deployer
+        treasury: AccountId,
+        /// Supply swing, in basis points of the supply at window start, that auto-pauses the contract
+        breaker_threshold_bps: Option<u32>,
+        /// Length of the window supply swings are measured over
+        breaker_window: Timestamp,
+        /// Start of the current circuit-breaker window and the total supply at that time
+        breaker_window_start: (Timestamp, Balance),
+        /// Whether the circuit breaker paused the contract
+        breaker_tripped: bool,
+        /// Holders whose claimed dividends are staked instead of paid out
+        auto_reinvest: Mapping<AccountId, ()>,
+        /// Staked shares held by each account
+        staked_shares: Mapping<AccountId, Balance>,
+        /// Staked shares outstanding across all accounts
+        total_shares: Balance,
+        /// Tokens held by the contract account backing the staked shares, rewards included
+        staked_tokens: Balance,
+        /// Tokens minted into the staking pool per block, and the block they were
+        /// last minted at; `None` while stakers only earn `add_staking_rewards`
+        staking_reward_rate: Option<(Balance, BlockNumber)>,
+        /// Emits a `CompactDiff` per account instead of `Transfer`, fixed at construction
+        compact_events: bool,
+        /// Accounts whose balances do not count toward the voting supply
+        non_voting_accounts: Vec<AccountId>,
+        /// Merkle root of the recipients `transfer_to_proven` may send to, if set
+        recipient_root: Option<[u8; 32]>,
+        /// PSP22 token fees can be paid in and the fee-token units charged per token of fee
+        fee_token: Option<(AccountId, Balance)>,
+        /// Transfer fees each account has paid since its last rebate claim
+        fees_paid: Mapping<AccountId, Balance>,
+        /// Portion of paid fees minted back by `claim_rebate`, in basis points
+        rebate_bps: u32,
+        /// Portion of the unburned fee paid into the raffle prize pool, in basis points
+        raffle_fee_bps: u16,
+        /// Tokens in the raffle prize pool, held by the contract account
+        raffle_pool: Balance,
+        /// Accounts entered in the raffle draw
+        raffle_entrants: Vec<AccountId>,
+        /// Windows in which no fee is charged: (start, end), end exclusive
+        fee_holidays: Vec<(Timestamp, Timestamp)>,
+        /// Destinations sharing the unburned fee by basis-point weight, replacing the treasury while set
+        fee_destinations: Vec<(AccountId, u16)>,
+        /// Native currency paid per redeemed token once the project winds down
+        wind_down_rate: Option<Balance>,
+        /// Pending conditional transfers: id -> (from, to, value, condition id, refundable at)
+        conditional_transfers: Mapping<u64, (AccountId, AccountId, Balance, u64, Timestamp)>,
+        /// Id handed to the next conditional transfer
+        next_conditional_id: u64,
+        /// Open token-for-token swaps: id -> (creator, counterparty, escrowed amount, wanted token, wanted amount)
+        swaps: Mapping<u64, (AccountId, AccountId, Balance, AccountId, Balance)>,
+        /// Id the next swap will be created with
+        next_swap_id: u64,
+        /// Latest provenance records, slot = id % `PROVENANCE_LEN`: slot -> (id, parent, from, to, value)
+        provenance: Mapping<u64, (u64, Option<u64>, AccountId, AccountId, Balance)>,
+        /// Id handed to the next transfer with provenance
+        next_provenance_id: u64,
+        /// Disputable transfers awaiting finalization: id -> (from, to, locked amount, dispute window end)
+        disputable_transfers: Mapping<u64, (AccountId, AccountId, Balance, Timestamp)>,
+        /// Id handed to the next disputable transfer
+        next_disputable_id: u64,
+        /// Tokens locked at each recipient by open disputable transfers
+        disputed: Mapping<AccountId, Balance>,
+        /// Share class of each account's balance, set when it is minted; 0 for the default class
+        class_of: Mapping<AccountId, u8>,
+        /// Share classes whose holders may not send tokens
+        non_transferable_classes: Mapping<u8, ()>,
+        /// Share classes whose balances carry no votes
+        non_voting_classes: Mapping<u8, ()>,
+        /// Share classes tokens were ever minted into, whose voting rights are fixed
+        minted_classes: Mapping<u8, ()>,
+        /// Remaining spending budget of each account per category: (account, category) -> budget
+        category_budgets: Mapping<(AccountId, u8), Balance>,
+        /// Oracle reporting whether the conditions of conditional transfers are met
+        condition_oracle: Option<AccountId>,
+        /// Contract every balance change is mirrored to, if any
+        mirror_target: Option<AccountId>,
+        /// Balance changes of the running message not yet sent to the mirror: (account, old, new)
+        mirror_queue: Vec<(AccountId, Balance, Balance)>,
+        /// How long a conditional transfer waits for its condition before it can be refunded
+        condition_timeout: Timestamp,
+        /// Total each minter has minted over the contract's lifetime
+        lifetime_minted: Mapping<AccountId, Balance>,
+        /// Most a single minter may mint over the contract's lifetime, or `None` for no cap
+        max_lifetime_mint: Option<Balance>,
+        /// Claims left in the first-come airdrop
+        claims_remaining: u32,
+        /// Tokens minted to each first-come airdrop claimant
+        airdrop_amount: Balance,
+        /// Accounts that claimed a first-come airdrop
+        airdrop_claimed: Mapping<AccountId, ()>,
+        /// Merkle root of the `(index, account, amount)` leaves of the running Merkle airdrop
+        merkle_airdrop_root: Option<[u8; 32]>,
+        /// Tokens left for Merkle airdrop claims, held by the contract account
+        merkle_airdrop_pool: Balance,
+        /// Number of Merkle airdrops started, so each gets a fresh claimed bitmap
+        merkle_airdrop_round: u32,
+        /// Claimed Merkle airdrop leaves, 128 per word: (round, index / 128) -> bitmap
+        merkle_airdrop_claimed: Mapping<(u32, u32), u128>,
+        /// Interest accrued per token per millisecond, scaled by `INTEREST_RATE_PRECISION`,
+        /// and when interest was switched on; `None` while balances earn nothing
+        interest_rate: Option<(Balance, Timestamp)>,
+        /// When each account's interest was last minted to it
+        interest_accrued_at: Mapping<AccountId, Timestamp>,
+        /// Holding fee decayed per token per millisecond, scaled by `DEMURRAGE_RATE_PRECISION`,
+        /// the pool collecting it and when it was switched on; `None` while balances keep their value
+        demurrage: Option<(Balance, AccountId, Timestamp)>,
+        /// When each account's decay was last collected into the pool
+        demurrage_applied_at: Mapping<AccountId, Timestamp>,
+        /// Most the total supply may ever reach, or `None` while uncapped; it can only be lowered
+        cap: Option<Balance>,
+        /// Accounts whose balance changes are reported with `TaxableEvent`
+        report_enabled: Mapping<AccountId, bool>,
+        /// Pending scheduled transfers: id -> (from, to, value, execute at)
+        scheduled_transfers: Mapping<u64, (AccountId, AccountId, Balance, Timestamp)>,
+        /// Id handed to the next scheduled transfer
+        next_schedule_id: u64,
+        /// Vesting schedules of each beneficiary, escrowed at the contract account:
+        /// (beneficiary, index) -> (start, cliff, duration, amount, released)
+        vesting_schedules: Mapping<(AccountId, u32), VestingSchedule>,
+        /// Number of vesting schedules created for each beneficiary
+        vesting_schedule_count: Mapping<AccountId, u32>,
+        /// Native currency paid per token by queued redemptions, or `None` if disabled
+        redemption_rate: Option<Balance>,
+        /// Queued redemptions awaiting reserves: id -> (account, escrowed tokens)
+        redemptions: Mapping<u64, (AccountId, Balance)>,
+        /// Id handed to the next queued redemption
+        next_redemption_id: u64,
+        /// Id of the oldest redemption not yet processed
+        next_redemption_to_process: u64,
+        /// Pending operations each account is party to, oldest first: account -> [(kind, id)]
+        pending_ops: Mapping<AccountId, Vec<(PendingOpKind, u64)>>,
+        /// Native currency deposited for wrapped tokens, the only backing `withdraw` pays out of
+        wrapped_supply: Balance,
+        /// Outstanding debt owed to the protocol, repaid from incoming transfers
+        debt: Mapping<AccountId, Balance>,
+        /// Blocks spending through allowances while direct transfers continue
+        allowances_frozen: bool,
+        /// Circuit breaker halting all token movements and approvals
+        paused: bool,
+        /// Roles granted by the owner: (role, account) -> ()
+        roles: Mapping<(Role, AccountId), ()>,
+        /// Held while a message that may call other contracts runs. A mapping,
+        /// because plain fields are only written back once the message returns
+        /// and a reentrant call would not see them
+        reentrancy_lock: Mapping<(), ()>,
+        /// Confirmations a multisig proposal needs before it executes
+        proposal_threshold: u32,
+        /// How long a multisig proposal can be confirmed after it was made
+        proposal_lifetime: Timestamp,
+        /// Pending multisig proposals: id -> (action, confirmations, expiry)
+        proposals: Mapping<u64, (Action, u32, Timestamp)>,
+        /// Admins that confirmed a proposal: (id, admin) -> ()
+        proposal_confirmations: Mapping<(u64, AccountId), ()>,
+        /// Id handed to the next multisig proposal
+        next_proposal_id: u64,
+        /// Emergency freeze making every balance immutable except those of thawed accounts
+        global_freeze: bool,
+        /// Accounts exempted from the global freeze for recovery
+        thawed: Mapping<AccountId, ()>,
+        /// Id of the latest snapshot, or 0 before the first one
+        current_snapshot_id: u32,
+        /// Balances as of a snapshot, recorded on an account's first change after it:
+        /// (account, snapshot id) -> balance
+        snapshot_balances: Mapping<(AccountId, u32), Balance>,
+        /// Total supply as of a snapshot, recorded on the first supply change after it
+        snapshot_supplies: Mapping<u32, Balance>,
+        /// Account each holder delegated its voting power to
+        delegates: Mapping<AccountId, AccountId>,
+        /// Voting power of a delegate from a block on: (delegate, index) -> (block, votes)
+        vote_checkpoints: Mapping<(AccountId, u32), (BlockNumber, Balance)>,
+        /// Number of checkpoints written for each delegate
+        num_vote_checkpoints: Mapping<AccountId, u32>,
+        /// Balance held over time by each account, in balance-milliseconds, and when it was last brought up to date
+        holder_time: Mapping<AccountId, (u128, Timestamp)>,
+        /// Rewards paid by the owner to holders as of a snapshot: id -> (minimum balance, reward)
+        snapshot_rewards: Mapping<u32, (Balance, Balance)>,
+        /// Snapshot rewards already claimed: (account, snapshot id) -> ()
+        snapshot_claims: Mapping<(AccountId, u32), ()>,
+        /// Next permit nonce of each owner, bumped by every accepted permit
+        nonces: Mapping<AccountId, u64>,
+        /// Owners that only allow approvals to spenders on their allowlist
+        spender_allowlist_mode: Mapping<AccountId, ()>,
+        /// Spenders each owner allows to hold an allowance: (owner, spender) -> ()
+        approved_spenders: Mapping<(AccountId, AccountId), ()>,
+        /// Emits non-essential events; see `set_emit_events` for which are gated
+        emit_events: bool,
+        /// Non-transferable loyalty points accrued by sending tokens
+        points: Mapping<AccountId, Balance>,
+        /// Points credited per transferred token, in basis points
+        points_rate: u32,
+        /// Accounts whose funds may only move to the quarantine address
+        tainted: Mapping<AccountId, ()>,
+        /// Only permitted destination for tokens held by tainted accounts
+        quarantine: Option<AccountId>,
+        /// Ring buffer of the latest transfers: slot -> (from, to, value, block)
+        #[cfg(feature = "recent-transfers")]
+        recent_transfers: Mapping<u32, (AccountId, AccountId, Balance, BlockNumber)>,
+        /// Number of transfers recorded in `recent_transfers` so far
+        #[cfg(feature = "recent-transfers")]
+        recorded_transfers: u32,
     }
 
     /// Emitted when `value` tokens are moved from one account (`from`) to another (`to`).
@@ -51,206 +880,9530 @@ mod erc20 {
         value: Balance,
     }
 
+    /// Emitted when ownership of the contract moves from `previous_owner` to `new_owner`,
+    /// with `new_owner: None` once ownership is renounced
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous_owner: AccountId,
+        #[ink(topic)]
+        new_owner: Option<AccountId>,
+    }
+
+    /// Emitted when `account` wraps `value` native currency into as many tokens
+    #[ink(event)]
+    pub struct Deposit {
+        #[ink(topic)]
+        account: AccountId,
+        value: Balance,
+    }
+
+    /// Emitted when `account` unwraps `value` tokens back into native currency
+    #[ink(event)]
+    pub struct Withdrawal {
+        #[ink(topic)]
+        account: AccountId,
+        value: Balance,
+    }
+
+    /// Emitted when the owner switches the contract to the code at `code_hash`
+    #[ink(event)]
+    pub struct CodeUpgraded {
+        #[ink(topic)]
+        code_hash: Hash,
+    }
+
+    /// Emitted when the owner grants `role` to `account`
+    #[ink(event)]
+    pub struct RoleGranted {
+        #[ink(topic)]
+        role: Role,
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Emitted when the owner revokes `role` from `account`
+    #[ink(event)]
+    pub struct RoleRevoked {
+        #[ink(topic)]
+        role: Role,
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Emitted when `delegator` moves its voting power from one delegate to another
+    #[ink(event)]
+    pub struct DelegateChanged {
+        #[ink(topic)]
+        delegator: AccountId,
+        #[ink(topic)]
+        from_delegate: Option<AccountId>,
+        #[ink(topic)]
+        to_delegate: Option<AccountId>,
+    }
+
+    /// Emitted when the owner locks `amount` tokens in a new vesting schedule of `beneficiary`
+    #[ink(event)]
+    pub struct VestingScheduleCreated {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        index: u32,
+        amount: Balance,
+    }
+
+    /// Emitted when `beneficiary` releases `amount` vested tokens
+    #[ink(event)]
+    pub struct VestingReleased {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        amount: Balance,
+    }
+
+    /// Emitted whenever the voting power of `delegate` changes
+    #[ink(event)]
+    pub struct DelegateVotesChanged {
+        #[ink(topic)]
+        delegate: AccountId,
+        previous_balance: Balance,
+        new_balance: Balance,
+    }
+
+    /// Emitted for every balance change of an account with tax reporting enabled
+    #[ink(event)]
+    pub struct TaxableEvent {
+        #[ink(topic)]
+        account: AccountId,
+        kind: TaxableKind,
+        amount: Balance,
+        timestamp: Timestamp,
+    }
+
+    /// Emitted when the owner lowers the supply cap
+    #[ink(event)]
+    pub struct CapLowered {
+        old_cap: Option<Balance>,
+        new_cap: Balance,
+    }
+
+    /// Emitted instead of `Transfer` in compact mode, once per account whose balance changed
+    #[ink(event)]
+    pub struct CompactDiff {
+        /// Blake2x256 hash of the SCALE-encoded account
+        account_hash: [u8; 32],
+        /// Signed balance change, negative for the sender
+        delta: i128,
+    }
+
+    /// Emitted when mirroring a balance change of `account` to `target` failed
+    #[ink(event)]
+    pub struct MirrorFailed {
+        #[ink(topic)]
+        target: AccountId,
+        account: AccountId,
+    }
+
+    /// Emitted when the total supply swung too far within one window and the contract auto-paused
+    #[ink(event)]
+    pub struct CircuitBreakerTripped {
+        supply_at_window_start: Balance,
+        supply: Balance,
+    }
+
+    /// Emitted for a transfer with provenance, linking it to the transfer it continues
+    #[ink(event)]
+    pub struct Provenance {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        parent_ref: Option<u64>,
+        from: AccountId,
+        to: AccountId,
+        value: Balance,
+    }
+
+    /// Emitted when the raffle is drawn and `winner` receives the `prize` pool
+    #[ink(event)]
+    pub struct RaffleDrawn {
+        #[ink(topic)]
+        winner: AccountId,
+        prize: Balance,
+    }
+
+    /// Emitted when `account` pauses the contract
+    #[ink(event)]
+    pub struct Paused {
+        account: AccountId,
+    }
+
+    /// Emitted when `account` unpauses the contract
+    #[ink(event)]
+    pub struct Unpaused {
+        account: AccountId,
+    }
+
+    /// Emitted when an admin proposes a multisig `action`
+    #[ink(event)]
+    pub struct ProposalCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        proposer: AccountId,
+        action: Action,
+    }
+
+    /// Emitted when multisig proposal `id` reached its threshold and executed
+    #[ink(event)]
+    pub struct ProposalExecuted {
+        #[ink(topic)]
+        id: u64,
+    }
+
+    /// Emitted when `account` is put on the denylist, `until` a timestamp or permanently with `None`
+    #[ink(event)]
+    pub struct AccountFrozen {
+        #[ink(topic)]
+        account: AccountId,
+        until: Option<Timestamp>,
+    }
+
+    /// Emitted when `account` is taken off the denylist
+    #[ink(event)]
+    pub struct AccountUnfrozen {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// private helper function to hash an account for a `CompactDiff`
+    fn account_hash(account: &AccountId) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(account, &mut hash);
+        hash
+    }
+
+    /// private helper function to hash a Merkle airdrop leaf
+    fn airdrop_leaf(index: u32, account: &AccountId, amount: Balance) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(&(index, account, amount), &mut hash);
+        hash
+    }
+
+    /// private helper function to hash two Merkle nodes, in sorted order so proofs need no positions
+    fn merkle_parent(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+        let (first, second) = if a <= b { (a, b) } else { (b, a) };
+        let mut pair = [0u8; 64];
+        pair[..32].copy_from_slice(first);
+        pair[32..].copy_from_slice(second);
+        let mut hash = [0u8; 32];
+        ink_env::hash_bytes::<ink_env::hash::Blake2x256>(&pair, &mut hash);
+        hash
+    }
+
+    /// private helper function to reject an optional message this build was compiled without
+    fn ensure_compiled_in(enabled: bool) -> Result<()> {
+        if !enabled {
+            return Err(Error::FeatureNotSupported);
+        }
+        Ok(())
+    }
+
     impl Erc20 {
-        /// Creates a new ERC-20 contract with an initial supply.
+        /// Creates a new ERC-20 contract with an initial supply and token metadata.
+        ///
+        /// `name` and `symbol` can be left out for gas-minimal tokens. With
+        /// `compact_events`, balance changes emit a `CompactDiff` per account
+        /// instead of `Transfer`, for indexers that want fixed-size payloads.
         #[ink(constructor)]
-        pub fn new(initial_supply: Balance) -> Self {
+        pub fn new(
+            initial_supply: Balance,
+            name: Option<String>,
+            symbol: Option<String>,
+            decimals: u8,
+            compact_events: bool,
+        ) -> Self {
             // Initialize mapping for the contract.
             ink_lang::utils::initialize_contract(|contract: &mut Self| {
                 contract.total_supply = initial_supply;
+                contract.name = name;
+                contract.symbol = symbol;
+                contract.decimals = decimals;
+                contract.compact_events = compact_events;
                 let caller = Self::env().caller();
-                contract.balances.insert(&caller, &initial_supply);
+                contract.owner = Some(caller);
+                contract.treasury = caller;
+                contract.renounce_grace_period = DEFAULT_RENOUNCE_GRACE_PERIOD;
+                contract.activity_window = DEFAULT_ACTIVITY_WINDOW;
+                contract.condition_timeout = DEFAULT_CONDITION_TIMEOUT;
+                contract.appeal_period = DEFAULT_APPEAL_PERIOD;
+                contract.proposal_threshold = 1;
+                contract.proposal_lifetime = DEFAULT_PROPOSAL_LIFETIME;
+                contract.emit_events = true;
+                contract.storage_version = STORAGE_VERSION;
+                contract.balances.insert(caller, &initial_supply);
+                contract
+                    .holder_time
+                    .insert(caller, &(0, Self::env().block_timestamp()));
 
-                // Emit Transfer event
-                Self::env().emit_event(Transfer {
-                    from: None,
-                    to: Some(caller),
-                    value: initial_supply,
-                })
+                contract.emit_balance_change(None, Some(caller), initial_supply);
             })
         }
 
+        /// Creates a new ERC-20 contract with an initial supply, no name or symbol and 18 decimals.
+        #[ink(constructor)]
+        pub fn new_minimal(initial_supply: Balance) -> Self {
+            Self::new(initial_supply, None, None, 18, false)
+        }
+
+        /// Returns the name of the token, if set.
+        #[ink(message)]
+        pub fn token_name(&self) -> Option<String> {
+            self.name.clone()
+        }
+
+        /// Returns the symbol of the token, if set.
+        #[ink(message)]
+        pub fn token_symbol(&self) -> Option<String> {
+            self.symbol.clone()
+        }
+
+        /// Returns the number of decimals used to display amounts.
+        #[ink(message)]
+        pub fn token_decimals(&self) -> u8 {
+            self.decimals
+        }
+
+        /// Returns the version of this contract's interface.
+        #[ink(message)]
+        pub fn contract_version(&self) -> u32 {
+            CONTRACT_VERSION
+        }
+
+        /// Returns `true` if the optional message with selector `id` is compiled into this build.
+        ///
+        /// Covers `mint`, `mint_with_class`, `claim_first_come` and `claim_rebate`
+        /// (`mintable` feature), `burn` and `burn_from` (`burnable`), and `pause`
+        /// and `unpause` (`pausable`).
+        #[ink(message)]
+        pub fn supports_feature(&self, id: [u8; 4]) -> bool {
+            (cfg!(feature = "mintable") && MINTABLE_SELECTORS.contains(&id))
+                || (cfg!(feature = "burnable") && BURNABLE_SELECTORS.contains(&id))
+                || (cfg!(feature = "pausable") && PAUSABLE_SELECTORS.contains(&id))
+        }
+
         /// Returns the total token supply
         #[ink(message)]
         pub fn total_supply(&self) -> Balance {
             self.total_supply
         }
 
+        /// Returns the supply that counts toward governance quorums.
+        ///
+        /// Excludes the tokens held by the contract itself, such as escrows and the
+        /// dividend pool, the treasury's balance, and those of non-voting accounts.
+        #[ink(message)]
+        pub fn voting_supply(&self) -> Balance {
+            let mut excluded = ink_prelude::vec![self.env().account_id(), self.treasury];
+            for account in &self.non_voting_accounts {
+                if !excluded.contains(account) {
+                    excluded.push(*account);
+                }
+            }
+            let held: Balance = excluded
+                .iter()
+                .map(|account| self.balance_of_impl(account))
+                .sum();
+            self.total_supply - held
+        }
+
+        /// Returns the accounts configured as non-voting.
+        #[ink(message)]
+        pub fn non_voting_accounts(&self) -> Vec<AccountId> {
+            self.non_voting_accounts.clone()
+        }
+
+        /// Excludes `account`'s balance from the voting supply, or counts it again.
+        #[ink(message)]
+        pub fn set_non_voting(&mut self, account: AccountId, non_voting: bool) -> Result<()> {
+            self.ensure_owner()?;
+            let listed = self.non_voting_accounts.contains(&account);
+            if non_voting && !listed {
+                self.non_voting_accounts.push(account);
+            } else if !non_voting && listed {
+                self.non_voting_accounts.retain(|other| *other != account);
+            }
+            Ok(())
+        }
+
         /// Returns the account balance for the specified `owner`.
+        ///
+        /// Demurrage accrued since the balance last changed is already deducted.
         #[ink(message)]
         pub fn balance_of(&self, owner: AccountId) -> Balance {
-            self.balances.get(owner).unwrap_or_default()
+            self.balance_of_impl(&owner)
         }
 
-        /// Transfers `value` amount of tokens from the caller's account to account `to`.
+        /// Mints `value` new tokens to `to`, increasing the total supply.
+        ///
+        /// Callable by the owner and `Minter`s until the multisig needs more than
+        /// one confirmation. Minted tokens count toward `to`'s activity cap, like
+        /// tokens it sends.
         #[ink(message)]
-        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
-            let from = self.env().caller();
-            self.transfer_from_to(&from, &to, value)
+        pub fn mint(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            self.non_reentrant(|contract| contract.mint_guarded(to, value))
         }
 
-        /// private helper function to transfer `value` amount of tokens from account `from` to account `to`.
-        fn transfer_from_to(
+        /// private helper function running `mint` under the reentrancy guard
+        fn mint_guarded(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            self.ensure_role(Role::Minter)?;
+            self.ensure_no_multisig()?;
+            self.mint_checked(&self.env().caller(), &to, value)
+        }
+
+        /// private helper function to mint `value` to `to`, counting it toward `minter`'s lifetime mint cap
+        fn mint_checked(
             &mut self,
-            from: &AccountId,
+            minter: &AccountId,
             to: &AccountId,
             value: Balance,
         ) -> Result<()> {
-            let from_balance = self.balance_of_impl(from);
-            if from_balance < value {
-                return Err(Error::InsufficientBalance);
+            ensure_compiled_in(cfg!(feature = "mintable"))?;
+            self.ensure_not_paused()?;
+            self.check_mint(to, value)?;
+            let minter = *minter;
+            let minted = self.lifetime_minted_of(minter) + value;
+            if self.max_lifetime_mint.is_some_and(|cap| minted > cap) {
+                return Err(Error::LifetimeMintCapExceeded);
             }
+            self.lifetime_minted.insert(minter, &minted);
 
-            // Update from balance
-            self.balances.insert(from, &(from_balance - value));
-            let to_balance = self.balance_of_impl(to);
-            self.balances.insert(to, &(to_balance + value));
+            self.mint_impl(to, value);
+            Ok(())
+        }
 
-            // Emit Transfer event
-            self.env().emit_event(Transfer {
-                from: Some(*from),
-                to: Some(*to),
-                value,
-            });
+        /// Mints `value` new tokens of share `class` to `to`, increasing the total supply.
+        ///
+        /// An account holds a single class: minting a different class to an
+        /// account that still holds tokens is rejected with `ClassMismatch`.
+        #[ink(message)]
+        pub fn mint_with_class(&mut self, to: AccountId, value: Balance, class: u8) -> Result<()> {
+            self.non_reentrant(|contract| contract.mint_with_class_impl(to, value, class))
+        }
 
+        /// private helper function running `mint_with_class` under the reentrancy guard
+        fn mint_with_class_impl(&mut self, to: AccountId, value: Balance, class: u8) -> Result<()> {
+            if self.class_of(to) != class && self.balance_of_impl(&to) > 0 {
+                return Err(Error::ClassMismatch);
+            }
+            let previous = self.voting_delegate(&to);
+            self.mint_guarded(to, value)?;
+            if class == 0 {
+                self.class_of.remove(to);
+            } else {
+                self.class_of.insert(to, &class);
+            }
+            self.minted_classes.insert(class, &());
+            // The mint counted under the old class; move the votes if the new one differs
+            self.move_votes(
+                previous,
+                self.voting_delegate(&to),
+                self.stored_balance(&to),
+            );
             Ok(())
         }
 
-        /// private helper function to get the balance of an account
-        #[inline]
-        fn balance_of_impl(&self, owner: &AccountId) -> Balance {
-            self.balances.get(owner).unwrap_or_default()
+        /// Returns the share class of `account`'s balance, 0 for the default class.
+        #[ink(message)]
+        pub fn class_of(&self, account: AccountId) -> u8 {
+            self.class_of.get(account).unwrap_or_default()
         }
 
-        /// Function to authorize `spender` to withdraw from your account multiple times, up to the `value` amount.
+        /// Lets holders of share `class` send tokens, or locks the class in place.
         #[ink(message)]
-        pub fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()> {
-            let owner = self.env().caller();
-            self.allowances.insert((&owner, &spender), &value);
-
-            // Emit Approval event
-            self.env().emit_event(Approval {
-                owner,
-                spender,
-                value,
-            });
+        pub fn set_class_transferable(&mut self, class: u8, transferable: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if transferable {
+                self.non_transferable_classes.remove(class);
+            } else {
+                self.non_transferable_classes.insert(class, &());
+            }
             Ok(())
         }
 
-        /// Returns the amount which `spender` is still allowed to withdraw from `owner`.
+        /// Returns `true` if balances of share `class` carry votes.
         #[ink(message)]
-        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
-            self.allowance_impl(&owner, &spender)
+        pub fn is_class_voting(&self, class: u8) -> bool {
+            !self.non_voting_classes.contains(class)
         }
 
-        /// private helper function to get the allowance of an account
-        #[inline]
-        fn allowance_impl(&self, owner: &AccountId, spender: &AccountId) -> Balance {
-            self.allowances.get((owner, spender)).unwrap_or_default()
+        /// Gives share `class` voting rights or takes them away, before any tokens are minted into it.
+        ///
+        /// Delegated votes are checkpointed as balances change, so the rights of a
+        /// class already held are fixed; the default class 0 always votes.
+        #[ink(message)]
+        pub fn set_class_voting(&mut self, class: u8, voting: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if class == 0 || self.minted_classes.contains(class) {
+                return Err(Error::ClassInUse);
+            }
+            if voting {
+                self.non_voting_classes.remove(class);
+            } else {
+                self.non_voting_classes.insert(class, &());
+            }
+            Ok(())
         }
 
-        /// Transfers tokens on the behalf of the `from` account to the `to` account.
+        /// private helper function to get the delegate `account`'s balance votes for, if its class votes
+        fn voting_delegate(&self, account: &AccountId) -> Option<AccountId> {
+            if self.non_voting_classes.contains(self.class_of(*account)) {
+                return None;
+            }
+            self.delegates.get(account)
+        }
+
+        /// private helper function to check that `from`'s share class may be transferred
+        fn check_class(&self, from: &AccountId) -> Result<()> {
+            if self.non_transferable_classes.contains(self.class_of(*from)) {
+                return Err(Error::ClassNotTransferable);
+            }
+            Ok(())
+        }
+
+        /// private helper function to validate minting `value` new tokens to `to`
+        fn check_mint(&self, to: &AccountId, value: Balance) -> Result<()> {
+            if *to == self.env().account_id() {
+                return Err(Error::TransferToContract);
+            }
+            self.ensure_thawed(to)?;
+            if *to == AccountId::from(ZERO_ADDRESS) {
+                return Err(Error::ZeroAddress);
+            }
+            if self.is_blacklisted_impl(to) {
+                return Err(Error::Blacklisted);
+            }
+            self.check_activity(to, value)?;
+            let total_supply = self
+                .total_supply
+                .checked_add(value)
+                .ok_or(Error::Overflow)?;
+            if self.cap.is_some_and(|cap| total_supply > cap) {
+                return Err(Error::CapExceeded);
+            }
+            // Every balance must stay accountable against the dividends per share
+            total_supply
+                .checked_mul(self.dividends_per_share)
+                .ok_or(Error::Overflow)?;
+            Ok(())
+        }
+
+        /// private helper function to create `value` new tokens for `to` and emit the `Transfer`.
+        ///
+        /// Callers are responsible for having validated the mint with `check_mint`.
+        fn mint_impl(&mut self, to: &AccountId, value: Balance) {
+            self.apply_demurrage(to);
+            self.checkpoint_balance(to);
+            self.accrue_holder_time(to);
+            self.settle_dividends(to);
+            self.accrue_interest(to);
+            let balance = self.balance_of_impl(to);
+            self.balances.insert(to, &(balance + value));
+            self.reset_dividend_debt(to);
+            self.mirror_balance_change(to, balance, balance + value);
+            self.move_votes(None, self.voting_delegate(to), value);
+            self.start_breaker_window();
+            self.checkpoint_supply();
+            self.total_supply += value;
+            self.record_activity(to, value);
+
+            self.emit_balance_change(None, Some(*to), value);
+            self.report_taxable(to, TaxableKind::Mint, value);
+            self.check_circuit_breaker();
+        }
+
+        /// Returns `(claims remaining, amount per claim)` of the first-come airdrop.
         #[ink(message)]
-        pub fn transfer_from(
+        pub fn first_come_airdrop(&self) -> (u32, Balance) {
+            (self.claims_remaining, self.airdrop_amount)
+        }
+
+        /// Opens a first-come airdrop minting `amount` to each of the next `claims` claimants.
+        ///
+        /// Accounts that claimed an earlier airdrop cannot claim again.
+        #[ink(message)]
+        pub fn set_first_come_airdrop(&mut self, claims: u32, amount: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            self.claims_remaining = claims;
+            self.airdrop_amount = amount;
+            Ok(())
+        }
+
+        /// Mints the airdrop amount to the caller while claims remain, once per account.
+        ///
+        /// `amount` must match the configured amount per claim.
+        #[ink(message)]
+        pub fn claim_first_come(&mut self, amount: Balance) -> Result<()> {
+            self.non_reentrant(|contract| contract.claim_first_come_impl(amount))
+        }
+
+        /// private helper function running `claim_first_come` under the reentrancy guard
+        fn claim_first_come_impl(&mut self, amount: Balance) -> Result<()> {
+            ensure_compiled_in(cfg!(feature = "mintable"))?;
+            self.ensure_not_paused()?;
+            if self.claims_remaining == 0 {
+                return Err(Error::AirdropExhausted);
+            }
+            if amount != self.airdrop_amount {
+                return Err(Error::RewardMismatch);
+            }
+            let caller = self.env().caller();
+            if self.airdrop_claimed.contains(caller) {
+                return Err(Error::AlreadyClaimed);
+            }
+            self.check_mint(&caller, amount)?;
+
+            self.claims_remaining -= 1;
+            self.airdrop_claimed.insert(caller, &());
+            self.mint_impl(&caller, amount);
+            Ok(())
+        }
+
+        /// Returns `(root, tokens left)` of the running Merkle airdrop, if any.
+        #[ink(message)]
+        pub fn merkle_airdrop(&self) -> Option<([u8; 32], Balance)> {
+            self.merkle_airdrop_root
+                .map(|root| (root, self.merkle_airdrop_pool))
+        }
+
+        /// Starts a Merkle airdrop of `pool` of the owner's tokens to the leaves under `root`.
+        ///
+        /// Leaves are the Blake2x256 hash of the SCALE-encoded `(index, account,
+        /// amount)`, and parents hash their children in sorted order, as for
+        /// `transfer_to_proven`. Tokens left from a previous airdrop go back to the owner.
+        #[ink(message)]
+        pub fn start_merkle_airdrop(&mut self, root: [u8; 32], pool: Balance) -> Result<()> {
+            self.non_reentrant(|contract| contract.start_merkle_airdrop_impl(root, pool))
+        }
+
+        /// private helper function running `start_merkle_airdrop` under the reentrancy guard
+        fn start_merkle_airdrop_impl(&mut self, root: [u8; 32], pool: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            let owner = self.env().caller();
+            let escrow = self.env().account_id();
+            let leftover = self.merkle_airdrop_pool;
+            let available = self.balance_of_impl(&owner) + leftover;
+            if available < pool {
+                return Err(Error::InsufficientBalance {
+                    required: pool,
+                    available,
+                });
+            }
+            if self.unlocked_balance_of(&owner) + leftover < pool {
+                return Err(Error::AmountLocked);
+            }
+
+            self.end_merkle_airdrop_impl()?;
+            self.move_tokens(&owner, &escrow, pool);
+            self.merkle_airdrop_root = Some(root);
+            self.merkle_airdrop_pool = pool;
+            self.merkle_airdrop_round += 1;
+            Ok(())
+        }
+
+        /// Ends the running Merkle airdrop and returns the tokens left to the owner.
+        ///
+        /// Returns how many tokens were returned.
+        #[ink(message)]
+        pub fn end_merkle_airdrop(&mut self) -> Result<Balance> {
+            self.non_reentrant(|contract| contract.end_merkle_airdrop_impl())
+        }
+
+        /// private helper function running `end_merkle_airdrop` under the reentrancy guard
+        fn end_merkle_airdrop_impl(&mut self) -> Result<Balance> {
+            self.ensure_owner()?;
+            let leftover = self.merkle_airdrop_pool;
+            if leftover > 0 {
+                let owner = self.env().caller();
+                let escrow = self.env().account_id();
+                self.move_tokens(&escrow, &owner, leftover);
+            }
+            self.merkle_airdrop_root = None;
+            self.merkle_airdrop_pool = 0;
+            Ok(leftover)
+        }
+
+        /// Returns `true` if leaf `index` of the running Merkle airdrop has been claimed.
+        #[ink(message)]
+        pub fn is_airdrop_claimed(&self, index: u32) -> bool {
+            let word = self
+                .merkle_airdrop_claimed
+                .get((self.merkle_airdrop_round, index / 128))
+                .unwrap_or_default();
+            word & (1 << (index % 128)) != 0
+        }
+
+        /// Claims the caller's `amount` at leaf `index` of the Merkle airdrop, once per leaf.
+        ///
+        /// `proof` lists the sibling hashes from the leaf up to the root.
+        #[ink(message)]
+        pub fn claim_airdrop(
             &mut self,
-            from: AccountId,
-            to: AccountId,
-            value: Balance,
+            index: u32,
+            amount: Balance,
+            proof: Vec<[u8; 32]>,
+        ) -> Result<()> {
+            self.non_reentrant(|contract| contract.claim_airdrop_impl(index, amount, proof))
+        }
+
+        /// private helper function running `claim_airdrop` under the reentrancy guard
+        fn claim_airdrop_impl(
+            &mut self,
+            index: u32,
+            amount: Balance,
+            proof: Vec<[u8; 32]>,
         ) -> Result<()> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let root = self.merkle_airdrop_root.ok_or(Error::InvalidAirdropProof)?;
+            let computed = proof
+                .iter()
+                .fold(airdrop_leaf(index, &caller, amount), |node, sibling| {
+                    merkle_parent(&node, sibling)
+                });
+            if computed != root {
+                return Err(Error::InvalidAirdropProof);
+            }
+            if self.is_airdrop_claimed(index) {
+                return Err(Error::AlreadyClaimed);
+            }
+            if self.merkle_airdrop_pool < amount {
+                return Err(Error::AirdropExhausted);
+            }
+
+            let escrow = self.env().account_id();
+            self.release_escrow(&escrow, &caller, amount)?;
+            self.merkle_airdrop_pool -= amount;
+            let key = (self.merkle_airdrop_round, index / 128);
+            let word = self.merkle_airdrop_claimed.get(key).unwrap_or_default();
+            self.merkle_airdrop_claimed
+                .insert(key, &(word | 1 << (index % 128)));
+            Ok(())
+        }
+
+        /// Returns how much `minter` has minted over the contract's lifetime.
+        #[ink(message)]
+        pub fn lifetime_minted_of(&self, minter: AccountId) -> Balance {
+            self.lifetime_minted.get(minter).unwrap_or_default()
+        }
+
+        /// Caps how much a single minter may mint over the contract's lifetime, or `None` to lift it.
+        #[ink(message)]
+        pub fn set_max_lifetime_mint(&mut self, cap: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            self.max_lifetime_mint = cap;
+            Ok(())
+        }
+
+        /// Returns the most the total supply may ever reach, or `None` while uncapped.
+        #[ink(message)]
+        pub fn cap(&self) -> Option<Balance> {
+            self.cap
+        }
+
+        /// Lowers the supply cap to `new_cap`, which can never be raised again.
+        ///
+        /// `new_cap` must be below the current cap and no less than the total supply.
+        #[ink(message)]
+        pub fn lower_cap(&mut self, new_cap: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            if self.cap.is_some_and(|cap| new_cap >= cap) || new_cap < self.total_supply {
+                return Err(Error::InvalidCap);
+            }
+            let old_cap = self.cap.replace(new_cap);
+            self.env().emit_event(CapLowered { old_cap, new_cap });
+            Ok(())
+        }
+
+        /// Returns the interest rate per millisecond, scaled by `INTEREST_RATE_PRECISION`, if interest is on.
+        #[ink(message)]
+        pub fn interest_rate(&self) -> Option<Balance> {
+            self.interest_rate.map(|(rate, _)| rate)
+        }
+
+        /// Turns balances into savings accruing `rate` per millisecond, or stops interest with `None`.
+        ///
+        /// `rate` is scaled by `INTEREST_RATE_PRECISION`. Interest is minted to an
+        /// account whenever its balance changes, so it compounds on every
+        /// interaction; a new rate also applies to interest not minted yet.
+        /// Only time since interest was switched on earns anything.
+        #[ink(message)]
+        pub fn set_interest_rate(&mut self, rate: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            self.interest_rate = match rate {
+                Some(0) => return Err(Error::InvalidInterestRate),
+                Some(rate) => {
+                    let since = self
+                        .interest_rate
+                        .map_or(self.env().block_timestamp(), |(_, since)| since);
+                    Some((rate, since))
+                }
+                None => None,
+            };
+            Ok(())
+        }
+
+        /// Returns the interest `account` has accrued since it was last minted to it.
+        ///
+        /// The interest is minted on the account's next balance change, as far as the supply cap allows.
+        #[ink(message)]
+        pub fn accrued_interest(&self, account: AccountId) -> Balance {
+            let (rate, since) = match self.interest_rate {
+                Some(interest_rate) if account != self.env().account_id() => interest_rate,
+                _ => return 0,
+            };
+            let last_accrual = self.interest_accrued_at.get(account).unwrap_or_default();
+            let elapsed = Balance::from(
+                self.env()
+                    .block_timestamp()
+                    .saturating_sub(last_accrual.max(since)),
+            );
+            let per_token = rate.saturating_mul(elapsed);
+            let balance = self.balance_of_impl(&account);
+            let interest = balance
+                .checked_mul(per_token)
+                .map(|scaled| scaled / INTEREST_RATE_PRECISION)
+                .unwrap_or_else(|| (balance / INTEREST_RATE_PRECISION).saturating_mul(per_token));
+            let headroom = self.cap.unwrap_or(Balance::MAX) - self.total_supply;
+            interest.min(headroom)
+        }
+
+        /// private helper function to mint `account`'s accrued interest before its balance changes
+        fn accrue_interest(&mut self, account: &AccountId) {
+            let interest = self.accrued_interest(*account);
+            self.interest_accrued_at
+                .insert(account, &self.env().block_timestamp());
+            if interest == 0 {
+                return;
+            }
+            let balance = self.balance_of_impl(account);
+            self.balances.insert(account, &(balance + interest));
+            self.move_votes(None, self.voting_delegate(account), interest);
+            self.checkpoint_supply();
+            self.total_supply += interest;
+            self.emit_balance_change(None, Some(*account), interest);
+            self.report_taxable(account, TaxableKind::Mint, interest);
+        }
+
+        /// Returns `(rate per millisecond, pool)` of the demurrage, if balances decay.
+        #[ink(message)]
+        pub fn demurrage(&self) -> Option<(Balance, AccountId)> {
+            self.demurrage.map(|(rate, pool, _)| (rate, pool))
+        }
+
+        /// Makes balances decay at `rate` per millisecond into `pool`, or stops the decay with `None`.
+        ///
+        /// `rate` is scaled by `DEMURRAGE_RATE_PRECISION`. Decay is collected into
+        /// the pool whenever a balance changes, and the pool itself does not decay.
+        /// A new rate or pool also applies to decay not collected yet; only time
+        /// since demurrage was switched on decays anything.
+        #[ink(message)]
+        pub fn set_demurrage(&mut self, config: Option<(Balance, AccountId)>) -> Result<()> {
+            self.ensure_owner()?;
+            self.demurrage = match config {
+                Some((rate, pool)) => {
+                    if rate == 0 || pool == self.env().account_id() {
+                        return Err(Error::InvalidDemurrage);
+                    }
+                    let since = self
+                        .demurrage
+                        .map_or(self.env().block_timestamp(), |(_, _, since)| since);
+                    Some((rate, pool, since))
+                }
+                None => None,
+            };
+            Ok(())
+        }
+
+        /// Returns `account`'s balance after the decay accrued since it was last collected.
+        ///
+        /// Matches `balance_of`; the stored balance only catches up on the next balance change.
+        #[ink(message)]
+        pub fn decayed_balance(&self, account: AccountId) -> Balance {
+            self.balance_of_impl(&account)
+        }
+
+        /// private helper function to get the decay `account` accrued on its stored balance
+        fn pending_demurrage(&self, account: &AccountId, stored: Balance) -> Balance {
+            let (rate, since) = match self.demurrage {
+                Some((rate, pool, since))
+                    if *account != pool && *account != self.env().account_id() =>
+                {
+                    (rate, since)
+                }
+                _ => return 0,
+            };
+            let last_applied = self.demurrage_applied_at.get(account).unwrap_or_default();
+            let elapsed = Balance::from(
+                self.env()
+                    .block_timestamp()
+                    .saturating_sub(last_applied.max(since)),
+            );
+            let per_token = rate.saturating_mul(elapsed);
+            let decay = stored
+                .checked_mul(per_token)
+                .map(|scaled| scaled / DEMURRAGE_RATE_PRECISION)
+                .unwrap_or_else(|| (stored / DEMURRAGE_RATE_PRECISION).saturating_mul(per_token));
+            decay.min(stored)
+        }
+
+        /// private helper function to move `account`'s accrued decay into the pool before its balance changes
+        fn apply_demurrage(&mut self, account: &AccountId) {
+            let decay = self.pending_demurrage(account, self.stored_balance(account));
+            self.demurrage_applied_at
+                .insert(account, &self.env().block_timestamp());
+            if let Some((_, pool, _)) = self.demurrage.filter(|_| decay > 0) {
+                self.write_balances(account, &pool, decay);
+                self.emit_transfer(account, &pool, decay);
+            }
+        }
+
+        /// Burns `value` of the caller's tokens, reducing the total supply.
+        #[ink(message)]
+        pub fn burn(&mut self, value: Balance) -> Result<()> {
+            self.non_reentrant(|contract| contract.burn_guarded(value))
+        }
+
+        /// private helper function running `burn` under the reentrancy guard
+        fn burn_guarded(&mut self, value: Balance) -> Result<()> {
+            ensure_compiled_in(cfg!(feature = "burnable"))?;
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            self.ensure_spendable(&caller, value)?;
+            self.burn_impl(&caller, value);
+            Ok(())
+        }
+
+        /// Burns `value` of `from`'s tokens, spending the caller's allowance like `transfer_from`.
+        ///
+        /// An allowance of `Balance::MAX` is unlimited and is not decremented.
+        #[ink(message)]
+        pub fn burn_from(&mut self, from: AccountId, value: Balance) -> Result<()> {
+            self.non_reentrant(|contract| contract.burn_from_impl(from, value))
+        }
+
+        /// private helper function running `burn_from` under the reentrancy guard
+        fn burn_from_impl(&mut self, from: AccountId, value: Balance) -> Result<()> {
+            ensure_compiled_in(cfg!(feature = "burnable"))?;
+            self.ensure_not_paused()?;
+            if self.allowances_frozen {
+                return Err(Error::AllowancesFrozen);
+            }
+
             let caller = self.env().caller();
+            if self.is_blacklisted_impl(&caller) {
+                return Err(Error::Blacklisted);
+            }
             let allowance = self.allowance_impl(&from, &caller);
+            if allowance < value {
+                return Err(Error::InsufficientAllowance {
+                    required: value,
+                    available: allowance,
+                });
+            }
+            self.ensure_spendable(&from, value)?;
+
+            self.burn_impl(&from, value);
+            if allowance != Balance::MAX {
+                self.write_allowance(&from, &caller, allowance - value);
+                if self.emit_events {
+                    self.env().emit_event(Approval {
+                        owner: from,
+                        spender: caller,
+                        value: allowance - value,
+                    });
+                }
+            }
+            Ok(())
+        }
+
+        /// private helper function to destroy `value` of `from`'s tokens and emit the `Transfer`.
+        ///
+        /// Callers are responsible for having checked that `from` holds `value`.
+        fn burn_impl(&mut self, from: &AccountId, value: Balance) {
+            self.write_burn(from, value);
+            self.emit_burn(from, value);
+        }
+
+        /// private helper function to destroy `value` of `from`'s tokens without emitting.
+        fn write_burn(&mut self, from: &AccountId, value: Balance) {
+            self.apply_demurrage(from);
+            self.checkpoint_balance(from);
+            self.accrue_holder_time(from);
+            self.settle_dividends(from);
+            self.accrue_interest(from);
+            let balance = self.balance_of_impl(from);
+            self.balances.insert(from, &(balance - value));
+            self.reset_dividend_debt(from);
+            self.start_breaker_window();
+            self.checkpoint_supply();
+            self.total_supply -= value;
+            self.mirror_balance_change(from, balance, balance - value);
+            self.move_votes(self.voting_delegate(from), None, value);
+            self.check_circuit_breaker();
+        }
+
+        /// private helper function to emit the `Transfer` for a burn already written.
+        fn emit_burn(&self, from: &AccountId, value: Balance) {
+            self.emit_balance_change(Some(*from), None, value);
+            self.report_taxable(from, TaxableKind::Burn, value);
+        }
+
+        /// Transfers `value` amount of tokens from the caller's account to account `to`.
+        #[ink(message)]
+        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            self.non_reentrant(|contract| contract.transfer_impl(to, value))
+        }
+
+        /// private helper function running `transfer` under the reentrancy guard
+        fn transfer_impl(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            let from = self.env().caller();
+            self.transfer_from_to(&from, &to, value)
+        }
+
+        /// Transfers `value` tokens to `to` after checking `proof` shows `to` is in the recipient root.
+        ///
+        /// Leaves are the Blake2x256 hash of the SCALE-encoded account, and each
+        /// parent hashes its two children in sorted order. Plain `transfer` is not gated.
+        #[ink(message)]
+        pub fn transfer_to_proven(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            proof: Vec<[u8; 32]>,
+        ) -> Result<()> {
+            self.non_reentrant(|contract| contract.transfer_to_proven_impl(to, value, proof))
+        }
+
+        /// private helper function running `transfer_to_proven` under the reentrancy guard
+        fn transfer_to_proven_impl(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            proof: Vec<[u8; 32]>,
+        ) -> Result<()> {
+            let root = self.recipient_root.ok_or(Error::RecipientNotProven)?;
+            let computed = proof.iter().fold(account_hash(&to), |node, sibling| {
+                merkle_parent(&node, sibling)
+            });
+            if computed != root {
+                return Err(Error::RecipientNotProven);
+            }
+            let from = self.env().caller();
+            self.transfer_from_to(&from, &to, value)
+        }
+
+        /// Returns the Merkle root of the recipients `transfer_to_proven` may send to.
+        #[ink(message)]
+        pub fn recipient_root(&self) -> Option<[u8; 32]> {
+            self.recipient_root
+        }
+
+        /// Sets the Merkle root of the recipients `transfer_to_proven` may send to, or clears it.
+        #[ink(message)]
+        pub fn set_recipient_root(&mut self, root: Option<[u8; 32]>) -> Result<()> {
+            self.ensure_owner()?;
+            self.recipient_root = root;
+            Ok(())
+        }
+
+        /// Returns the PSP22 token fees can be paid in and the fee-token units charged per token of fee.
+        #[ink(message)]
+        pub fn fee_token(&self) -> Option<(AccountId, Balance)> {
+            self.fee_token
+        }
+
+        /// Lets fees be paid in the PSP22 `token` at `rate` units per token of fee, or stops it with `None`.
+        #[ink(message)]
+        pub fn set_fee_token(&mut self, fee_token: Option<(AccountId, Balance)>) -> Result<()> {
+            self.ensure_owner()?;
+            self.fee_token = fee_token;
+            Ok(())
+        }
+
+        /// Transfers the full `value` to `to` and pays the flat fee in the fee token instead.
+        ///
+        /// The fee is pulled from the caller's fee-token balance to the treasury with
+        /// `PSP22::transfer_from`, so the caller must have approved this contract first.
+        /// The transfer is validated before the fee is pulled; if the pull fails nothing moves.
+        /// As with `transfer`, values below the flat fee are rejected.
+        #[ink(message)]
+        pub fn transfer_fee_in_token(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            self.non_reentrant(|contract| contract.transfer_fee_in_token_impl(to, value))
+        }
+
+        /// private helper function running `transfer_fee_in_token` under the reentrancy guard
+        fn transfer_fee_in_token_impl(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            let from = self.env().caller();
+            let fee = self.transfer_fee(&from, value)?;
+            self.check_transfer(&from, &to, value)?;
+            self.ensure_spendable(&from, value)?;
+
+            if fee > 0 {
+                let (token, rate) = self.fee_token.ok_or(Error::FeeTokenPaymentFailed)?;
+                let token_fee = fee.checked_mul(rate).ok_or(Error::Overflow)?;
+                let result = build_call::<Environment>()
+                    .call_type(Call::new().callee(token).gas_limit(0))
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(PSP22_TRANSFER_FROM_SELECTOR))
+                            .push_arg(from)
+                            .push_arg(self.treasury)
+                            .push_arg(token_fee)
+                            .push_arg(Vec::<u8>::new()),
+                    )
+                    .returns::<core::result::Result<(), u8>>()
+                    .fire();
+                if !matches!(result, Ok(Ok(()))) {
+                    return Err(Error::FeeTokenPaymentFailed);
+                }
+            }
+            self.transfer_with_fee(&from, &to, value, 0)
+        }
+
+        /// Transfers `value` tokens to `to`, failing unless at least `min_received` arrive after the fee.
+        ///
+        /// Tokens paying down the recipient's debt count as received.
+        #[ink(message)]
+        pub fn transfer_min_received(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            min_received: Balance,
+        ) -> Result<()> {
+            self.non_reentrant(|contract| {
+                contract.transfer_min_received_impl(to, value, min_received)
+            })
+        }
+
+        /// private helper function running `transfer_min_received` under the reentrancy guard
+        fn transfer_min_received_impl(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            min_received: Balance,
+        ) -> Result<()> {
+            let from = self.env().caller();
+            let fee = self.transfer_fee(&from, value)?;
+            if value.saturating_sub(fee) < min_received {
+                return Err(Error::SlippageExceeded);
+            }
+            self.transfer_from_to(&from, &to, value)
+        }
+
+        /// Transfers `value` tokens to `to` with `data`, letting a receiving contract refuse them.
+        ///
+        /// When `to` is a contract, its `PSP22Receiver::before_received` is called
+        /// first with the caller, `value` and `data`; if it rejects or traps,
+        /// nothing moves and `TransferRejected` is returned. Transfers to plain
+        /// accounts behave like `transfer`.
+        #[ink(message)]
+        pub fn transfer_with_data(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            self.non_reentrant(|contract| contract.transfer_with_data_impl(to, value, data))
+        }
+
+        /// private helper function running `transfer_with_data` under the reentrancy guard
+        fn transfer_with_data_impl(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            let from = self.env().caller();
+            self.check_transfer(&from, &to, value)?;
+            self.ensure_spendable(&from, value)?;
+
+            if self.env().is_contract(&to) {
+                let result = build_call::<Environment>()
+                    .call_type(Call::new().callee(to).gas_limit(0))
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(PSP22_BEFORE_RECEIVED_SELECTOR))
+                            .push_arg(from)
+                            .push_arg(from)
+                            .push_arg(value)
+                            .push_arg(data),
+                    )
+                    .returns::<core::result::Result<(), u8>>()
+                    .fire();
+                if !matches!(result, Ok(Ok(()))) {
+                    return Err(Error::TransferRejected);
+                }
+            }
+            // The callback ran before anything moved, so the transfer is validated again
+            self.transfer_from_to(&from, &to, value)
+        }
+
+        /// Transfers `value` tokens to `to` as the next link of a provenance chain and returns its id.
+        ///
+        /// `parent_ref` names the earlier transfer this one continues, if any.
+        /// Only the last `PROVENANCE_LEN` records stay queryable with
+        /// `provenance_of`; the `Provenance` events keep the full chain.
+        #[ink(message)]
+        pub fn transfer_with_provenance(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            parent_ref: Option<u64>,
+        ) -> Result<u64> {
+            self.non_reentrant(|contract| {
+                contract.transfer_with_provenance_impl(to, value, parent_ref)
+            })
+        }
+
+        /// private helper function running `transfer_with_provenance` under the reentrancy guard
+        fn transfer_with_provenance_impl(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            parent_ref: Option<u64>,
+        ) -> Result<u64> {
+            let from = self.env().caller();
+            if parent_ref.is_some_and(|parent| parent >= self.next_provenance_id) {
+                return Err(Error::UnknownProvenanceRef);
+            }
+            self.transfer_from_to(&from, &to, value)?;
+            let id = self.next_provenance_id;
+            self.next_provenance_id += 1;
+            self.provenance
+                .insert(id % PROVENANCE_LEN, &(id, parent_ref, from, to, value));
+            self.env().emit_event(Provenance {
+                id,
+                parent_ref,
+                from,
+                to,
+                value,
+            });
+            Ok(id)
+        }
+
+        /// Returns the provenance record of transfer `id` as `(parent, from, to, value)`,
+        /// or `None` if it is unknown or was overwritten by newer records.
+        #[ink(message)]
+        pub fn provenance_of(
+            &self,
+            id: u64,
+        ) -> Option<(Option<u64>, AccountId, AccountId, Balance)> {
+            self.provenance
+                .get(id % PROVENANCE_LEN)
+                .filter(|record| record.0 == id)
+                .map(|(_, parent, from, to, value)| (parent, from, to, value))
+        }
+
+        /// Returns what `account` may still spend with `transfer_categorized` in `category`.
+        #[ink(message)]
+        pub fn category_budget(&self, account: AccountId, category: u8) -> Balance {
+            self.category_budgets
+                .get((account, category))
+                .unwrap_or_default()
+        }
+
+        /// Sets the remaining budget `account` may spend in `category`, replacing the old one.
+        #[ink(message)]
+        pub fn set_category_budget(
+            &mut self,
+            account: AccountId,
+            category: u8,
+            amount: Balance,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            if amount == 0 {
+                self.category_budgets.remove((account, category));
+            } else {
+                self.category_budgets.insert((account, category), &amount);
+            }
+            Ok(())
+        }
+
+        /// Transfers `value` tokens to `to`, spending them from the caller's budget in `category`.
+        ///
+        /// Budgets only restrict categorized transfers; plain transfers are unaffected.
+        #[ink(message)]
+        pub fn transfer_categorized(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            category: u8,
+        ) -> Result<()> {
+            self.non_reentrant(|contract| contract.transfer_categorized_impl(to, value, category))
+        }
+
+        /// private helper function running `transfer_categorized` under the reentrancy guard
+        fn transfer_categorized_impl(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            category: u8,
+        ) -> Result<()> {
+            let from = self.env().caller();
+            let budget = self.category_budget(from, category);
+            if value > budget {
+                return Err(Error::CategoryBudgetExceeded);
+            }
+            self.transfer_from_to(&from, &to, value)?;
+            self.category_budgets
+                .insert((from, category), &(budget - value));
+            Ok(())
+        }
+
+        /// Transfers `value` tokens to `to` and forwards the attached native value to them as a tip.
+        ///
+        /// The tokens move first through the full transfer checks and the tip is
+        /// sent last. Returning an error does not revert state, so the tip is
+        /// refunded to the caller whenever it was not forwarded.
+        #[ink(message, payable)]
+        pub fn transfer_with_tip(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            let from = self.env().caller();
+            let tip = self.env().transferred_value();
+
+            let forwarded = self
+                .non_reentrant(|contract| contract.transfer_with_tip_impl(&from, &to, value, tip));
+            if let Err(error) = forwarded {
+                if tip > 0 {
+                    self.env()
+                        .transfer(from, tip)
+                        .map_err(|_| Error::NativeTransferFailed)?;
+                }
+                return Err(error);
+            }
+            Ok(())
+        }
+
+        /// private helper function running `transfer_with_tip` under the reentrancy guard
+        fn transfer_with_tip_impl(
+            &mut self,
+            from: &AccountId,
+            to: &AccountId,
+            value: Balance,
+            tip: Balance,
+        ) -> Result<()> {
+            self.transfer_from_to(from, to, value)?;
+            self.env()
+                .transfer(*to, tip)
+                .map_err(|_| Error::NativeTransferFailed)
+        }
+
+        /// Escrows `value` of the caller's tokens to be sent to `to` once `execute_at` is reached.
+        ///
+        /// The transfer is validated and counted toward the caller's activity now;
+        /// the flat fee is taken from the escrow on execution. Returns the schedule id.
+        #[ink(message)]
+        pub fn schedule_transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            execute_at: Timestamp,
+        ) -> Result<u64> {
+            self.non_reentrant(|contract| contract.schedule_transfer_impl(to, value, execute_at))
+        }
+
+        /// private helper function running `schedule_transfer` under the reentrancy guard
+        fn schedule_transfer_impl(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            execute_at: Timestamp,
+        ) -> Result<u64> {
+            let from = self.env().caller();
+            self.check_transfer(&from, &to, value)?;
+            self.ensure_spendable(&from, value)?;
+
+            let escrow = self.env().account_id();
+            self.move_tokens(&from, &escrow, value);
+            self.record_activity(&from, value);
+
+            let id = self.next_schedule_id;
+            self.scheduled_transfers
+                .insert(id, &(from, to, value, execute_at));
+            self.track_pending_op(PendingOpKind::ScheduledTransfer, id, &[from, to]);
+            self.next_schedule_id += 1;
+            Ok(id)
+        }
+
+        /// Returns the pending scheduled transfer `id` as `(from, to, value, execute_at)`.
+        #[ink(message)]
+        pub fn scheduled_transfer(
+            &self,
+            id: u64,
+        ) -> Option<(AccountId, AccountId, Balance, Timestamp)> {
+            self.scheduled_transfers.get(id)
+        }
+
+        /// Completes the scheduled transfer `id` once it is due; callable by anyone.
+        ///
+        /// The recipient is checked again at execution time.
+        #[ink(message)]
+        pub fn execute_scheduled(&mut self, id: u64) -> Result<()> {
+            self.non_reentrant(|contract| contract.execute_scheduled_impl(id))
+        }
+
+        /// private helper function running `execute_scheduled` under the reentrancy guard
+        fn execute_scheduled_impl(&mut self, id: u64) -> Result<()> {
+            let (from, to, value, execute_at) = self
+                .scheduled_transfers
+                .get(id)
+                .ok_or(Error::UnknownSchedule)?;
+            if self.env().block_timestamp() < execute_at {
+                return Err(Error::NotYetDue);
+            }
+            self.release_escrow(&from, &to, value)?;
+            self.scheduled_transfers.remove(id);
+            self.untrack_pending_op(PendingOpKind::ScheduledTransfer, id, &[from, to]);
+            Ok(())
+        }
+
+        /// Escrows `value` of the caller's tokens until the oracle reports `condition_id` as met.
+        ///
+        /// Once met, anyone can release the tokens to `to` with `settle_condition`; if
+        /// the condition timeout passes first, they can be refunded with
+        /// `refund_conditional`. Returns the escrow id.
+        #[ink(message)]
+        pub fn conditional_transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            condition_id: u64,
+        ) -> Result<u64> {
+            self.non_reentrant(|contract| {
+                contract.conditional_transfer_impl(to, value, condition_id)
+            })
+        }
+
+        /// private helper function running `conditional_transfer` under the reentrancy guard
+        fn conditional_transfer_impl(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            condition_id: u64,
+        ) -> Result<u64> {
+            let from = self.env().caller();
+            self.check_transfer(&from, &to, value)?;
+            self.ensure_spendable(&from, value)?;
+
+            let escrow = self.env().account_id();
+            self.move_tokens(&from, &escrow, value);
+            self.record_activity(&from, value);
+
+            let id = self.next_conditional_id;
+            let refundable_at = self.env().block_timestamp() + self.condition_timeout;
+            self.conditional_transfers
+                .insert(id, &(from, to, value, condition_id, refundable_at));
+            self.track_pending_op(PendingOpKind::ConditionalTransfer, id, &[from, to]);
+            self.next_conditional_id += 1;
+            Ok(id)
+        }
+
+        /// Returns the pending conditional transfer `escrow_id` as
+        /// `(from, to, value, condition_id, refundable_at)`.
+        #[ink(message)]
+        pub fn conditional_transfer_of(
+            &self,
+            escrow_id: u64,
+        ) -> Option<(AccountId, AccountId, Balance, u64, Timestamp)> {
+            self.conditional_transfers.get(escrow_id)
+        }
+
+        /// Releases the conditional transfer `escrow_id` if the oracle reports its condition as met.
+        #[ink(message)]
+        pub fn settle_condition(&mut self, escrow_id: u64) -> Result<()> {
+            self.non_reentrant(|contract| contract.settle_condition_impl(escrow_id))
+        }
+
+        /// private helper function running `settle_condition` under the reentrancy guard
+        fn settle_condition_impl(&mut self, escrow_id: u64) -> Result<()> {
+            let (from, to, value, condition_id, _) = self
+                .conditional_transfers
+                .get(escrow_id)
+                .ok_or(Error::UnknownSchedule)?;
+            let oracle = self.condition_oracle.ok_or(Error::OracleCallFailed)?;
+            let met = build_call::<Environment>()
+                .call_type(Call::new().callee(oracle).gas_limit(0))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(CONDITION_IS_MET_SELECTOR))
+                        .push_arg(condition_id),
+                )
+                .returns::<bool>()
+                .fire()
+                .map_err(|_| Error::OracleCallFailed)?;
+            if !met {
+                return Err(Error::ConditionNotMet);
+            }
+
+            self.release_escrow(&from, &to, value)?;
+            self.conditional_transfers.remove(escrow_id);
+            self.untrack_pending_op(PendingOpKind::ConditionalTransfer, escrow_id, &[from, to]);
+            Ok(())
+        }
+
+        /// Refunds the conditional transfer `escrow_id` to its sender once its timeout has passed.
+        #[ink(message)]
+        pub fn refund_conditional(&mut self, escrow_id: u64) -> Result<()> {
+            self.non_reentrant(|contract| contract.refund_conditional_impl(escrow_id))
+        }
+
+        /// private helper function running `refund_conditional` under the reentrancy guard
+        fn refund_conditional_impl(&mut self, escrow_id: u64) -> Result<()> {
+            self.ensure_not_paused()?;
+            let (from, to, value, _, refundable_at) = self
+                .conditional_transfers
+                .get(escrow_id)
+                .ok_or(Error::UnknownSchedule)?;
+            if self.env().block_timestamp() < refundable_at {
+                return Err(Error::NotYetDue);
+            }
+
+            self.conditional_transfers.remove(escrow_id);
+            self.untrack_pending_op(PendingOpKind::ConditionalTransfer, escrow_id, &[from, to]);
+            let escrow = self.env().account_id();
+            self.move_tokens(&escrow, &from, value);
+            Ok(())
+        }
+
+        /// Sets the oracle queried by `settle_condition`.
+        #[ink(message)]
+        pub fn set_condition_oracle(&mut self, oracle: Option<AccountId>) -> Result<()> {
+            self.ensure_owner()?;
+            self.condition_oracle = oracle;
+            Ok(())
+        }
+
+        /// Sets how long new conditional transfers wait before they can be refunded.
+        #[ink(message)]
+        pub fn set_condition_timeout(&mut self, timeout: Timestamp) -> Result<()> {
+            self.ensure_owner()?;
+            self.condition_timeout = timeout;
+            Ok(())
+        }
+
+        /// Returns the contract every balance change is mirrored to, if any.
+        #[ink(message)]
+        pub fn mirror_target(&self) -> Option<AccountId> {
+            self.mirror_target
+        }
+
+        /// Mirrors every balance change to `target`'s `record_balance_change`, or stops with `None`.
+        ///
+        /// Mirroring is best-effort: a failed call emits `MirrorFailed` and never
+        /// blocks the transfer, mint or burn that caused it.
+        #[ink(message)]
+        pub fn set_mirror_target(&mut self, target: Option<AccountId>) -> Result<()> {
+            self.ensure_owner()?;
+            self.mirror_target = target;
+            Ok(())
+        }
+
+        /// private helper function to queue a balance change of `account` for the mirror contract
+        ///
+        /// The change is sent by `flush_mirror_queue` once the message has written
+        /// all storage and emitted its events, following checks-effects-interactions.
+        fn mirror_balance_change(&mut self, account: &AccountId, old: Balance, new: Balance) {
+            if self.mirror_target.is_some() {
+                self.mirror_queue.push((*account, old, new));
+            }
+        }
+
+        /// private helper function to report the queued balance changes to the mirror contract
+        fn flush_mirror_queue(&mut self) {
+            let queue = core::mem::take(&mut self.mirror_queue);
+            let Some(target) = self.mirror_target else {
+                return;
+            };
+            for (account, old, new) in queue {
+                let result = build_call::<Environment>()
+                    .call_type(Call::new().callee(target).gas_limit(0))
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(RECORD_BALANCE_CHANGE_SELECTOR))
+                            .push_arg(account)
+                            .push_arg(old)
+                            .push_arg(new),
+                    )
+                    .returns::<()>()
+                    .fire();
+                if result.is_err() {
+                    self.env().emit_event(MirrorFailed { target, account });
+                }
+            }
+        }
+
+        /// private helper function to deliver `value` escrowed for `from` to `to`, minus the flat fee
+        fn release_escrow(
+            &mut self,
+            from: &AccountId,
+            to: &AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            let escrow = self.env().account_id();
+            self.move_with_fee(&escrow, from, to, value)?;
+            Ok(())
+        }
+
+        /// private helper function to deliver `value` held by `source` to `to`, minus the flat
+        /// fee charged to `payer`, and return the amount delivered.
+        ///
+        /// Callers are responsible for having checked that `source` holds `value`.
+        fn move_with_fee(
+            &mut self,
+            source: &AccountId,
+            payer: &AccountId,
+            to: &AccountId,
+            value: Balance,
+        ) -> Result<Balance> {
+            self.check_transfer(source, to, value)?;
+            let fee = self.transfer_fee(payer, value)?;
+            if value < fee {
+                return Err(Error::AmountBelowFee);
+            }
+
+            let (burned, routed) = self.split_fee(fee);
+            if burned > 0 {
+                self.burn_impl(source, burned);
+            }
+            for (destination, share) in self.fee_legs(routed) {
+                self.move_tokens(source, &destination, share);
+            }
+            self.raffle_pool += self.raffle_share(routed);
+            self.move_tokens(source, to, value - fee);
+            self.accrue_points(payer, value);
+            self.record_fee_paid(payer, fee);
+            Ok(value - fee)
+        }
+
+        /// Cancels the pending scheduled transfer `id` and refunds its escrow to the scheduler.
+        #[ink(message)]
+        pub fn cancel_scheduled(&mut self, id: u64) -> Result<()> {
+            self.non_reentrant(|contract| contract.cancel_scheduled_impl(id))
+        }
+
+        /// private helper function running `cancel_scheduled` under the reentrancy guard
+        fn cancel_scheduled_impl(&mut self, id: u64) -> Result<()> {
+            self.ensure_not_paused()?;
+            let (from, to, value, _) = self
+                .scheduled_transfers
+                .get(id)
+                .ok_or(Error::UnknownSchedule)?;
+            if self.env().caller() != from {
+                return Err(Error::NotScheduler);
+            }
+
+            self.scheduled_transfers.remove(id);
+            self.untrack_pending_op(PendingOpKind::ScheduledTransfer, id, &[from, to]);
+            let escrow = self.env().account_id();
+            self.move_tokens(&escrow, &from, value);
+            Ok(())
+        }
+
+        /// Locks `amount` of the owner's tokens for `beneficiary`, vesting linearly
+        /// over `duration` milliseconds from `start`. Returns the schedule's index.
+        ///
+        /// Nothing vests before the `cliff` has passed since `start`; the part
+        /// vested by then becomes releasable at once. A beneficiary can hold
+        /// any number of schedules.
+        #[ink(message)]
+        pub fn create_vesting_schedule(
+            &mut self,
+            beneficiary: AccountId,
+            start: Timestamp,
+            cliff: Timestamp,
+            duration: Timestamp,
+            amount: Balance,
+        ) -> Result<u32> {
+            self.non_reentrant(|contract| {
+                contract.create_vesting_schedule_impl(beneficiary, start, cliff, duration, amount)
+            })
+        }
+
+        /// private helper function running `create_vesting_schedule` under the reentrancy guard
+        fn create_vesting_schedule_impl(
+            &mut self,
+            beneficiary: AccountId,
+            start: Timestamp,
+            cliff: Timestamp,
+            duration: Timestamp,
+            amount: Balance,
+        ) -> Result<u32> {
+            self.ensure_owner()?;
+            if amount == 0 || duration == 0 || cliff > duration {
+                return Err(Error::InvalidVestingSchedule);
+            }
+            let owner = self.env().caller();
+            self.check_transfer(&owner, &beneficiary, amount)?;
+            self.ensure_spendable(&owner, amount)?;
+
+            let escrow = self.env().account_id();
+            self.move_tokens(&owner, &escrow, amount);
+            let index = self
+                .vesting_schedule_count
+                .get(beneficiary)
+                .unwrap_or_default();
+            self.vesting_schedules
+                .insert((beneficiary, index), &(start, cliff, duration, amount, 0));
+            self.vesting_schedule_count
+                .insert(beneficiary, &(index + 1));
+            self.env().emit_event(VestingScheduleCreated {
+                beneficiary,
+                index,
+                amount,
+            });
+            Ok(index)
+        }
+
+        /// Returns vesting schedule `index` of `beneficiary` as
+        /// `(start, cliff, duration, amount, released)`.
+        #[ink(message)]
+        pub fn vesting_schedule(
+            &self,
+            beneficiary: AccountId,
+            index: u32,
+        ) -> Option<VestingSchedule> {
+            self.vesting_schedules.get((beneficiary, index))
+        }
+
+        /// Returns the vested tokens of all `beneficiary`'s schedules not released yet.
+        #[ink(message)]
+        pub fn releasable_amount(&self, beneficiary: AccountId) -> Balance {
+            (0..self
+                .vesting_schedule_count
+                .get(beneficiary)
+                .unwrap_or_default())
+                .filter_map(|index| self.vesting_schedules.get((beneficiary, index)))
+                .map(|schedule| self.vested_amount(schedule) - schedule.4)
+                .sum()
+        }
+
+        /// Sends the caller every vested token of its schedules not released yet.
+        #[ink(message)]
+        pub fn release(&mut self) -> Result<Balance> {
+            self.non_reentrant(|contract| contract.release_impl())
+        }
+
+        /// private helper function running `release` under the reentrancy guard
+        fn release_impl(&mut self) -> Result<Balance> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let mut total = 0;
+            for index in 0..self.vesting_schedule_count.get(caller).unwrap_or_default() {
+                if let Some(mut schedule) = self.vesting_schedules.get((caller, index)) {
+                    let vested = self.vested_amount(schedule);
+                    total += vested - schedule.4;
+                    schedule.4 = vested;
+                    self.vesting_schedules.insert((caller, index), &schedule);
+                }
+            }
+            if total == 0 {
+                return Err(Error::NothingVested);
+            }
+
+            let escrow = self.env().account_id();
+            self.move_tokens(&escrow, &caller, total);
+            self.env().emit_event(VestingReleased {
+                beneficiary: caller,
+                amount: total,
+            });
+            Ok(total)
+        }
+
+        /// private helper function to get the tokens of a vesting schedule vested by now
+        fn vested_amount(
+            &self,
+            (start, cliff, duration, amount, _): (
+                Timestamp,
+                Timestamp,
+                Timestamp,
+                Balance,
+                Balance,
+            ),
+        ) -> Balance {
+            let elapsed = self.env().block_timestamp().saturating_sub(start);
+            if elapsed < cliff {
+                return 0;
+            }
+            if elapsed >= duration {
+                return amount;
+            }
+            // Split the amount so `amount * elapsed` cannot overflow
+            let (elapsed, duration) = (Balance::from(elapsed), Balance::from(duration));
+            amount / duration * elapsed + amount % duration * elapsed / duration
+        }
+
+        /// Escrows `give_amount` of the caller's tokens in exchange for `want_amount`
+        /// of the PSP22 token `want_token` from `counterparty`. Returns the swap id.
+        ///
+        /// The counterparty settles with `accept_swap` after approving this
+        /// contract to spend their `want_token`; the flat fee is taken from the escrow then.
+        #[ink(message)]
+        pub fn create_swap(
+            &mut self,
+            counterparty: AccountId,
+            give_amount: Balance,
+            want_token: AccountId,
+            want_amount: Balance,
+        ) -> Result<u64> {
+            self.non_reentrant(|contract| {
+                contract.create_swap_impl(counterparty, give_amount, want_token, want_amount)
+            })
+        }
+
+        /// private helper function running `create_swap` under the reentrancy guard
+        fn create_swap_impl(
+            &mut self,
+            counterparty: AccountId,
+            give_amount: Balance,
+            want_token: AccountId,
+            want_amount: Balance,
+        ) -> Result<u64> {
+            let from = self.env().caller();
+            self.check_transfer(&from, &counterparty, give_amount)?;
+            self.ensure_spendable(&from, give_amount)?;
+
+            let escrow = self.env().account_id();
+            self.move_tokens(&from, &escrow, give_amount);
+            self.record_activity(&from, give_amount);
+
+            let id = self.next_swap_id;
+            self.swaps.insert(
+                id,
+                &(from, counterparty, give_amount, want_token, want_amount),
+            );
+            self.track_pending_op(PendingOpKind::Swap, id, &[from, counterparty]);
+            self.next_swap_id += 1;
+            Ok(id)
+        }
+
+        /// Returns the open swap `id` as
+        /// `(creator, counterparty, escrowed amount, wanted token, wanted amount)`.
+        #[ink(message)]
+        pub fn swap(&self, id: u64) -> Option<(AccountId, AccountId, Balance, AccountId, Balance)> {
+            self.swaps.get(id)
+        }
+
+        /// Settles the swap `id`: the counterparty's wanted tokens go to the creator
+        /// and the escrowed tokens to the counterparty, or neither does.
+        ///
+        /// Only the counterparty can accept.
+        #[ink(message)]
+        pub fn accept_swap(&mut self, id: u64) -> Result<()> {
+            self.non_reentrant(|contract| contract.accept_swap_impl(id))
+        }
+
+        /// private helper function running `accept_swap` under the reentrancy guard
+        fn accept_swap_impl(&mut self, id: u64) -> Result<()> {
+            let (creator, counterparty, give_amount, want_token, want_amount) =
+                self.swaps.get(id).ok_or(Error::SwapNotFound)?;
+            if self.env().caller() != counterparty {
+                return Err(Error::WrongCounterparty);
+            }
+            // Our side must be known to succeed before the other token moves
+            let escrow = self.env().account_id();
+            self.check_transfer(&escrow, &counterparty, give_amount)?;
+
+            let result = build_call::<Environment>()
+                .call_type(Call::new().callee(want_token).gas_limit(0))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(PSP22_TRANSFER_FROM_SELECTOR))
+                        .push_arg(counterparty)
+                        .push_arg(creator)
+                        .push_arg(want_amount)
+                        .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<core::result::Result<(), u8>>()
+                .fire();
+            if !matches!(result, Ok(Ok(()))) {
+                return Err(Error::SwapCallFailed);
+            }
+
+            self.swaps.remove(id);
+            self.untrack_pending_op(PendingOpKind::Swap, id, &[creator, counterparty]);
+            self.release_escrow(&creator, &counterparty, give_amount)
+        }
+
+        /// Cancels the open swap `id` and refunds its escrow to the creator.
+        #[ink(message)]
+        pub fn cancel_swap(&mut self, id: u64) -> Result<()> {
+            self.non_reentrant(|contract| contract.cancel_swap_impl(id))
+        }
+
+        /// private helper function running `cancel_swap` under the reentrancy guard
+        fn cancel_swap_impl(&mut self, id: u64) -> Result<()> {
+            self.ensure_not_paused()?;
+            let (creator, counterparty, give_amount, _, _) =
+                self.swaps.get(id).ok_or(Error::SwapNotFound)?;
+            if self.env().caller() != creator {
+                return Err(Error::WrongCounterparty);
+            }
+
+            self.swaps.remove(id);
+            self.untrack_pending_op(PendingOpKind::Swap, id, &[creator, counterparty]);
+            let escrow = self.env().account_id();
+            self.move_tokens(&escrow, &creator, give_amount);
+            Ok(())
+        }
+
+        /// Returns `(from, to, locked amount, dispute window end)` of disputable transfer `id`, if open.
+        #[ink(message)]
+        pub fn disputable_transfer(
+            &self,
+            id: u64,
+        ) -> Option<(AccountId, AccountId, Balance, Timestamp)> {
+            self.disputable_transfers.get(id)
+        }
+
+        /// Transfers `value` tokens to `to` but keeps them locked there for
+        /// `dispute_secs` seconds, during which the caller may reverse the transfer.
+        /// Returns the id of the disputable transfer.
+        ///
+        /// The flat fee is taken up front and is not refunded by a reversal.
+        /// Disputable transfers do not pay down the recipient's debt.
+        #[ink(message)]
+        pub fn transfer_disputable(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            dispute_secs: u64,
+        ) -> Result<u64> {
+            self.non_reentrant(|contract| {
+                contract.transfer_disputable_impl(to, value, dispute_secs)
+            })
+        }
+
+        /// private helper function running `transfer_disputable` under the reentrancy guard
+        fn transfer_disputable_impl(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            dispute_secs: u64,
+        ) -> Result<u64> {
+            let from = self.env().caller();
+            self.ensure_spendable(&from, value)?;
+
+            let received = self.move_with_fee(&from, &from, &to, value)?;
+            let id = self.next_disputable_id;
+            let dispute_ends = self
+                .env()
+                .block_timestamp()
+                .saturating_add(dispute_secs.saturating_mul(1000));
+            self.disputable_transfers
+                .insert(id, &(from, to, received, dispute_ends));
+            self.track_pending_op(PendingOpKind::DisputableTransfer, id, &[from, to]);
+            self.next_disputable_id += 1;
+            self.disputed
+                .insert(to, &(self.disputed.get(to).unwrap_or_default() + received));
+            Ok(id)
+        }
+
+        /// Claws back the tokens of disputable transfer `id` while its dispute window is open.
+        ///
+        /// Only the sender may reverse, and only in full: the disputed tokens
+        /// cannot leave the recipient while the window is open, so a shortfall,
+        /// e.g. from demurrage, fails with `InsufficientBalance`.
+        #[ink(message)]
+        pub fn reverse_disputable(&mut self, id: u64) -> Result<()> {
+            self.non_reentrant(|contract| contract.reverse_disputable_impl(id))
+        }
+
+        /// private helper function running `reverse_disputable` under the reentrancy guard
+        fn reverse_disputable_impl(&mut self, id: u64) -> Result<()> {
+            self.ensure_not_paused()?;
+            let (from, to, amount, dispute_ends) = self
+                .disputable_transfers
+                .get(id)
+                .ok_or(Error::DisputeNotFound)?;
+            if self.env().caller() != from {
+                return Err(Error::NotSender);
+            }
+            if self.env().block_timestamp() >= dispute_ends {
+                return Err(Error::DisputeWindowClosed);
+            }
+
+            self.ensure_balance(&to, amount)?;
+
+            self.unlock_disputed(id, &from, &to, amount);
+            self.move_tokens(&to, &from, amount);
+            Ok(())
+        }
+
+        /// Releases the tokens of disputable transfer `id` to its recipient once the
+        /// dispute window has closed. Anyone may finalize.
+        #[ink(message)]
+        pub fn finalize_disputable(&mut self, id: u64) -> Result<()> {
+            let (from, to, amount, dispute_ends) = self
+                .disputable_transfers
+                .get(id)
+                .ok_or(Error::DisputeNotFound)?;
+            if self.env().block_timestamp() < dispute_ends {
+                return Err(Error::DisputeWindowOpen);
+            }
+            self.unlock_disputed(id, &from, &to, amount);
+            Ok(())
+        }
+
+        /// private helper function to close disputable transfer `id` from `from` and unlock its `amount` at `to`
+        fn unlock_disputed(&mut self, id: u64, from: &AccountId, to: &AccountId, amount: Balance) {
+            self.disputable_transfers.remove(id);
+            self.untrack_pending_op(PendingOpKind::DisputableTransfer, id, &[*from, *to]);
+            let remaining = self.disputed.get(to).unwrap_or_default() - amount;
+            if remaining == 0 {
+                self.disputed.remove(to);
+            } else {
+                self.disputed.insert(to, &remaining);
+            }
+        }
+
+        /// Returns every pending scheduled transfer, conditional transfer, queued
+        /// redemption, open swap and disputable transfer `account` is party to, in that order.
+        ///
+        /// Conditional transfers list the time they become refundable and
+        /// disputable transfers the time they can be finalized. Only the
+        /// operations indexed under `account` are read.
+        #[ink(message)]
+        pub fn pending_operations(&self, account: AccountId) -> Vec<PendingOp> {
+            let mut operations: Vec<PendingOp> = self
+                .pending_ops
+                .get(account)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|(kind, id)| {
+                    let (amount, executable_at) = match kind {
+                        PendingOpKind::ScheduledTransfer => self
+                            .scheduled_transfers
+                            .get(id)
+                            .map(|(_, _, amount, execute_at)| (amount, Some(execute_at)))?,
+                        PendingOpKind::ConditionalTransfer => {
+                            self.conditional_transfers.get(id).map(
+                                |(_, _, amount, _, refundable_at)| (amount, Some(refundable_at)),
+                            )?
+                        }
+                        PendingOpKind::Redemption => {
+                            self.redemptions.get(id).map(|(_, amount)| (amount, None))?
+                        }
+                        PendingOpKind::Swap => self
+                            .swaps
+                            .get(id)
+                            .map(|(_, _, amount, _, _)| (amount, None))?,
+                        PendingOpKind::DisputableTransfer => self
+                            .disputable_transfers
+                            .get(id)
+                            .map(|(_, _, amount, dispute_ends)| (amount, Some(dispute_ends)))?,
+                    };
+                    Some(PendingOp {
+                        kind,
+                        id,
+                        amount,
+                        executable_at,
+                    })
+                })
+                .collect();
+            // Stable, so each kind stays oldest first
+            operations.sort_by_key(|operation| operation.kind as u8);
+            operations
+        }
+
+        /// private helper function to index operation `id` of `kind` under each of `parties`
+        fn track_pending_op(&mut self, kind: PendingOpKind, id: u64, parties: &[AccountId]) {
+            for (index, party) in parties.iter().enumerate() {
+                if parties[..index].contains(party) {
+                    continue;
+                }
+                let mut operations = self.pending_ops.get(party).unwrap_or_default();
+                operations.push((kind, id));
+                self.pending_ops.insert(party, &operations);
+            }
+        }
+
+        /// private helper function to drop operation `id` of `kind` from the index of each of `parties`
+        fn untrack_pending_op(&mut self, kind: PendingOpKind, id: u64, parties: &[AccountId]) {
+            for party in parties {
+                let mut operations = self.pending_ops.get(party).unwrap_or_default();
+                operations.retain(|operation| *operation != (kind, id));
+                if operations.is_empty() {
+                    self.pending_ops.remove(party);
+                } else {
+                    self.pending_ops.insert(party, &operations);
+                }
+            }
+        }
+
+        /// private helper function to transfer `value` amount of tokens from account `from` to account `to`.
+        fn transfer_from_to(
+            &mut self,
+            from: &AccountId,
+            to: &AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            let fee = self.transfer_fee(from, value)?;
+            self.transfer_with_fee(from, to, value, fee)
+        }
+
+        /// private helper function to transfer `value` tokens from `from` to `to`, taking `fee` off the top.
+        ///
+        /// Follows checks-effects-interactions: everything is validated first, then
+        /// all storage is written, and the `Transfer` events are emitted last, so
+        /// nothing observing them can see a half-applied transfer.
+        fn transfer_with_fee(
+            &mut self,
+            from: &AccountId,
+            to: &AccountId,
+            value: Balance,
+            fee: Balance,
+        ) -> Result<()> {
+            // Checks
+            self.check_transfer(from, to, value)?;
+            self.ensure_spendable(from, value)?;
+
+            // Split the value into its legs: the flat fee off the top, then the
+            // recipient's debt paid down, as long as there is an owner left to collect it
+            let (burned, routed) = self.split_fee(fee);
+            let received = value - fee;
+            let debt = self.debt_of(*to);
+            let repaid = match self.owner {
+                Some(_) => debt.min(received),
+                None => 0,
+            };
+
+            let mut legs = self.fee_legs(routed);
+            if let Some(owner) = self.owner.filter(|_| repaid > 0) {
+                legs.push((owner, repaid));
+            }
+            if repaid == 0 || received > repaid {
+                legs.push((*to, received - repaid));
+            }
+
+            // Every account credited must be able to hold all of its legs
+            for (leg_to, _) in legs.iter().filter(|(leg_to, _)| leg_to != from) {
+                let credited: Balance = legs
+                    .iter()
+                    .filter(|(other, _)| other == leg_to)
+                    .map(|(_, leg_value)| leg_value)
+                    .sum();
+                self.balance_of_impl(leg_to)
+                    .checked_add(credited)
+                    .ok_or(Error::Overflow)?;
+            }
+
+            // Effects
+            if repaid > 0 {
+                self.debt.insert(to, &(debt - repaid));
+            }
+            if burned > 0 {
+                self.write_burn(from, burned);
+            }
+            for (leg_to, leg_value) in &legs {
+                self.write_balances(from, leg_to, *leg_value);
+            }
+            self.raffle_pool += self.raffle_share(routed);
+
+            // Credit loyalty points and paid fees to the sender
+            self.accrue_points(from, value);
+            self.record_fee_paid(from, fee);
+            self.record_activity(from, value);
+            self.record_pair_transfer(from, to);
+
+            // Events last
+            if burned > 0 {
+                self.emit_burn(from, burned);
+            }
+            for (leg_to, leg_value) in &legs {
+                self.emit_transfer(from, leg_to, *leg_value);
+            }
+
+            Ok(())
+        }
+
+        /// private helper function to move `value` tokens between balances and emit the `Transfer`.
+        ///
+        /// Callers are responsible for having checked that `from` holds `value`.
+        fn move_tokens(&mut self, from: &AccountId, to: &AccountId, value: Balance) {
+            self.write_balances(from, to, value);
+            self.emit_transfer(from, to, value);
+        }
+
+        /// private helper function to move `value` tokens between balances without emitting.
+        fn write_balances(&mut self, from: &AccountId, to: &AccountId, value: Balance) {
+            // Collect decay first so every bookkeeping step sees the decayed balances
+            self.apply_demurrage(from);
+            self.apply_demurrage(to);
+            self.checkpoint_balance(from);
+            self.checkpoint_balance(to);
+            self.accrue_holder_time(from);
+            self.accrue_holder_time(to);
+
+            // Settle accrued dividends against the old balances
+            self.settle_dividends(from);
+            self.settle_dividends(to);
+            self.accrue_interest(from);
+            self.accrue_interest(to);
+
+            // Update from balance
+            let from_balance = self.balance_of_impl(from);
+            self.balances.insert(from, &(from_balance - value));
+            let to_balance = self.balance_of_impl(to);
+            self.balances.insert(to, &(to_balance + value));
+
+            // Start accruing against the new balances
+            self.reset_dividend_debt(from);
+            self.reset_dividend_debt(to);
+            self.mirror_balance_change(from, from_balance, from_balance - value);
+            self.mirror_balance_change(to, to_balance, to_balance + value);
+            self.move_votes(self.voting_delegate(from), self.voting_delegate(to), value);
+
+            #[cfg(feature = "recent-transfers")]
+            self.record_transfer(from, to, value);
+        }
+
+        /// Records the current balances as a new snapshot and returns its id.
+        ///
+        /// Nothing is written up front; each account's balance is checkpointed
+        /// lazily the first time it changes after the snapshot.
+        #[ink(message)]
+        pub fn snapshot(&mut self) -> Result<u32> {
+            self.ensure_owner()?;
+            self.current_snapshot_id += 1;
+            Ok(self.current_snapshot_id)
+        }
+
+        /// Returns the balance `owner` had when snapshot `id` was taken.
+        ///
+        /// Ids that were never taken read as the live balance.
+        #[ink(message)]
+        pub fn balance_of_at(&self, owner: AccountId, id: u32) -> Balance {
+            (id.max(1)..=self.current_snapshot_id)
+                .find_map(|snapshot| self.snapshot_balances.get((owner, snapshot)))
+                .unwrap_or_else(|| self.balance_of_impl(&owner))
+        }
+
+        /// Returns the total supply when snapshot `id` was taken.
+        ///
+        /// Ids that were never taken read as the live supply.
+        #[ink(message)]
+        pub fn total_supply_at(&self, id: u32) -> Balance {
+            (id.max(1)..=self.current_snapshot_id)
+                .find_map(|snapshot| self.snapshot_supplies.get(snapshot))
+                .unwrap_or(self.total_supply)
+        }
+
+        /// Returns `(minimum balance, reward)` of the claim offered for snapshot `id`, if any.
+        #[ink(message)]
+        pub fn snapshot_reward(&self, id: u32) -> Option<(Balance, Balance)> {
+            self.snapshot_rewards.get(id)
+        }
+
+        /// Offers `reward` of the owner's tokens to each account that held at
+        /// least `min_balance` at snapshot `id`, or `None` to withdraw the offer.
+        ///
+        /// `id` must name a snapshot already taken; a future id would pay out
+        /// against balances that are still changing.
+        #[ink(message)]
+        pub fn set_snapshot_reward(
+            &mut self,
+            id: u32,
+            reward: Option<(Balance, Balance)>,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            if id == 0 || id > self.current_snapshot_id {
+                return Err(Error::UnknownSnapshot);
+            }
+            match reward {
+                Some(reward) => self.snapshot_rewards.insert(id, &reward),
+                None => self.snapshot_rewards.remove(id),
+            }
+            Ok(())
+        }
+
+        /// Returns `true` if `account` has claimed the reward of snapshot `id`.
+        #[ink(message)]
+        pub fn has_claimed_at(&self, account: AccountId, id: u32) -> bool {
+            self.snapshot_claims.contains((account, id))
+        }
+
+        /// Claims the reward offered to holders as of snapshot `id`, once per account.
+        ///
+        /// `reward` must match the offered reward, so a claim never pays out an
+        /// amount the caller did not expect. The reward is paid from the owner's balance.
+        #[ink(message)]
+        pub fn claim_if_held_at(&mut self, id: u32, reward: Balance) -> Result<()> {
+            self.non_reentrant(|contract| contract.claim_if_held_at_impl(id, reward))
+        }
+
+        /// private helper function running `claim_if_held_at` under the reentrancy guard
+        fn claim_if_held_at_impl(&mut self, id: u32, reward: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            let (min_balance, offered) = self.snapshot_rewards.get(id).ok_or(Error::NotEligible)?;
+            if reward != offered {
+                return Err(Error::RewardMismatch);
+            }
+            if self.has_claimed_at(caller, id) {
+                return Err(Error::AlreadyClaimed);
+            }
+            if self.balance_of_at(caller, id) < min_balance {
+                return Err(Error::NotEligible);
+            }
+
+            // Offers lapse once ownership is renounced
+            let owner = self.owner.ok_or(Error::NotEligible)?;
+            self.transfer_from_to(&owner, &caller, reward)?;
+            self.snapshot_claims.insert((caller, id), &());
+            Ok(())
+        }
+
+        /// private helper function to record `account`'s balance for the current snapshot before it changes
+        fn checkpoint_balance(&mut self, account: &AccountId) {
+            let snapshot = self.current_snapshot_id;
+            if snapshot > 0 && !self.snapshot_balances.contains((account, snapshot)) {
+                self.snapshot_balances
+                    .insert((account, snapshot), &self.balance_of_impl(account));
+            }
+        }
+
+        /// private helper function to record the total supply for the current snapshot before it changes
+        fn checkpoint_supply(&mut self) {
+            let snapshot = self.current_snapshot_id;
+            if snapshot > 0 && !self.snapshot_supplies.contains(snapshot) {
+                self.snapshot_supplies.insert(snapshot, &self.total_supply);
+            }
+        }
+
+        /// Returns the account `owner` delegated its voting power to, if any.
+        #[ink(message)]
+        pub fn delegates(&self, owner: AccountId) -> Option<AccountId> {
+            self.delegates.get(owner)
+        }
+
+        /// Delegates the caller's voting power to `to`, which may be the caller itself.
+        ///
+        /// Tokens carry no votes until their holder delegates, and none at all in a
+        /// non-voting share class. The delegate then follows every later change of
+        /// the caller's balance.
+        #[ink(message)]
+        pub fn delegate(&mut self, to: AccountId) -> Result<()> {
+            let caller = self.env().caller();
+            let previous = self.delegates.get(caller);
+            let previous_voting = self.voting_delegate(&caller);
+            self.delegates.insert(caller, &to);
+            self.env().emit_event(DelegateChanged {
+                delegator: caller,
+                from_delegate: previous,
+                to_delegate: Some(to),
+            });
+            self.move_votes(
+                previous_voting,
+                self.voting_delegate(&caller),
+                self.stored_balance(&caller),
+            );
+            Ok(())
+        }
+
+        /// Returns the current voting power of `account`.
+        #[ink(message)]
+        pub fn get_votes(&self, account: AccountId) -> Balance {
+            match self.num_vote_checkpoints.get(account).unwrap_or_default() {
+                0 => 0,
+                count => self.vote_checkpoint(&account, count - 1).1,
+            }
+        }
+
+        /// Returns the voting power `account` had at the end of `block_number`.
+        ///
+        /// Only finished blocks can be looked up, so the result cannot change anymore.
+        #[ink(message)]
+        pub fn get_past_votes(
+            &self,
+            account: AccountId,
+            block_number: BlockNumber,
+        ) -> Result<Balance> {
+            if block_number >= self.env().block_number() {
+                return Err(Error::FutureLookup);
+            }
+            // Binary search for the last checkpoint written at or before the block
+            let (mut low, mut high) = (
+                0,
+                self.num_vote_checkpoints.get(account).unwrap_or_default(),
+            );
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if self.vote_checkpoint(&account, mid).0 > block_number {
+                    high = mid;
+                } else {
+                    low = mid + 1;
+                }
+            }
+            Ok(match low {
+                0 => 0,
+                index => self.vote_checkpoint(&account, index - 1).1,
+            })
+        }
+
+        /// private helper function to read checkpoint `index` of `account`
+        fn vote_checkpoint(&self, account: &AccountId, index: u32) -> (BlockNumber, Balance) {
+            self.vote_checkpoints
+                .get((account, index))
+                .unwrap_or_default()
+        }
+
+        /// private helper function to move `amount` votes between two delegates, `None` being no delegate
+        fn move_votes(&mut self, from: Option<AccountId>, to: Option<AccountId>, amount: Balance) {
+            if from == to || amount == 0 {
+                return;
+            }
+            if let Some(from) = from {
+                let votes = self.get_votes(from);
+                self.write_vote_checkpoint(&from, votes, votes - amount);
+            }
+            if let Some(to) = to {
+                let votes = self.get_votes(to);
+                self.write_vote_checkpoint(&to, votes, votes + amount);
+            }
+        }
+
+        /// private helper function to record new voting power of `delegate`, once per block
+        fn write_vote_checkpoint(
+            &mut self,
+            delegate: &AccountId,
+            previous: Balance,
+            votes: Balance,
+        ) {
+            let block = self.env().block_number();
+            let count = self.num_vote_checkpoints.get(delegate).unwrap_or_default();
+            if count > 0 && self.vote_checkpoint(delegate, count - 1).0 == block {
+                self.vote_checkpoints
+                    .insert((delegate, count - 1), &(block, votes));
+            } else {
+                self.vote_checkpoints
+                    .insert((delegate, count), &(block, votes));
+                self.num_vote_checkpoints.insert(delegate, &(count + 1));
+            }
+            self.env().emit_event(DelegateVotesChanged {
+                delegate: *delegate,
+                previous_balance: previous,
+                new_balance: votes,
+            });
+        }
+
+        /// Returns the integral of `account`'s balance over time, in balance × seconds.
+        ///
+        /// Grows with every second a balance is held, so it can weight loyalty rewards.
+        #[ink(message)]
+        pub fn holder_seconds(&self, account: AccountId) -> u128 {
+            self.holder_millis(&account) / 1000
+        }
+
+        /// Returns when each of `accounts` last had its balance changed, or 0 if never.
+        ///
+        /// Reads the timestamp the holder-time tracker keeps for every balance change.
+        #[ink(message)]
+        pub fn last_activity_batch(&self, accounts: Vec<AccountId>) -> Vec<Timestamp> {
+            accounts
+                .iter()
+                .map(|account| {
+                    self.holder_time
+                        .get(account)
+                        .map(|(_, since)| since)
+                        .unwrap_or_default()
+                })
+                .collect()
+        }
+
+        /// private helper function to get `account`'s balance integrated over time up to now, in milliseconds
+        fn holder_millis(&self, account: &AccountId) -> u128 {
+            let (accrued, since) = self.holder_time.get(account).unwrap_or_default();
+            let held_for = u128::from(self.env().block_timestamp().saturating_sub(since));
+            accrued.saturating_add(self.balance_of_impl(account).saturating_mul(held_for))
+        }
+
+        /// private helper function to bring `account`'s holder time up to date before its balance changes
+        fn accrue_holder_time(&mut self, account: &AccountId) {
+            let accrued = self.holder_millis(account);
+            self.holder_time
+                .insert(account, &(accrued, self.env().block_timestamp()));
+        }
+
+        /// private helper function to emit the `Transfer` for a move already written.
+        fn emit_transfer(&self, from: &AccountId, to: &AccountId, value: Balance) {
+            // A self-transfer moves nothing, so its event is non-essential
+            if from == to && !self.emit_events {
+                return;
+            }
+
+            self.emit_balance_change(Some(*from), Some(*to), value);
+            self.report_taxable(from, TaxableKind::TransferOut, value);
+            self.report_taxable(to, TaxableKind::TransferIn, value);
+        }
+
+        /// private helper function to emit a `Transfer`, or a `CompactDiff` per account in compact mode
+        fn emit_balance_change(
+            &self,
+            from: Option<AccountId>,
+            to: Option<AccountId>,
+            value: Balance,
+        ) {
+            if !self.compact_events {
+                self.env().emit_event(Transfer { from, to, value });
+                return;
+            }
+            // Amounts beyond the signed range saturate
+            let delta = i128::try_from(value).unwrap_or(i128::MAX);
+            for (account, delta) in [(from, -delta), (to, delta)] {
+                if let Some(account) = account {
+                    self.env().emit_event(CompactDiff {
+                        account_hash: account_hash(&account),
+                        delta,
+                    });
+                }
+            }
+        }
+
+        /// private helper function to emit a `TaxableEvent` if `account` has reporting enabled
+        fn report_taxable(&self, account: &AccountId, kind: TaxableKind, amount: Balance) {
+            if self.report_enabled.get(account).unwrap_or_default() {
+                self.env().emit_event(TaxableEvent {
+                    account: *account,
+                    kind,
+                    amount,
+                    timestamp: self.env().block_timestamp(),
+                });
+            }
+        }
+
+        /// Returns up to the last `RECENT_TRANSFERS_LEN` transfers as
+        /// `(from, to, value, block)`, oldest first.
+        ///
+        /// Always empty unless the contract is built with the `recent-transfers` feature.
+        #[ink(message)]
+        pub fn recent_transfers(&self) -> Vec<(AccountId, AccountId, Balance, BlockNumber)> {
+            #[cfg(feature = "recent-transfers")]
+            {
+                let first = self.recorded_transfers.saturating_sub(RECENT_TRANSFERS_LEN);
+                (first..self.recorded_transfers)
+                    .filter_map(|index| self.recent_transfers.get(index % RECENT_TRANSFERS_LEN))
+                    .collect()
+            }
+            #[cfg(not(feature = "recent-transfers"))]
+            {
+                Vec::new()
+            }
+        }
+
+        /// private helper function to append a transfer to the recent-transfers ring buffer
+        #[cfg(feature = "recent-transfers")]
+        fn record_transfer(&mut self, from: &AccountId, to: &AccountId, value: Balance) {
+            let slot = self.recorded_transfers % RECENT_TRANSFERS_LEN;
+            let block = self.env().block_number();
+            self.recent_transfers
+                .insert(slot, &(*from, *to, value, block));
+            self.recorded_transfers += 1;
+        }
+
+        /// Splits `total` tokens from the caller across `recipients` by basis-point weight.
+        ///
+        /// The weights must sum to exactly 10000. Rounding dust left over by the
+        /// proportional shares goes to the first recipient.
+        #[ink(message)]
+        pub fn transfer_split(
+            &mut self,
+            recipients: Vec<(AccountId, u16)>,
+            total: Balance,
+        ) -> Result<()> {
+            self.non_reentrant(|contract| contract.transfer_split_impl(recipients, total))
+        }
+
+        /// private helper function running `transfer_split` under the reentrancy guard
+        fn transfer_split_impl(
+            &mut self,
+            recipients: Vec<(AccountId, u16)>,
+            total: Balance,
+        ) -> Result<()> {
+            let weights: u32 = recipients
+                .iter()
+                .map(|(_, weight)| u32::from(*weight))
+                .sum();
+            if weights != BPS_DENOMINATOR {
+                return Err(Error::InvalidWeights);
+            }
+
+            let from = self.env().caller();
+            let mut shares: Vec<(AccountId, Balance)> = recipients
+                .iter()
+                .map(|(to, weight)| {
+                    let share = total
+                        .checked_mul(Balance::from(*weight))
+                        .and_then(|scaled| scaled.checked_div(Balance::from(BPS_DENOMINATOR)))
+                        .ok_or(Error::Overflow)?;
+                    Ok((*to, share))
+                })
+                .collect::<Result<_>>()?;
+            let distributed: Balance = shares.iter().map(|(_, share)| share).sum();
+            shares[0].1 += total - distributed;
+
+            self.check_batch(&from, &shares)?;
+            for (to, share) in shares {
+                self.transfer_from_to(&from, &to, share)?;
+            }
+            Ok(())
+        }
+
+        /// Transfers `values[i]` tokens from the caller to each `recipients[i]`, all or nothing.
+        #[ink(message)]
+        pub fn transfer_batch(
+            &mut self,
+            recipients: Vec<AccountId>,
+            values: Vec<Balance>,
+        ) -> Result<()> {
+            self.non_reentrant(|contract| contract.transfer_batch_impl(recipients, values))
+        }
+
+        /// private helper function running `transfer_batch` under the reentrancy guard
+        fn transfer_batch_impl(
+            &mut self,
+            recipients: Vec<AccountId>,
+            values: Vec<Balance>,
+        ) -> Result<()> {
+            if recipients.len() != values.len() {
+                return Err(Error::LengthMismatch);
+            }
+            let from = self.env().caller();
+            let transfers: Vec<(AccountId, Balance)> = recipients.into_iter().zip(values).collect();
+
+            self.check_batch(&from, &transfers)?;
+            for (to, value) in transfers {
+                self.transfer_from_to(&from, &to, value)?;
+            }
+            Ok(())
+        }
+
+        /// Transfers each of `transfers` from `from` using the caller's allowance, all or nothing.
+        ///
+        /// The allowance must cover the whole batch and is spent once, with a
+        /// single `Approval`; an allowance of `Balance::MAX` is not decremented.
+        #[ink(message)]
+        pub fn transfer_from_batch(
+            &mut self,
+            from: AccountId,
+            transfers: Vec<(AccountId, Balance)>,
+        ) -> Result<()> {
+            self.non_reentrant(|contract| contract.transfer_from_batch_impl(from, transfers))
+        }
+
+        /// private helper function running `transfer_from_batch` under the reentrancy guard
+        fn transfer_from_batch_impl(
+            &mut self,
+            from: AccountId,
+            transfers: Vec<(AccountId, Balance)>,
+        ) -> Result<()> {
+            if self.allowances_frozen {
+                return Err(Error::AllowancesFrozen);
+            }
+            let caller = self.env().caller();
+            if self.is_blacklisted_impl(&caller) {
+                return Err(Error::Blacklisted);
+            }
+            let allowance = self.allowance_impl(&from, &caller);
+            let total = transfers
+                .iter()
+                .try_fold(0, |total: Balance, (_, value)| total.checked_add(*value))
+                .ok_or(Error::Overflow)?;
+            if allowance < total {
+                return Err(Error::InsufficientAllowance {
+                    required: total,
+                    available: allowance,
+                });
+            }
+
+            self.check_batch(&from, &transfers)?;
+            for (to, value) in transfers {
+                self.transfer_from_to(&from, &to, value)?;
+            }
+            if allowance == Balance::MAX {
+                return Ok(());
+            }
+            self.write_allowance(&from, &caller, allowance - total);
+            if self.emit_events {
+                self.env().emit_event(Approval {
+                    owner: from,
+                    spender: caller,
+                    value: allowance - total,
+                });
+            }
+            Ok(())
+        }
+
+        /// private helper function to validate a whole batch of transfers from `from` up front
+        ///
+        /// Returning an error does not revert state, so every leg must be known to
+        /// succeed before the first one is applied.
+        fn check_batch(&self, from: &AccountId, transfers: &[(AccountId, Balance)]) -> Result<()> {
+            let total = transfers
+                .iter()
+                .try_fold(0, |total: Balance, (_, value)| total.checked_add(*value))
+                .ok_or(Error::Overflow)?;
+            self.ensure_spendable(from, total)?;
+            for (to, value) in transfers {
+                self.check_transfer(from, to, *value)?;
+            }
+            self.check_activity(from, total)?;
+            for (to, _) in transfers {
+                let legs = transfers.iter().filter(|(other, _)| other == to).count();
+                self.check_pair_limit(from, to, legs as u32)?;
+            }
+            Ok(())
+        }
+
+        /// Previews sending each of `transfers` from `from` in order, without changing state.
+        ///
+        /// Entries are evaluated sequentially, so an earlier transfer can leave too
+        /// little balance for a later one, exactly as separate `transfer` calls would.
+        #[ink(message)]
+        pub fn preview_transfer_batch(
+            &self,
+            from: AccountId,
+            transfers: Vec<(AccountId, Balance)>,
+        ) -> BatchPreview {
+            let mut balance = self.balance_of_impl(&from);
+            let mut moved = 0;
+            let mut sent_to: Vec<AccountId> = Vec::new();
+            let results = transfers
+                .iter()
+                .map(|(to, value)| {
+                    self.check_transfer(&from, to, *value)?;
+                    self.check_activity(&from, moved + value)?;
+                    let earlier = sent_to.iter().filter(|other| *other == to).count();
+                    self.check_pair_limit(&from, to, earlier as u32 + 1)?;
+                    if balance < *value {
+                        return Err(Error::InsufficientBalance {
+                            required: *value,
+                            available: balance,
+                        });
+                    }
+                    if balance.saturating_sub(self.total_locked(&from)) < *value {
+                        return Err(Error::AmountLocked);
+                    }
+                    moved += value;
+                    sent_to.push(*to);
+                    let fee = self.transfer_fee(&from, *value)?;
+                    let received = value - fee;
+                    balance -= value;
+                    if *to == from {
+                        balance += received;
+                    }
+                    for (destination, share) in self.fee_legs(self.split_fee(fee).1) {
+                        if destination == from {
+                            balance += share;
+                        }
+                    }
+                    Ok(received)
+                })
+                .collect();
+            BatchPreview {
+                results,
+                final_balance: balance,
+            }
+        }
+
+        /// Checks whether a planned sequence of `(from, to, value)` transfers would pass, without changing state.
+        ///
+        /// Steps are simulated in order, so each hop sees the balances, activity and
+        /// pair counts left by the previous ones. Returns the first step's error.
+        #[ink(message)]
+        pub fn path_allowed(&self, steps: Vec<(AccountId, AccountId, Balance)>) -> Result<()> {
+            // Balances and activity as changed by the steps simulated so far
+            let mut balances: Vec<(AccountId, Balance)> = Vec::new();
+            let mut moved: Vec<(AccountId, Balance)> = Vec::new();
+            let lookup = |entries: &Vec<(AccountId, Balance)>, account: &AccountId| {
+                entries
+                    .iter()
+                    .find(|(other, _)| other == account)
+                    .map(|(_, amount)| *amount)
+            };
+            let update =
+                |entries: &mut Vec<(AccountId, Balance)>, account: AccountId, amount| match entries
+                    .iter_mut()
+                    .find(|(other, _)| *other == account)
+                {
+                    Some(entry) => entry.1 = amount,
+                    None => entries.push((account, amount)),
+                };
+
+            for (index, (from, to, value)) in steps.iter().enumerate() {
+                self.check_transfer(from, to, *value)?;
+                let moved_by_from = lookup(&moved, from).unwrap_or_default() + value;
+                self.check_activity(from, moved_by_from)?;
+                let earlier = steps[..index]
+                    .iter()
+                    .filter(|(other_from, other_to, _)| other_from == from && other_to == to)
+                    .count();
+                self.check_pair_limit(from, to, earlier as u32 + 1)?;
+
+                let from_balance =
+                    lookup(&balances, from).unwrap_or_else(|| self.balance_of_impl(from));
+                if from_balance < *value {
+                    return Err(Error::InsufficientBalance {
+                        required: *value,
+                        available: from_balance,
+                    });
+                }
+                let fee = self.transfer_fee(from, *value)?;
+                update(&mut balances, *from, from_balance - value);
+                let mut credits = self.fee_legs(self.split_fee(fee).1);
+                credits.push((*to, value - fee));
+                for (account, credit) in credits {
+                    let balance = lookup(&balances, &account)
+                        .unwrap_or_else(|| self.balance_of_impl(&account));
+                    let credited = balance.checked_add(credit).ok_or(Error::Overflow)?;
+                    update(&mut balances, account, credited);
+                }
+                update(&mut moved, *from, moved_by_from);
+            }
+            Ok(())
+        }
+
+        /// private helper function to validate the restrictions on moving tokens from `from` to `to`
+        fn check_transfer(&self, from: &AccountId, to: &AccountId, value: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
+            // Tokens held by the contract move on the thawed caller's behalf
+            if *from != self.env().account_id() {
+                self.ensure_thawed(from)?;
+            }
+            if *to == self.env().account_id() {
+                return Err(Error::TransferToContract);
+            }
+            // Frozen balances are immutable in both directions
+            self.ensure_thawed(to)?;
+            // Tokens sent there would be lost without leaving the supply; burn them instead
+            if *to == AccountId::from(ZERO_ADDRESS) {
+                return Err(Error::ZeroAddress);
+            }
+            if self.is_blacklisted_impl(from) || self.is_blacklisted_impl(to) {
+                return Err(Error::Blacklisted);
+            }
+            if self.tainted.contains(from) && self.quarantine != Some(*to) {
+                return Err(Error::TaintedFunds);
+            }
+            if value < self.transfer_fee(from, value)? {
+                return Err(Error::AmountBelowFee);
+            }
+            self.check_activity(from, value)?;
+            self.check_pair_limit(from, to, 1)?;
+            self.check_regions(from, to)?;
+            self.check_groups(from, to)?;
+            self.check_class(from)?;
+            self.check_tos(from, to)?;
+            self.check_launch_phase(from, value)?;
+            if let Some(min) = self.min_recipient_balance {
+                // Payouts from the contract-held pool are not gated
+                if self.balance_of_impl(to) < min && *from != self.env().account_id() {
+                    return Err(Error::RecipientBalanceTooLow);
+                }
+            }
+            if let Some(threshold) = self.large_transfer_threshold {
+                if value > threshold && !self.large_transfer_optin.contains(to) {
+                    return Err(Error::RecipientNotOptedIn);
+                }
+            }
+            if self.code_hash_strict {
+                // Accounts without code are not contracts and are always allowed
+                if let Ok(code_hash) = self.env().code_hash(to) {
+                    if !self.allowed_code_hashes.contains(code_hash) {
+                        return Err(Error::CodeHashNotAllowed);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        /// private helper function to validate `from` moving `value` tokens into escrow at the contract account
+        ///
+        /// Applies the checks of `check_transfer` that restrict the sender; the
+        /// contract account is not a holder, so the recipient-side checks do not apply.
+        fn check_escrow(&self, from: &AccountId, value: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
+            self.ensure_thawed(from)?;
+            if self.is_blacklisted_impl(from) {
+                return Err(Error::Blacklisted);
+            }
+            if self.tainted.contains(from) {
+                return Err(Error::TaintedFunds);
+            }
+            self.check_activity(from, value)?;
+            self.check_class(from)?;
+            self.check_tos(from, &self.env().account_id())?;
+            self.check_launch_phase(from, value)
+        }
+
+        /// private helper function to get the fee charged when `from` sends `value` tokens
+        fn transfer_fee(&self, from: &AccountId, value: Balance) -> Result<Balance> {
+            // Payouts from the contract-held pool are not charged
+            if *from == self.env().account_id() || self.fee_exempt.contains(from) {
+                return Ok(0);
+            }
+            if !self.feature_enabled(FEATURE_FEES) || self.is_fee_holiday() {
+                return Ok(0);
+            }
+            let fee = value
+                .checked_mul(Balance::from(self.fee_bps))
+                .and_then(|scaled| {
+                    self.flat_fee
+                        .checked_add(scaled / Balance::from(BPS_DENOMINATOR))
+                })
+                .ok_or(Error::Overflow)?;
+            if let Some(nft_contract) = self.nft_contract {
+                if fee > 0
+                    && self.nft_fee_discount > 0
+                    && self.holds_partner_nft(nft_contract, from)?
+                {
+                    let discount = fee
+                        .checked_mul(Balance::from(self.nft_fee_discount))
+                        .ok_or(Error::Overflow)?
+                        / Balance::from(BPS_DENOMINATOR);
+                    return Ok(fee - discount);
+                }
+            }
+            Ok(fee)
+        }
+
+        /// private helper function to split a collected `fee` into `(burned, routed to the fee destinations)`
+        fn split_fee(&self, fee: Balance) -> (Balance, Balance) {
+            let burned = fee * Balance::from(self.fee_split_bps) / Balance::from(BPS_DENOMINATOR);
+            (burned, fee - burned)
+        }
+
+        /// private helper function to split the unburned part of a fee into what each destination receives
+        ///
+        /// Falls back to the treasury while no destinations are set. Rounding dust
+        /// goes to the first destination, and empty shares are left out.
+        fn fee_legs(&self, routed: Balance) -> Vec<(AccountId, Balance)> {
+            let raffle_share = self.raffle_share(routed);
+            let routed = routed - raffle_share;
+            let mut shares: Vec<(AccountId, Balance)> = if self.fee_destinations.is_empty() {
+                ink_prelude::vec![(self.treasury, routed)]
+            } else {
+                self.fee_destinations
+                    .iter()
+                    .map(|(destination, weight)| {
+                        (
+                            *destination,
+                            routed * Balance::from(*weight) / Balance::from(BPS_DENOMINATOR),
+                        )
+                    })
+                    .collect()
+            };
+            let distributed: Balance = shares.iter().map(|(_, share)| share).sum();
+            shares[0].1 += routed - distributed;
+            // The raffle's share is held by the contract account until the draw
+            shares.push((self.env().account_id(), raffle_share));
+            shares.retain(|(_, share)| *share > 0);
+            shares
+        }
+
+        /// private helper function to get the part of the unburned fee `routed` paid into the raffle pool
+        fn raffle_share(&self, routed: Balance) -> Balance {
+            routed * Balance::from(self.raffle_fee_bps) / Balance::from(BPS_DENOMINATOR)
+        }
+
+        /// private helper function to query the partner NFT contract for `account`'s holdings
+        fn holds_partner_nft(&self, nft_contract: AccountId, account: &AccountId) -> Result<bool> {
+            build_call::<Environment>()
+                .call_type(Call::new().callee(nft_contract).gas_limit(0))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(PSP34_BALANCE_OF_SELECTOR))
+                        .push_arg(*account),
+                )
+                .returns::<u32>()
+                .fire()
+                .map(|held| held > 0)
+                .map_err(|_| Error::NftCheckFailed)
+        }
+
+        /// private helper function to get the balance of an account, net of uncollected demurrage
+        #[inline]
+        fn balance_of_impl(&self, owner: &AccountId) -> Balance {
+            let stored = self.stored_balance(owner);
+            stored - self.pending_demurrage(owner, stored)
+        }
+
+        /// private helper function to check that `account` holds at least `required` tokens
+        fn ensure_balance(&self, account: &AccountId, required: Balance) -> Result<()> {
+            let available = self.balance_of_impl(account);
+            if available < required {
+                return Err(Error::InsufficientBalance {
+                    required,
+                    available,
+                });
+            }
+            Ok(())
+        }
+
+        /// private helper function to check that `account` holds `value` tokens outside any lock
+        ///
+        /// Every path moving tokens out of an account, into escrow or out of the
+        /// supply, goes through this, so tokens locked by the owner or an open
+        /// dispute stay put.
+        fn ensure_spendable(&self, account: &AccountId, value: Balance) -> Result<()> {
+            self.ensure_balance(account, value)?;
+            if self.unlocked_balance_of(account) < value {
+                return Err(Error::AmountLocked);
+            }
+            Ok(())
+        }
+
+        /// private helper function to get the balance of an account as last written
+        #[inline]
+        fn stored_balance(&self, owner: &AccountId) -> Balance {
+            self.balances.get(owner).unwrap_or_default()
+        }
+
+        /// Function to authorize `spender` to withdraw from your account multiple times, up to the `value` amount.
+        ///
+        /// If the caller enabled allowlist mode, `spender` must be on their allowlist,
+        /// and neither side may be blacklisted; approving zero is always possible.
+        #[ink(message)]
+        pub fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
+            let owner = self.env().caller();
+            self.check_approval(&owner, &spender, value)?;
+            self.approve_impl(owner, spender, value);
+            Ok(())
+        }
+
+        /// private helper function to check that `owner` may grant `spender` `value` more allowance
+        ///
+        /// Lowering an allowance to zero is always allowed, so blacklisted accounts
+        /// and spenders dropped from an allowlist can still be cut off.
+        fn check_approval(
+            &self,
+            owner: &AccountId,
+            spender: &AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            if value == 0 {
+                return Ok(());
+            }
+            if self.is_blacklisted_impl(owner) || self.is_blacklisted_impl(spender) {
+                return Err(Error::Blacklisted);
+            }
+            if self.spender_allowlist_mode.contains(owner)
+                && !self.approved_spenders.contains((owner, spender))
+            {
+                return Err(Error::SpenderNotAllowed);
+            }
+            Ok(())
+        }
+
+        /// private helper function to grant `spender` a fresh allowance of `value` over `owner`'s tokens
+        fn approve_impl(&mut self, owner: AccountId, spender: AccountId, value: Balance) {
+            self.adjust_allowance(owner, spender, value, value);
+        }
+
+        /// Sets `spender`'s allowance over `owner`'s tokens from a signature `owner` made off-chain.
+        ///
+        /// `signature` is a 65-byte recoverable ECDSA signature over `permit_digest`
+        /// for the owner's current nonce; ink exposes no sr25519/ed25519 verification.
+        /// On success this behaves exactly like `owner` calling `approve`.
+        #[ink(message)]
+        pub fn permit(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+            deadline: Timestamp,
+            signature: [u8; 65],
+        ) -> Result<()> {
+            self.ensure_not_paused()?;
+            if self.env().block_timestamp() > deadline {
+                return Err(Error::PermitExpired);
+            }
+            let digest = self.permit_digest(owner, spender, value, deadline);
+            let mut public_key = [0u8; 33];
+            ink_env::ecdsa_recover(&signature, &digest, &mut public_key)
+                .map_err(|_| Error::InvalidSignature)?;
+            let mut signer = [0u8; 32];
+            ink_env::hash_bytes::<ink_env::hash::Blake2x256>(&public_key, &mut signer);
+            if AccountId::from(signer) != owner {
+                return Err(Error::InvalidSignature);
+            }
+            self.check_approval(&owner, &spender, value)?;
+
+            self.nonces.insert(owner, &(self.nonce_of(owner) + 1));
+            self.approve_impl(owner, spender, value);
+            Ok(())
+        }
+
+        /// Returns the digest `owner` must sign for `permit`, using their current nonce.
+        ///
+        /// It is the Blake2x256 hash of the SCALE-encoded
+        /// `(domain_separator, owner, spender, value, deadline, nonce)`.
+        #[ink(message)]
+        pub fn permit_digest(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+            deadline: Timestamp,
+        ) -> [u8; 32] {
+            let payload = (
+                self.domain_separator(),
+                owner,
+                spender,
+                value,
+                deadline,
+                self.nonce_of(owner),
+            );
+            let mut digest = [0u8; 32];
+            ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(&payload, &mut digest);
+            digest
+        }
+
+        /// Returns the domain separator binding permits to this contract and its version.
+        ///
+        /// It is the Blake2x256 hash of the SCALE-encoded
+        /// `(b"permit", contract, contract_version)`, so a signature cannot be
+        /// replayed against another deployment.
+        #[ink(message)]
+        pub fn domain_separator(&self) -> [u8; 32] {
+            let domain = (b"permit", self.env().account_id(), CONTRACT_VERSION);
+            let mut separator = [0u8; 32];
+            ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(&domain, &mut separator);
+            separator
+        }
+
+        /// Returns the nonce the next permit signed by `owner` must use.
+        #[ink(message)]
+        pub fn nonce_of(&self, owner: AccountId) -> u64 {
+            self.nonces.get(owner).unwrap_or_default()
+        }
+
+        /// Returns whether `owner` only allows approvals to spenders on their allowlist.
+        #[ink(message)]
+        pub fn spender_allowlist_mode(&self, owner: AccountId) -> bool {
+            self.spender_allowlist_mode.contains(owner)
+        }
+
+        /// Turns allowlist mode on or off for the caller's approvals.
+        ///
+        /// Existing allowances are left as they are.
+        #[ink(message)]
+        pub fn set_spender_allowlist_mode(&mut self, enabled: bool) -> Result<()> {
+            let owner = self.env().caller();
+            if enabled {
+                self.spender_allowlist_mode.insert(owner, &());
+            } else {
+                self.spender_allowlist_mode.remove(owner);
+            }
+            Ok(())
+        }
+
+        /// Returns whether `spender` is on `owner`'s allowlist.
+        #[ink(message)]
+        pub fn is_approved_spender(&self, owner: AccountId, spender: AccountId) -> bool {
+            self.approved_spenders.contains((owner, spender))
+        }
+
+        /// Adds `spender` to or removes it from the caller's allowlist.
+        #[ink(message)]
+        pub fn set_approved_spender(&mut self, spender: AccountId, allowed: bool) -> Result<()> {
+            let owner = self.env().caller();
+            if allowed {
+                self.approved_spenders.insert((owner, spender), &());
+            } else {
+                self.approved_spenders.remove((owner, spender));
+            }
+            Ok(())
+        }
+
+        /// Raises the allowance the caller granted to `spender` by `delta`.
+        ///
+        /// Unlike `approve` this does not overwrite the allowance, so a spender cannot
+        /// race a change to spend both the old and the new value.
+        #[ink(message)]
+        pub fn increase_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
+            let owner = self.env().caller();
+            self.check_approval(&owner, &spender, delta)?;
+            let allowance = self
+                .allowance_impl(&owner, &spender)
+                .checked_add(delta)
+                .ok_or(Error::Overflow)?;
+            let original = self
+                .original_allowances
+                .get((&owner, &spender))
+                .unwrap_or_default();
+            self.adjust_allowance(owner, spender, allowance, original.saturating_add(delta));
+            Ok(())
+        }
+
+        /// Lowers the allowance the caller granted to `spender` by `delta`.
+        ///
+        /// Decreasing by more than the remaining allowance saturates it to zero.
+        #[ink(message)]
+        pub fn decrease_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
+            let owner = self.env().caller();
+            let current = self.allowance_impl(&owner, &spender);
+            let allowance = current.saturating_sub(delta);
+            let original = self
+                .original_allowances
+                .get((&owner, &spender))
+                .unwrap_or_default();
+            self.adjust_allowance(
+                owner,
+                spender,
+                allowance,
+                original.saturating_sub(current - allowance),
+            );
+            Ok(())
+        }
+
+        /// private helper function to set an allowance while keeping how much of it was spent
+        fn adjust_allowance(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            allowance: Balance,
+            original: Balance,
+        ) {
+            self.write_allowance(&owner, &spender, allowance);
+            self.original_allowances
+                .insert((&owner, &spender), &original);
+
+            // Emit Approval event
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value: allowance,
+            });
+        }
+
+        /// private helper function to store an allowance and stamp when it was last touched
+        fn write_allowance(&mut self, owner: &AccountId, spender: &AccountId, allowance: Balance) {
+            self.allowances.insert((owner, spender), &allowance);
+            self.allowance_updated_at
+                .insert((owner, spender), &self.env().block_timestamp());
+        }
+
+        /// Revokes each of the `(owner, spender)` allowances in `pairs` last set or
+        /// spent from before `older_than`, and returns how many were revoked.
+        ///
+        /// Each revocation emits an `Approval` of 0; empty and fresh allowances are skipped.
+        #[ink(message)]
+        pub fn sweep_stale_allowances(
+            &mut self,
+            pairs: Vec<(AccountId, AccountId)>,
+            older_than: Timestamp,
+        ) -> Result<u32> {
+            self.ensure_owner()?;
+            let mut revoked = 0;
+            for (owner, spender) in pairs {
+                let current = self.allowance_impl(&owner, &spender);
+                let updated_at = self
+                    .allowance_updated_at
+                    .get((owner, spender))
+                    .unwrap_or_default();
+                if current == 0 || updated_at >= older_than {
+                    continue;
+                }
+                let original = self
+                    .original_allowances
+                    .get((&owner, &spender))
+                    .unwrap_or_default();
+                self.adjust_allowance(owner, spender, 0, original.saturating_sub(current));
+                revoked += 1;
+            }
+            Ok(revoked)
+        }
+
+        /// Returns an advisory 0-100 risk score for the allowance `owner` granted to `spender`.
+        ///
+        /// An unlimited (`Balance::MAX`) allowance adds 40. A spender missing from
+        /// `owner`'s allowlist adds 20, plus 40 more if it is a contract whose code
+        /// hash is not allowlisted. No allowance scores 0.
+        #[ink(message)]
+        pub fn approval_risk(&self, owner: AccountId, spender: AccountId) -> u8 {
+            let allowance = self.allowance_impl(&owner, &spender);
+            if allowance == 0 {
+                return 0;
+            }
+            let mut score = 0;
+            if allowance == Balance::MAX {
+                score += 40;
+            }
+            if !self.approved_spenders.contains((owner, spender)) {
+                score += 20;
+                if let Ok(code_hash) = self.env().code_hash(&spender) {
+                    if !self.allowed_code_hashes.contains(code_hash) {
+                        score += 40;
+                    }
+                }
+            }
+            score
+        }
+
+        /// Zeroes the allowances `owner` granted to each of `spenders`, e.g. for compliance.
+        #[ink(message)]
+        pub fn revoke_allowances_of(
+            &mut self,
+            owner: AccountId,
+            spenders: Vec<AccountId>,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            for spender in spenders {
+                self.approve_impl(owner, spender, 0);
+            }
+            Ok(())
+        }
+
+        /// Returns the amount which `spender` is still allowed to withdraw from `owner`.
+        #[ink(message)]
+        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowance_impl(&owner, &spender)
+        }
+
+        /// Returns `(spent, original)` for the allowance `owner` last granted to `spender`.
+        ///
+        /// `original` is the value of the latest `approve`; `spent` is how much of it
+        /// has been used through `transfer_from` since.
+        #[ink(message)]
+        pub fn allowance_utilization(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+        ) -> (Balance, Balance) {
+            let original = self
+                .original_allowances
+                .get((&owner, &spender))
+                .unwrap_or_default();
+            let remaining = self.allowance_impl(&owner, &spender);
+            (original.saturating_sub(remaining), original)
+        }
+
+        /// private helper function to get the allowance of an account
+        #[inline]
+        fn allowance_impl(&self, owner: &AccountId, spender: &AccountId) -> Balance {
+            self.allowances.get((owner, spender)).unwrap_or_default()
+        }
+
+        /// Transfers tokens on the behalf of the `from` account to the `to` account.
+        ///
+        /// An allowance of `Balance::MAX` is unlimited and is not decremented.
+        #[ink(message)]
+        pub fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            self.non_reentrant(|contract| contract.transfer_from_impl(from, to, value))
+        }
+
+        /// private helper function running `transfer_from` under the reentrancy guard
+        fn transfer_from_impl(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            if self.allowances_frozen {
+                return Err(Error::AllowancesFrozen);
+            }
+
+            let caller = self.env().caller();
+            if self.is_blacklisted_impl(&caller) {
+                return Err(Error::Blacklisted);
+            }
+            let allowance = self.allowance_impl(&from, &caller);
+
+            if allowance < value {
+                return Err(Error::InsufficientAllowance {
+                    required: value,
+                    available: allowance,
+                });
+            }
+
+            self.transfer_from_to(&from, &to, value)?;
+            // An unlimited allowance is never spent down
+            if allowance == Balance::MAX {
+                return Ok(());
+            }
+            self.write_allowance(&from, &caller, allowance - value);
+
+            if self.emit_events {
+                self.env().emit_event(Approval {
+                    owner: from,
+                    spender: caller,
+                    value: allowance - value,
+                });
+            }
+            Ok(())
+        }
+
+        /// Deposits `value` of the owner's tokens as a dividend for all holders.
+        ///
+        /// The tokens are held by the contract account and shared pro-rata to
+        /// balances at the time of the deposit; holders collect them with `claim_dividends`.
+        #[ink(message)]
+        pub fn deposit_dividends(&mut self, value: Balance) -> Result<()> {
+            self.non_reentrant(|contract| contract.deposit_dividends_impl(value))
+        }
+
+        /// private helper function running `deposit_dividends` under the reentrancy guard
+        fn deposit_dividends_impl(&mut self, value: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
+            self.ensure_owner()?;
+            let caller = self.env().caller();
+            self.ensure_spendable(&caller, value)?;
+
+            let pool = self.env().account_id();
+            let eligible_supply = self.total_supply - self.balance_of_impl(&pool) - value;
+            if eligible_supply == 0 {
+                return Err(Error::NoEligibleHolders);
+            }
+
+            let dividends_per_share = value
+                .checked_mul(DIVIDEND_PRECISION)
+                .and_then(|scaled| {
+                    self.dividends_per_share
+                        .checked_add(scaled / eligible_supply)
+                })
+                .filter(|per_share| self.total_supply.checked_mul(*per_share).is_some())
+                .ok_or(Error::Overflow)?;
+
+            // The pool is the contract account, which ordinary transfers refuse
+            self.move_tokens(&caller, &pool, value);
+            self.dividends_per_share = dividends_per_share;
+            Ok(())
+        }
+
+        /// Returns the dividends `owner` can currently claim.
+        #[ink(message)]
+        pub fn dividends_of(&self, owner: AccountId) -> Balance {
+            self.pending_dividends.get(owner).unwrap_or_default() + self.accrued_dividends(&owner)
+        }
+
+        /// Transfers the caller's accrued dividends from the contract-held pool.
+        ///
+        /// Pays out at most `max_reward_per_claim`, if set, and returns the amount paid.
+        /// Holders with auto-reinvest get staked shares for it instead, unless it is
+        /// too small to buy a single share.
+        #[ink(message)]
+        pub fn claim_dividends(&mut self) -> Result<Balance> {
+            self.non_reentrant(|contract| contract.claim_dividends_impl())
+        }
+
+        /// private helper function running `claim_dividends` under the reentrancy guard
+        fn claim_dividends_impl(&mut self) -> Result<Balance> {
+            let caller = self.env().caller();
+            let now = self.env().block_timestamp();
+            if let Some(last_claim) = self.last_claim_at.get(caller) {
+                if now < last_claim + self.claim_cooldown {
+                    return Err(Error::ClaimCooldownActive);
+                }
+            }
+
+            let owed = self.dividends_of(caller);
+            let amount = match self.max_reward_per_claim {
+                Some(cap) => owed.min(cap),
+                None => owed,
+            };
+            if amount == 0 {
+                return Ok(0);
+            }
+
+            // Validate the payout before touching the dividend bookkeeping
+            let pool = self.env().account_id();
+            self.check_transfer(&pool, &caller, amount)?;
+
+            // Anything above the per-claim cap stays pending for the next claim
+            self.settle_dividends(&caller);
+            self.pending_dividends.insert(caller, &(owed - amount));
+            self.last_claim_at.insert(caller, &now);
+            if self.auto_reinvest(caller) {
+                // The dividend stays in the pool and now backs the new shares
+                self.accrue_staking_rewards();
+                let shares = self.shares_for(amount)?;
+                if shares > 0 {
+                    self.credit_shares(&caller, shares, amount);
+                    return Ok(amount);
+                }
+            }
+            self.transfer_from_to(&pool, &caller, amount)?;
+            Ok(amount)
+        }
+
+        /// Caps how much a single claim pays out, or `None` to lift the cap.
+        #[ink(message)]
+        pub fn set_max_reward_per_claim(&mut self, cap: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            self.max_reward_per_claim = cap;
+            Ok(())
+        }
+
+        /// Sets the minimum time a holder must wait between two paid-out claims.
+        #[ink(message)]
+        pub fn set_claim_cooldown(&mut self, cooldown: Timestamp) -> Result<()> {
+            self.ensure_owner()?;
+            self.claim_cooldown = cooldown;
+            Ok(())
+        }
+
+        /// Returns the staked shares `account` holds.
+        #[ink(message)]
+        pub fn shares_of(&self, account: AccountId) -> Balance {
+            self.staked_shares.get(account).unwrap_or_default()
+        }
+
+        /// Returns the tokens one staked share redeems for, scaled by 10^12.
+        ///
+        /// Starts at one token per share and rises as staking rewards are added.
+        #[ink(message)]
+        pub fn share_price(&self) -> Balance {
+            if self.total_shares == 0 {
+                return SHARE_PRICE_PRECISION;
+            }
+            (self.staked_tokens + self.pending_staking_rewards()) * SHARE_PRICE_PRECISION
+                / self.total_shares
+        }
+
+        /// Returns the tokens minted into the staking pool per block, if any.
+        #[ink(message)]
+        pub fn staking_reward_rate(&self) -> Option<Balance> {
+            self.staking_reward_rate.map(|(rate, _)| rate)
+        }
+
+        /// Mints `rate` tokens per block into the staking pool, or stops with `None`.
+        ///
+        /// Rewards raise the share price, so every staker earns in proportion to
+        /// their shares. Rewards accrued under the old rate are minted first.
+        #[ink(message)]
+        pub fn set_staking_reward_rate(&mut self, rate: Option<Balance>) -> Result<()> {
+            self.non_reentrant(|contract| contract.set_staking_reward_rate_impl(rate))
+        }
+
+        /// private helper function running `set_staking_reward_rate` under the reentrancy guard
+        fn set_staking_reward_rate_impl(&mut self, rate: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            if rate == Some(0) {
+                return Err(Error::InvalidRewardRate);
+            }
+            self.accrue_staking_rewards();
+            self.staking_reward_rate = rate.map(|rate| (rate, self.env().block_number()));
+            Ok(())
+        }
+
+        /// Returns the staking rewards `account`'s shares have earned but which are not minted yet.
+        ///
+        /// They are minted into the pool on the next stake, unstake or rate change,
+        /// and paid out with the shares by `unstake`.
+        #[ink(message)]
+        pub fn pending_rewards(&self, account: AccountId) -> Balance {
+            if self.total_shares == 0 {
+                return 0;
+            }
+            self.shares_of(account)
+                .saturating_mul(self.pending_staking_rewards())
+                / self.total_shares
+        }
+
+        /// private helper function to get the rewards accrued by the whole staking pool since they were last minted
+        ///
+        /// An empty pool earns nothing, and rewards stop at the supply cap.
+        fn pending_staking_rewards(&self) -> Balance {
+            match self.staking_reward_rate {
+                Some((rate, since)) if self.total_shares > 0 => {
+                    let blocks = Balance::from(self.env().block_number().saturating_sub(since));
+                    let headroom = self.cap.unwrap_or(Balance::MAX) - self.total_supply;
+                    rate.saturating_mul(blocks).min(headroom)
+                }
+                _ => 0,
+            }
+        }
+
+        /// private helper function to mint the accrued staking rewards into the pool
+        fn accrue_staking_rewards(&mut self) {
+            let rewards = self.pending_staking_rewards();
+            if let Some((rate, _)) = self.staking_reward_rate {
+                self.staking_reward_rate = Some((rate, self.env().block_number()));
+            }
+            if rewards == 0 {
+                return;
+            }
+            let pool = self.env().account_id();
+            self.mint_impl(&pool, rewards);
+            self.staked_tokens += rewards;
+        }
+
+        /// Stakes `amount` of the caller's tokens for shares at the current share price.
+        ///
+        /// The tokens are held by the contract account, so staking is restricted
+        /// like a transfer into it; returns the shares minted.
+        #[ink(message)]
+        pub fn stake(&mut self, amount: Balance) -> Result<Balance> {
+            self.non_reentrant(|contract| contract.stake_impl(amount))
+        }
+
+        /// private helper function running `stake` under the reentrancy guard
+        fn stake_impl(&mut self, amount: Balance) -> Result<Balance> {
+            let caller = self.env().caller();
+            self.check_escrow(&caller, amount)?;
+            self.ensure_spendable(&caller, amount)?;
+            self.accrue_staking_rewards();
+            let shares = self.shares_for(amount)?;
+            if shares == 0 {
+                return Err(Error::StakeTooSmall);
+            }
+
+            let pool = self.env().account_id();
+            self.move_tokens(&caller, &pool, amount);
+            self.credit_shares(&caller, shares, amount);
+            Ok(shares)
+        }
+
+        /// private helper function to get the shares `amount` tokens buy at the current share price
+        fn shares_for(&self, amount: Balance) -> Result<Balance> {
+            if self.total_shares == 0 {
+                return Ok(amount);
+            }
+            Ok(amount
+                .checked_mul(self.total_shares)
+                .ok_or(Error::Overflow)?
+                / self.staked_tokens)
+        }
+
+        /// private helper function to mint `shares` to `account` for `amount` tokens already in the pool
+        fn credit_shares(&mut self, account: &AccountId, shares: Balance, amount: Balance) {
+            self.staked_shares
+                .insert(account, &(self.shares_of(*account) + shares));
+            self.total_shares += shares;
+            self.staked_tokens += amount;
+        }
+
+        /// Returns whether `account` reinvests its dividends into staked shares.
+        #[ink(message)]
+        pub fn auto_reinvest(&self, account: AccountId) -> bool {
+            self.auto_reinvest.contains(account)
+        }
+
+        /// Opts the caller in or out of reinvesting claimed dividends into staked shares.
+        #[ink(message)]
+        pub fn set_auto_reinvest(&mut self, enabled: bool) -> Result<()> {
+            let caller = self.env().caller();
+            if enabled {
+                self.auto_reinvest.insert(caller, &());
+            } else {
+                self.auto_reinvest.remove(caller);
+            }
+            Ok(())
+        }
+
+        /// Redeems `shares` of the caller's staked shares for tokens at the current share price.
+        ///
+        /// Returns the tokens paid out.
+        #[ink(message)]
+        pub fn unstake(&mut self, shares: Balance) -> Result<Balance> {
+            self.non_reentrant(|contract| contract.unstake_impl(shares))
+        }
+
+        /// private helper function running `unstake` under the reentrancy guard
+        fn unstake_impl(&mut self, shares: Balance) -> Result<Balance> {
+            let caller = self.env().caller();
+            let held = self.shares_of(caller);
+            if held < shares {
+                return Err(Error::InsufficientShares);
+            }
+            if shares == 0 {
+                return Err(Error::StakeTooSmall);
+            }
+            self.accrue_staking_rewards();
+            let amount = shares
+                .checked_mul(self.staked_tokens)
+                .ok_or(Error::Overflow)?
+                / self.total_shares;
+            let pool = self.env().account_id();
+            self.check_transfer(&pool, &caller, amount)?;
+
+            self.staked_shares.insert(caller, &(held - shares));
+            self.total_shares -= shares;
+            self.staked_tokens -= amount;
+            self.move_tokens(&pool, &caller, amount);
+            Ok(amount)
+        }
+
+        /// Adds `amount` of the owner's tokens to the staking pool, raising the share price.
+        #[ink(message)]
+        pub fn add_staking_rewards(&mut self, amount: Balance) -> Result<()> {
+            self.non_reentrant(|contract| contract.add_staking_rewards_impl(amount))
+        }
+
+        /// private helper function running `add_staking_rewards` under the reentrancy guard
+        fn add_staking_rewards_impl(&mut self, amount: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
+            self.ensure_owner()?;
+            if self.total_shares == 0 {
+                return Err(Error::NoEligibleHolders);
+            }
+            let caller = self.env().caller();
+            self.ensure_spendable(&caller, amount)?;
+
+            self.accrue_staking_rewards();
+            let pool = self.env().account_id();
+            self.move_tokens(&caller, &pool, amount);
+            self.staked_tokens += amount;
+            Ok(())
+        }
+
+        /// Returns the loyalty points accrued by `owner`.
+        #[ink(message)]
+        pub fn points_of(&self, owner: AccountId) -> Balance {
+            self.points.get(owner).unwrap_or_default()
+        }
+
+        /// Sets how many points a sender earns per transferred token, in basis points.
+        #[ink(message)]
+        pub fn set_points_rate(&mut self, rate: u32) -> Result<()> {
+            self.ensure_owner()?;
+            self.points_rate = rate;
+            Ok(())
+        }
+
+        /// private helper function to credit points for sending `value` tokens
+        ///
+        /// Points saturate rather than overflow, so accrual never fails a transfer.
+        fn accrue_points(&mut self, owner: &AccountId, value: Balance) {
+            let earned = value.saturating_mul(Balance::from(self.points_rate))
+                / Balance::from(BPS_DENOMINATOR);
+            if earned > 0 {
+                self.points
+                    .insert(owner, &self.points_of(*owner).saturating_add(earned));
+            }
+        }
+
+        /// Returns `true` if spending through allowances is currently frozen.
+        #[ink(message)]
+        pub fn allowances_frozen(&self) -> bool {
+            self.allowances_frozen
+        }
+
+        /// Freezes or unfreezes all spending through allowances.
+        ///
+        /// Meant for allowance-phishing incidents: `transfer_from` is rejected
+        /// while frozen, but direct `transfer`s keep working.
+        #[ink(message)]
+        pub fn set_allowances_frozen(&mut self, frozen: bool) -> Result<()> {
+            self.ensure_owner()?;
+            self.allowances_frozen = frozen;
+            Ok(())
+        }
+
+        /// Returns `true` if `account` is on the denylist.
+        #[ink(message)]
+        pub fn is_blacklisted(&self, account: AccountId) -> bool {
+            self.is_blacklisted_impl(&account)
+        }
+
+        /// Blacklists `account` until `until`, after which it can transact again
+        /// without being explicitly removed.
+        #[ink(message)]
+        pub fn set_blacklisted_until(
+            &mut self,
+            account: AccountId,
+            until: Timestamp,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            self.freeze(account, Some(until));
+            Ok(())
+        }
+
+        /// Permanently blacklists `account`, a compliance freeze lifted with `unfreeze_account`.
+        #[ink(message)]
+        pub fn freeze_account(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.freeze(account, None);
+            Ok(())
+        }
+
+        /// Removes `account` from the denylist.
+        #[ink(message)]
+        pub fn unfreeze_account(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.unfreeze(account);
+            Ok(())
+        }
+
+        /// Permanently flags every account in `accounts` as blacklisted, e.g. to sync a sanctions list.
+        ///
+        /// Returns how many of them were not already on the denylist.
+        #[ink(message)]
+        pub fn import_denylist(&mut self, accounts: Vec<AccountId>) -> Result<u32> {
+            self.ensure_owner()?;
+            let mut added = 0;
+            for account in accounts {
+                if !self.is_blacklisted_impl(&account) {
+                    added += 1;
+                }
+                self.freeze(account, None);
+            }
+            Ok(added)
+        }
+
+        /// Removes every account in `accounts` from the denylist.
+        #[ink(message)]
+        pub fn clear_denylist(&mut self, accounts: Vec<AccountId>) -> Result<()> {
+            self.ensure_owner()?;
+            for account in accounts {
+                self.unfreeze(account);
+            }
+            Ok(())
+        }
+
+        /// Moves the balance of the blacklisted `from` into escrow pending appeal.
+        ///
+        /// Tokens under an open dispute are left for the sender to reverse.
+        /// Seizing again adds to the escrow and restarts the appeal window.
+        /// Returns the amount seized.
+        #[ink(message)]
+        pub fn seize_to_escrow(&mut self, from: AccountId) -> Result<Balance> {
+            self.non_reentrant(|contract| contract.seize_to_escrow_impl(from))
+        }
+
+        /// private helper function running `seize_to_escrow` under the reentrancy guard
+        fn seize_to_escrow_impl(&mut self, from: AccountId) -> Result<Balance> {
+            self.ensure_owner()?;
+            if !self.is_blacklisted_impl(&from) {
+                return Err(Error::NotBlacklisted);
+            }
+            // Tokens under an open dispute stay available to reverse it
+            let amount = self
+                .balance_of_impl(&from)
+                .saturating_sub(self.disputed.get(from).unwrap_or_default());
+            if amount == 0 {
+                return Err(Error::InsufficientBalance {
+                    required: 1,
+                    available: 0,
+                });
+            }
+
+            let escrow = self.env().account_id();
+            self.move_tokens(&from, &escrow, amount);
+            let (held, _) = self.seized_of(from).unwrap_or_default();
+            let appeal_ends = self.env().block_timestamp() + self.appeal_period;
+            self.seized.insert(from, &(held + amount, appeal_ends));
+            Ok(amount)
+        }
+
+        /// Returns `(amount, appeal window end)` of the funds seized from `account`, if any.
+        #[ink(message)]
+        pub fn seized_of(&self, account: AccountId) -> Option<(Balance, Timestamp)> {
+            self.seized.get(account)
+        }
+
+        /// Sets how long blacklisted accounts have to appeal future seizures.
+        #[ink(message)]
+        pub fn set_appeal_period(&mut self, period: Timestamp) -> Result<()> {
+            self.ensure_owner()?;
+            self.appeal_period = period;
+            Ok(())
+        }
+
+        /// Returns the funds seized from `account` to it after a successful appeal.
+        ///
+        /// Only possible while the appeal window is open.
+        #[ink(message)]
+        pub fn return_seized(&mut self, account: AccountId) -> Result<()> {
+            self.non_reentrant(|contract| contract.return_seized_impl(account))
+        }
+
+        /// private helper function running `return_seized` under the reentrancy guard
+        fn return_seized_impl(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            let (amount, appeal_ends) = self.seized_of(account).ok_or(Error::NothingSeized)?;
+            if self.env().block_timestamp() >= appeal_ends {
+                return Err(Error::AppealWindowClosed);
+            }
+
+            self.seized.remove(account);
+            let escrow = self.env().account_id();
+            self.move_tokens(&escrow, &account, amount);
+            Ok(())
+        }
+
+        /// Sends the funds seized from `account` to `to`, e.g. a recovery address,
+        /// once the appeal window has closed.
+        #[ink(message)]
+        pub fn release_seized(&mut self, account: AccountId, to: AccountId) -> Result<()> {
+            self.non_reentrant(|contract| contract.release_seized_impl(account, to))
+        }
+
+        /// private helper function running `release_seized` under the reentrancy guard
+        fn release_seized_impl(&mut self, account: AccountId, to: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            let (amount, appeal_ends) = self.seized_of(account).ok_or(Error::NothingSeized)?;
+            if self.env().block_timestamp() < appeal_ends {
+                return Err(Error::AppealPending);
+            }
+            let escrow = self.env().account_id();
+            self.check_transfer(&escrow, &to, amount)?;
+
+            self.seized.remove(account);
+            self.move_tokens(&escrow, &to, amount);
+            Ok(())
+        }
+
+        /// Consolidates `from` into `to`: its balance, unclaimed dividends, points, debt,
+        /// staked shares and vesting schedules.
+        ///
+        /// Callable by `from` itself or by the owner on its behalf. The balance must
+        /// pass the same checks as a transfer and may not be locked, but no transfer
+        /// fee is charged. If `blacklist_from` is set, `from` is permanently blacklisted afterwards.
+        #[ink(message)]
+        pub fn merge_accounts(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            blacklist_from: bool,
+        ) -> Result<()> {
+            self.non_reentrant(|contract| contract.merge_accounts_impl(from, to, blacklist_from))
+        }
+
+        /// private helper function running `merge_accounts` under the reentrancy guard
+        fn merge_accounts_impl(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            blacklist_from: bool,
+        ) -> Result<()> {
+            self.ensure_not_paused()?;
+            if self.env().caller() != from {
+                self.ensure_owner()?;
+            }
+            if from == to {
+                return Ok(());
+            }
+            let balance = self.balance_of_impl(&from);
+            self.check_transfer(&from, &to, balance)?;
+            if self.unlocked_balance_of(&from) < balance {
+                return Err(Error::AmountLocked);
+            }
+
+            self.move_tokens(&from, &to, balance);
+
+            let dividends = self.pending_dividends.get(from).unwrap_or_default();
+            if dividends > 0 {
+                self.pending_dividends.remove(from);
+                let pending = self.pending_dividends.get(to).unwrap_or_default();
+                self.pending_dividends.insert(to, &(pending + dividends));
+            }
+            let points = self.points_of(from);
+            if points > 0 {
+                self.points.remove(from);
+                self.points.insert(to, &(self.points_of(to) + points));
+            }
+            let debt = self.debt_of(from);
+            if debt > 0 {
+                self.debt.remove(from);
+                self.debt.insert(to, &(self.debt_of(to) + debt));
+            }
+            let shares = self.shares_of(from);
+            if shares > 0 {
+                self.staked_shares.remove(from);
+                self.staked_shares
+                    .insert(to, &(self.shares_of(to) + shares));
+            }
+            let schedules = self.vesting_schedule_count.get(from).unwrap_or_default();
+            let first = self.vesting_schedule_count.get(to).unwrap_or_default();
+            for index in 0..schedules {
+                if let Some(schedule) = self.vesting_schedules.get((from, index)) {
+                    self.vesting_schedules.remove((from, index));
+                    self.vesting_schedules
+                        .insert((to, first + index), &schedule);
+                }
+            }
+            if schedules > 0 {
+                self.vesting_schedule_count.remove(from);
+                self.vesting_schedule_count.insert(to, &(first + schedules));
+            }
+
+            if blacklist_from {
+                self.blacklisted.insert(from, &None::<Timestamp>);
+            }
+            Ok(())
+        }
+
+        /// Returns `true` if contracts running `code_hash` may receive tokens in strict mode.
+        #[ink(message)]
+        pub fn is_code_hash_allowed(&self, code_hash: Hash) -> bool {
+            self.allowed_code_hashes.contains(code_hash)
+        }
+
+        /// Adds or removes `code_hash` from the allowlist of vetted contract code.
+        #[ink(message)]
+        pub fn set_code_hash_allowed(&mut self, code_hash: Hash, allowed: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if allowed {
+                self.allowed_code_hashes.insert(code_hash, &());
+            } else {
+                self.allowed_code_hashes.remove(code_hash);
+            }
+            Ok(())
+        }
+
+        /// Turns strict mode on or off. While on, transfers to contracts whose code
+        /// hash is not allowlisted fail with `CodeHashNotAllowed`; plain accounts are unaffected.
+        #[ink(message)]
+        pub fn set_code_hash_strict(&mut self, strict: bool) -> Result<()> {
+            self.ensure_owner()?;
+            self.code_hash_strict = strict;
+            Ok(())
+        }
+
+        /// Returns the version of the storage layout.
+        #[ink(message)]
+        pub fn storage_version(&self) -> u32 {
+            self.storage_version
+        }
+
+        /// Replaces the contract's code with the code uploaded under `code_hash`.
+        ///
+        /// Storage and balances stay as they are. The new code takes over after
+        /// this call; if it changes the storage layout, the owner must call its
+        /// `migrate` before anything else. Goes through the multisig once it needs
+        /// more than one confirmation.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_no_multisig()?;
+            self.upgrade_impl(code_hash)
+        }
+
+        /// private helper function to switch to the code at `code_hash` and emit the `CodeUpgraded`
+        fn upgrade_impl(&mut self, code_hash: Hash) -> Result<()> {
+            let mut bytes = [0u8; 32];
+            bytes.copy_from_slice(code_hash.as_ref());
+            ink_env::set_code_hash(&bytes).map_err(|_| Error::UpgradeFailed)?;
+            self.env().emit_event(CodeUpgraded { code_hash });
+            Ok(())
+        }
+
+        /// Runs the storage migrations from the stored layout up to `STORAGE_VERSION`, once.
+        ///
+        /// Returns the new storage version.
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<u32> {
+            self.ensure_owner()?;
+            if self.storage_version >= STORAGE_VERSION {
+                return Err(Error::AlreadyMigrated);
+            }
+            // Each layout change adds a step converting from the version before it
+            // here; the first layout has nothing to convert.
+            self.storage_version = STORAGE_VERSION;
+            Ok(self.storage_version)
+        }
+
+        /// Returns how many tokens of the PSP22 contract `token` this contract holds.
+        #[ink(message)]
+        pub fn treasury_balance(&self, token: AccountId) -> Result<Balance> {
+            build_call::<Environment>()
+                .call_type(Call::new().callee(token).gas_limit(0))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(PSP22_BALANCE_OF_SELECTOR))
+                        .push_arg(self.env().account_id()),
+                )
+                .returns::<Balance>()
+                .fire()
+                .map_err(|_| Error::TreasuryCallFailed)
+        }
+
+        /// Sends `amount` of the PSP22 tokens `token` held by this contract to `to`.
+        #[ink(message)]
+        pub fn treasury_withdraw(
+            &mut self,
+            token: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<()> {
+            self.non_reentrant(|contract| contract.treasury_withdraw_impl(token, to, amount))
+        }
+
+        /// private helper function running `treasury_withdraw` under the reentrancy guard
+        fn treasury_withdraw_impl(
+            &mut self,
+            token: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            // The token's own error type is opaque here, only its variant index is decoded
+            let result = build_call::<Environment>()
+                .call_type(Call::new().callee(token).gas_limit(0))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(PSP22_TRANSFER_SELECTOR))
+                        .push_arg(to)
+                        .push_arg(amount)
+                        .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<core::result::Result<(), u8>>()
+                .fire();
+            match result {
+                Ok(Ok(())) => Ok(()),
+                _ => Err(Error::TreasuryCallFailed),
+            }
+        }
+
+        /// Opts the caller in or out of receiving transfers above the large-transfer threshold.
+        #[ink(message)]
+        pub fn set_large_transfer_optin(&mut self, opted_in: bool) -> Result<()> {
+            let caller = self.env().caller();
+            if opted_in {
+                self.large_transfer_optin.insert(caller, &());
+            } else {
+                self.large_transfer_optin.remove(caller);
+            }
+            Ok(())
+        }
+
+        /// Returns `true` if `account` accepts transfers above the large-transfer threshold.
+        #[ink(message)]
+        pub fn accepts_large_transfers(&self, account: AccountId) -> bool {
+            self.large_transfer_optin.contains(account)
+        }
+
+        /// Sets the value above which recipients must have opted in, or `None` to disable the check.
+        #[ink(message)]
+        pub fn set_large_transfer_threshold(&mut self, threshold: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            self.large_transfer_threshold = threshold;
+            Ok(())
+        }
+
+        /// Returns the balance recipients must already hold, if the gate is on.
+        #[ink(message)]
+        pub fn min_recipient_balance(&self) -> Option<Balance> {
+            self.min_recipient_balance
+        }
+
+        /// Requires recipients to hold at least `min` before a transfer, or `None` to disable the gate.
+        ///
+        /// The incoming amount does not count toward the minimum.
+        #[ink(message)]
+        pub fn set_min_recipient_balance(&mut self, min: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            self.min_recipient_balance = min;
+            Ok(())
+        }
+
+        /// Returns the region `account` is tagged with, if any.
+        #[ink(message)]
+        pub fn region_of(&self, account: AccountId) -> Option<u16> {
+            self.region_of.get(account)
+        }
+
+        /// Tags `account` with `region`, or removes its tag with `None`.
+        #[ink(message)]
+        pub fn set_region(&mut self, account: AccountId, region: Option<u16>) -> Result<()> {
+            self.ensure_owner()?;
+            match region {
+                Some(region) => self.region_of.insert(account, &region),
+                None => self.region_of.remove(account),
+            }
+            Ok(())
+        }
+
+        /// Returns whether tagged accounts in `from` may send to tagged accounts in `to`.
+        ///
+        /// Transfers within one region are always allowed.
+        #[ink(message)]
+        pub fn is_region_pair_allowed(&self, from: u16, to: u16) -> bool {
+            from == to || self.allowed_region_pairs.contains((from, to))
+        }
+
+        /// Allows or disallows transfers from region `from` to region `to`; the
+        /// reverse direction is configured separately.
+        #[ink(message)]
+        pub fn set_region_pair_allowed(&mut self, from: u16, to: u16, allowed: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if allowed {
+                self.allowed_region_pairs.insert((from, to), &());
+            } else {
+                self.allowed_region_pairs.remove((from, to));
+            }
+            Ok(())
+        }
+
+        /// Returns whether transfers involving an untagged account are blocked.
+        #[ink(message)]
+        pub fn untagged_restricted(&self) -> bool {
+            self.untagged_restricted
+        }
+
+        /// Blocks or allows transfers where the sender or recipient has no region tag.
+        ///
+        /// Untagged accounts are unrestricted by default.
+        #[ink(message)]
+        pub fn set_untagged_restricted(&mut self, restricted: bool) -> Result<()> {
+            self.ensure_owner()?;
+            self.untagged_restricted = restricted;
+            Ok(())
+        }
+
+        /// Returns the current launch phase.
+        #[ink(message)]
+        pub fn launch_phase(&self) -> u8 {
+            self.launch_phase
+        }
+
+        /// Moves to the next launch phase and returns it; phases never go back.
+        #[ink(message)]
+        pub fn advance_launch_phase(&mut self) -> Result<u8> {
+            self.ensure_owner()?;
+            if self.launch_phase >= LAUNCH_PHASE_OPEN {
+                return Err(Error::InvalidLaunchPhase);
+            }
+            self.launch_phase += 1;
+            Ok(self.launch_phase)
+        }
+
+        /// Returns `(transfer cap, whether account is allowlisted)` for the capped launch phase.
+        #[ink(message)]
+        pub fn launch_limits(&self, account: AccountId) -> (Balance, bool) {
+            (
+                self.launch_transfer_cap,
+                self.launch_allowlist.contains(account),
+            )
+        }
+
+        /// Sets the most a single transfer may move in the capped launch phase.
+        #[ink(message)]
+        pub fn set_launch_transfer_cap(&mut self, cap: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            self.launch_transfer_cap = cap;
+            Ok(())
+        }
+
+        /// Exempts `account` from the transfer cap in the capped launch phase, or revokes it.
+        #[ink(message)]
+        pub fn set_launch_allowlisted(&mut self, account: AccountId, allowed: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if allowed {
+                self.launch_allowlist.insert(account, &());
+            } else {
+                self.launch_allowlist.remove(account);
+            }
+            Ok(())
+        }
+
+        /// private helper function to check that the launch phase allows `from` to send `value`
+        fn check_launch_phase(&self, from: &AccountId, value: Balance) -> Result<()> {
+            if !self.feature_enabled(FEATURE_LAUNCH) {
+                return Ok(());
+            }
+            // The owner seeds the launch and the contract-held pool keeps paying out
+            if self.owner == Some(*from) || *from == self.env().account_id() {
+                return Ok(());
+            }
+            let allowed = match self.launch_phase {
+                LAUNCH_PHASE_CLOSED => false,
+                LAUNCH_PHASE_CAPPED => {
+                    value <= self.launch_transfer_cap || self.launch_allowlist.contains(from)
+                }
+                _ => true,
+            };
+            if !allowed {
+                return Err(Error::LaunchRestricted);
+            }
+            Ok(())
+        }
+
+        /// Returns the part of `account`'s balance that cannot be transferred.
+        #[ink(message)]
+        pub fn locked_amount_of(&self, account: AccountId) -> Balance {
+            self.locked_amount.get(account).unwrap_or_default()
+        }
+
+        /// Locks `amount` of `account`'s balance against transfers, e.g. as collateral, or unlocks it with 0.
+        ///
+        /// The rest of the balance stays transferable. The lock may exceed the
+        /// current balance, in which case incoming tokens are locked until it is covered.
+        #[ink(message)]
+        pub fn set_locked_amount(&mut self, account: AccountId, amount: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            if amount == 0 {
+                self.locked_amount.remove(account);
+            } else {
+                self.locked_amount.insert(account, &amount);
+            }
+            Ok(())
+        }
+
+        /// private helper function to get the part of `account`'s balance that is free to transfer
+        fn unlocked_balance_of(&self, account: &AccountId) -> Balance {
+            self.balance_of_impl(account)
+                .saturating_sub(self.total_locked(account))
+        }
+
+        /// private helper function to get the part of `account`'s balance locked by the owner or open disputes
+        fn total_locked(&self, account: &AccountId) -> Balance {
+            self.locked_amount_of(*account)
+                .saturating_add(self.disputed.get(account).unwrap_or_default())
+        }
+
+        /// Returns the group `account` belongs to, 0 if it is ungrouped.
+        #[ink(message)]
+        pub fn group_of(&self, account: AccountId) -> u32 {
+            self.group_of.get(account).unwrap_or_default()
+        }
+
+        /// Puts `account` in `group`, or takes it out of any group with 0.
+        ///
+        /// While `FEATURE_GROUPS` is on, accounts in different groups cannot
+        /// transfer to each other. Ungrouped accounts may transfer with anyone.
+        #[ink(message)]
+        pub fn set_group(&mut self, account: AccountId, group: u32) -> Result<()> {
+            self.ensure_owner()?;
+            if group == 0 {
+                self.group_of.remove(account);
+            } else {
+                self.group_of.insert(account, &group);
+            }
+            Ok(())
+        }
+
+        /// private helper function to check that `from` and `to` are not in different groups
+        fn check_groups(&self, from: &AccountId, to: &AccountId) -> Result<()> {
+            if !self.feature_enabled(FEATURE_GROUPS) {
+                return Ok(());
+            }
+            let (from_group, to_group) = (self.group_of(*from), self.group_of(*to));
+            if from_group != 0 && to_group != 0 && from_group != to_group {
+                return Err(Error::CrossGroupTransfer);
+            }
+            Ok(())
+        }
+
+        /// Returns the current terms-of-service version.
+        #[ink(message)]
+        pub fn tos_version(&self) -> u16 {
+            self.tos_version
+        }
+
+        /// Returns the latest terms-of-service version `account` accepted, 0 if none.
+        #[ink(message)]
+        pub fn accepted_tos(&self, account: AccountId) -> u16 {
+            self.accepted_tos.get(account).unwrap_or_default()
+        }
+
+        /// Accepts terms-of-service `version` for the caller.
+        ///
+        /// Only the current version can be accepted.
+        #[ink(message)]
+        pub fn accept_tos(&mut self, version: u16) -> Result<()> {
+            if version != self.tos_version {
+                return Err(Error::UnknownTosVersion);
+            }
+            let caller = self.env().caller();
+            self.accepted_tos.insert(caller, &version);
+            Ok(())
+        }
+
+        /// Publishes a new terms-of-service version and returns it.
+        ///
+        /// While `FEATURE_TOS` is on, accounts must accept it before they transact again.
+        #[ink(message)]
+        pub fn bump_tos_version(&mut self) -> Result<u16> {
+            self.ensure_owner()?;
+            self.tos_version = self.tos_version.checked_add(1).ok_or(Error::Overflow)?;
+            Ok(self.tos_version)
+        }
+
+        /// private helper function to check both parties accepted the current terms of service
+        fn check_tos(&self, from: &AccountId, to: &AccountId) -> Result<()> {
+            if !self.feature_enabled(FEATURE_TOS) {
+                return Ok(());
+            }
+            let contract = self.env().account_id();
+            // Payouts from the contract-held pool are not gated
+            for account in [from, to] {
+                if *account != contract && self.accepted_tos(*account) < self.tos_version {
+                    return Err(Error::TosNotAccepted);
+                }
+            }
+            Ok(())
+        }
+
+        /// Returns `(count, limit)` of transfers from `from` to `to`, if the pair is limited.
+        #[ink(message)]
+        pub fn pair_transfers(&self, from: AccountId, to: AccountId) -> Option<(u32, u32)> {
+            self.pair_transfer_limit.get((from, to)).map(|limit| {
+                let count = self.pair_transfer_count.get((from, to)).unwrap_or_default();
+                (count, limit)
+            })
+        }
+
+        /// Allows at most `limit` transfers from `from` to `to`, or `None` to lift the limit.
+        ///
+        /// Transfers are only counted while the pair is limited.
+        #[ink(message)]
+        pub fn set_pair_transfer_limit(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            limit: Option<u32>,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            match limit {
+                Some(limit) => self.pair_transfer_limit.insert((from, to), &limit),
+                None => {
+                    self.pair_transfer_limit.remove((from, to));
+                    self.pair_transfer_count.remove((from, to));
+                }
+            }
+            Ok(())
+        }
+
+        /// Returns how much `account` has moved in the current activity window.
+        #[ink(message)]
+        pub fn daily_activity_of(&self, account: AccountId) -> Balance {
+            self.activity_today(&account)
+        }
+
+        /// Caps how much an account may move per activity window, or `None` to lift the cap.
+        ///
+        /// The owner and the contract account are exempt.
+        #[ink(message)]
+        pub fn set_daily_activity_cap(&mut self, cap: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            self.daily_activity_cap = cap;
+            Ok(())
+        }
+
+        /// Sets the length of the window the activity cap applies to; defaults to one day.
+        #[ink(message)]
+        pub fn set_activity_window(&mut self, window: Timestamp) -> Result<()> {
+            self.ensure_owner()?;
+            if window == 0 {
+                return Err(Error::InvalidActivityWindow);
+            }
+            self.activity_window = window;
+            Ok(())
+        }
+
+        /// private helper function to get the index of the current activity window
+        fn activity_window_index(&self) -> u64 {
+            self.env().block_timestamp() / self.activity_window
+        }
+
+        /// private helper function to get what `account` moved in the current activity window
+        fn activity_today(&self, account: &AccountId) -> Balance {
+            match self.daily_activity.get(account) {
+                Some((window, amount)) if window == self.activity_window_index() => amount,
+                _ => 0,
+            }
+        }
+
+        /// private helper function to check that `account` may move another `value` in this window
+        fn check_activity(&self, account: &AccountId, value: Balance) -> Result<()> {
+            if let Some(cap) = self.daily_activity_cap {
+                if self.activity_today(account) + value > cap && !self.is_activity_exempt(account) {
+                    return Err(Error::DailyActivityCapExceeded);
+                }
+            }
+            Ok(())
+        }
+
+        /// private helper function to check that a transfer from `from` to `to` may cross their regions
+        fn check_regions(&self, from: &AccountId, to: &AccountId) -> Result<()> {
+            // Payouts from the contract-held pool are not restricted
+            if *from == self.env().account_id() {
+                return Ok(());
+            }
+            let allowed = match (self.region_of.get(from), self.region_of.get(to)) {
+                (Some(from_region), Some(to_region)) => {
+                    from_region == to_region
+                        || self.allowed_region_pairs.contains((from_region, to_region))
+                }
+                _ => !self.untagged_restricted,
+            };
+            if !allowed {
+                return Err(Error::RegionRestricted);
+            }
+            Ok(())
+        }
+
+        /// private helper function to check that `from` may send `transfers` more transfers to `to`
+        fn check_pair_limit(&self, from: &AccountId, to: &AccountId, transfers: u32) -> Result<()> {
+            if let Some(limit) = self.pair_transfer_limit.get((from, to)) {
+                let count = self.pair_transfer_count.get((from, to)).unwrap_or_default();
+                if count + transfers > limit {
+                    return Err(Error::PairTransferLimitReached);
+                }
+            }
+            Ok(())
+        }
+
+        /// private helper function to count a transfer between a limited pair of accounts
+        fn record_pair_transfer(&mut self, from: &AccountId, to: &AccountId) {
+            if self.pair_transfer_limit.contains((from, to)) {
+                let count = self.pair_transfer_count.get((from, to)).unwrap_or_default();
+                self.pair_transfer_count.insert((from, to), &(count + 1));
+            }
+        }
+
+        /// private helper function to check whether `account` is exempt from the activity cap
+        fn is_activity_exempt(&self, account: &AccountId) -> bool {
+            self.owner == Some(*account) || *account == self.env().account_id()
+        }
+
+        /// private helper function to add `value` to `account`'s activity in the current window
+        fn record_activity(&mut self, account: &AccountId, value: Balance) {
+            if self.daily_activity_cap.is_none() || self.is_activity_exempt(account) {
+                return;
+            }
+            let window = self.activity_window_index();
+            let amount = self.activity_today(account) + value;
+            self.daily_activity.insert(account, &(window, amount));
+        }
+
+        /// Returns whether non-essential events are emitted.
+        #[ink(message)]
+        pub fn emit_events(&self) -> bool {
+            self.emit_events
+        }
+
+        /// Turns non-essential events on or off, e.g. where event costs add up.
+        ///
+        /// When off, `transfer_from` no longer emits the `Approval` for the reduced
+        /// allowance and self-transfers emit no `Transfer`. All other `Transfer`,
+        /// `Approval` and `OwnershipTransferred` events are always emitted.
+        #[ink(message)]
+        pub fn set_emit_events(&mut self, enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            self.emit_events = enabled;
+            Ok(())
+        }
+
+        /// Returns whether the contract is paused.
+        #[ink(message)]
+        pub fn paused(&self) -> bool {
+            self.paused
+        }
+
+        /// Halts all transfers, mints, burns and approvals, e.g. during incident response.
+        ///
+        /// Callable by the owner and `Pauser`s until the multisig needs more than
+        /// one confirmation. Read-only messages and owner configuration stay available.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<()> {
+            self.ensure_role(Role::Pauser)?;
+            self.ensure_no_multisig()?;
+            self.set_paused(true)
+        }
+
+        /// Lifts a pause; callable by the owner and `Pauser`s.
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<()> {
+            self.ensure_role(Role::Pauser)?;
+            self.ensure_no_multisig()?;
+            self.set_paused(false)
+        }
+
+        /// private helper function to pause or unpause the contract and emit the event
+        fn set_paused(&mut self, paused: bool) -> Result<()> {
+            ensure_compiled_in(cfg!(feature = "pausable"))?;
+            self.paused = paused;
+            let account = self.env().caller();
+            if paused {
+                self.env().emit_event(Paused { account });
+            } else {
+                self.env().emit_event(Unpaused { account });
+            }
+            Ok(())
+        }
+
+        /// Returns whether the emergency global freeze is on.
+        #[ink(message)]
+        pub fn global_freeze(&self) -> bool {
+            self.global_freeze
+        }
+
+        /// Freezes or unfreezes every balance at once.
+        ///
+        /// While frozen, only thawed accounts can act, send or receive tokens,
+        /// so funds can be recovered in an orderly way; unlike `pause`, this is
+        /// available in every build.
+        #[ink(message)]
+        pub fn set_global_freeze(&mut self, frozen: bool) -> Result<()> {
+            self.ensure_owner()?;
+            self.global_freeze = frozen;
+            Ok(())
+        }
+
+        /// Returns `true` if `account` is exempt from the global freeze.
+        #[ink(message)]
+        pub fn is_thawed(&self, account: AccountId) -> bool {
+            self.thawed.contains(account)
+        }
+
+        /// Exempts `account` from the global freeze until the thaw is revoked.
+        #[ink(message)]
+        pub fn thaw(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.thawed.insert(account, &());
+            Ok(())
+        }
+
+        /// Puts `account` back under the global freeze.
+        #[ink(message)]
+        pub fn revoke_thaw(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.thawed.remove(account);
+            Ok(())
+        }
+
+        /// private helper function to reject `account` during a global freeze unless it is thawed
+        fn ensure_thawed(&self, account: &AccountId) -> Result<()> {
+            if self.global_freeze && !self.thawed.contains(account) {
+                return Err(Error::GloballyFrozen);
+            }
+            Ok(())
+        }
+
+        /// Returns `(threshold in basis points, window)` of the circuit breaker, if armed.
+        #[ink(message)]
+        pub fn circuit_breaker(&self) -> Option<(u32, Timestamp)> {
+            self.breaker_threshold_bps
+                .map(|threshold| (threshold, self.breaker_window))
+        }
+
+        /// Auto-pauses the contract once the total supply moves by more than
+        /// `threshold_bps` within `window`, or disarms the breaker with `None`.
+        #[ink(message)]
+        pub fn set_circuit_breaker(&mut self, config: Option<(u32, Timestamp)>) -> Result<()> {
+            self.ensure_owner()?;
+            match config {
+                Some((threshold, window)) if threshold == 0 || window == 0 => {
+                    return Err(Error::InvalidCircuitBreaker)
+                }
+                Some((threshold, window)) => {
+                    self.breaker_threshold_bps = Some(threshold);
+                    self.breaker_window = window;
+                }
+                None => self.breaker_threshold_bps = None,
+            }
+            self.breaker_window_start = (self.env().block_timestamp(), self.total_supply);
+            Ok(())
+        }
+
+        /// Restarts the circuit-breaker window at the current supply and lifts a pause it caused.
+        #[ink(message)]
+        pub fn reset_circuit_breaker(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            self.breaker_window_start = (self.env().block_timestamp(), self.total_supply);
+            if self.breaker_tripped {
+                self.breaker_tripped = false;
+                self.paused = false;
+                self.env().emit_event(Unpaused {
+                    account: self.env().caller(),
+                });
+            }
+            Ok(())
+        }
+
+        /// private helper function to start a new circuit-breaker window before a supply change once the old one ended
+        fn start_breaker_window(&mut self) {
+            let (start, _) = self.breaker_window_start;
+            let now = self.env().block_timestamp();
+            if self.breaker_threshold_bps.is_some()
+                && now >= start.saturating_add(self.breaker_window)
+            {
+                self.breaker_window_start = (now, self.total_supply);
+            }
+        }
+
+        /// private helper function to auto-pause once the supply swung past the breaker threshold
+        fn check_circuit_breaker(&mut self) {
+            let Some(threshold) = self.breaker_threshold_bps else {
+                return;
+            };
+            let (_, supply_at_start) = self.breaker_window_start;
+            if self.paused || supply_at_start == 0 {
+                return;
+            }
+            let swing = self.total_supply.abs_diff(supply_at_start);
+            let limit = supply_at_start.saturating_mul(Balance::from(threshold))
+                / Balance::from(BPS_DENOMINATOR);
+            if swing > limit {
+                self.paused = true;
+                self.breaker_tripped = true;
+                self.env().emit_event(CircuitBreakerTripped {
+                    supply_at_window_start: supply_at_start,
+                    supply: self.total_supply,
+                });
+            }
+        }
+
+        /// Returns the safety-relevant state in one read, for monitoring and alerting.
+        ///
+        /// Backing is measured against the wind-down rate while winding down, else
+        /// the redemption rate; with neither set there is nothing to back.
+        #[ink(message)]
+        pub fn health(&self) -> Health {
+            let reserves = self.native_reserves();
+            let backing_ratio_ok = match self.wind_down_rate.or(self.redemption_rate) {
+                Some(rate) => self
+                    .total_supply
+                    .checked_mul(rate)
+                    .is_some_and(|owed| owed <= reserves),
+                None => true,
+            };
+            let minting_disabled = !cfg!(feature = "mintable")
+                || self.owner.is_none()
+                || self.cap.is_some_and(|cap| self.total_supply >= cap);
+            Health {
+                paused: self.paused,
+                backing_ratio_ok,
+                reserves,
+                minting_disabled,
+            }
+        }
+
+        /// Returns whether the optional behavior `feature` is switched on.
+        #[ink(message)]
+        pub fn feature_enabled(&self, feature: [u8; 4]) -> bool {
+            self.feature_flags.get(feature).unwrap_or_default()
+        }
+
+        /// Switches the optional behavior `feature` on or off, e.g. `FEATURE_FEES`.
+        ///
+        /// Features start off, so a deployment can launch minimal and enable them gradually.
+        #[ink(message)]
+        pub fn set_feature_flag(&mut self, feature: [u8; 4], enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            self.feature_flags.insert(feature, &enabled);
+            Ok(())
+        }
+
+        /// Returns the flat fee charged on every transfer.
+        #[ink(message)]
+        pub fn flat_fee(&self) -> Balance {
+            self.flat_fee
+        }
+
+        /// Sets the flat fee deducted from every transfer and sent to the treasury.
+        ///
+        /// Only charged while `FEATURE_FEES` is on. Transfers of less than the fee
+        /// are rejected with `AmountBelowFee`.
+        #[ink(message)]
+        pub fn set_flat_fee(&mut self, fee: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            self.flat_fee = fee;
+            Ok(())
+        }
+
+        /// Returns the fee charged on top of the flat fee, in basis points of the amount sent.
+        #[ink(message)]
+        pub fn fee_bps(&self) -> u16 {
+            self.fee_bps
+        }
+
+        /// Charges `bps` basis points of every transfer's amount on top of the flat fee.
+        ///
+        /// Like the flat fee, it is only charged while `FEATURE_FEES` is on and
+        /// goes through the same burn split and fee destinations.
+        #[ink(message)]
+        pub fn set_fee_bps(&mut self, bps: u16) -> Result<()> {
+            self.ensure_owner()?;
+            if u32::from(bps) > BPS_DENOMINATOR {
+                return Err(Error::InvalidFeeRate);
+            }
+            self.fee_bps = bps;
+            Ok(())
+        }
+
+        /// Returns `true` if `account` sends tokens without paying transfer fees.
+        #[ink(message)]
+        pub fn is_fee_exempt(&self, account: AccountId) -> bool {
+            self.fee_exempt.contains(account)
+        }
+
+        /// Lets `account` send tokens without paying transfer fees, or revokes that.
+        #[ink(message)]
+        pub fn set_fee_exempt(&mut self, account: AccountId, exempt: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if exempt {
+                self.fee_exempt.insert(account, &());
+            } else {
+                self.fee_exempt.remove(account);
+            }
+            Ok(())
+        }
+
+        /// Returns the portion of each collected fee that is burned, in basis points.
+        #[ink(message)]
+        pub fn fee_split_bps(&self) -> u16 {
+            self.fee_split_bps
+        }
+
+        /// Burns `burn_bps` basis points of every collected fee and sends the rest to the treasury.
+        #[ink(message)]
+        pub fn set_fee_split_bps(&mut self, burn_bps: u16) -> Result<()> {
+            self.ensure_owner()?;
+            if u32::from(burn_bps) > BPS_DENOMINATOR {
+                return Err(Error::InvalidFeeSplit);
+            }
+            self.fee_split_bps = burn_bps;
+            Ok(())
+        }
+
+        /// Returns the account collecting transfer fees.
+        #[ink(message)]
+        pub fn treasury(&self) -> AccountId {
+            self.treasury
+        }
+
+        /// Sets the account collecting transfer fees.
+        #[ink(message)]
+        pub fn set_treasury(&mut self, treasury: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.treasury = treasury;
+            Ok(())
+        }
+
+        /// Returns the `(start, end)` windows in which no fee is charged.
+        #[ink(message)]
+        pub fn fee_holidays(&self) -> Vec<(Timestamp, Timestamp)> {
+            self.fee_holidays.clone()
+        }
+
+        /// Waives the flat fee from `start` until just before `end` for each of `windows`,
+        /// replacing any earlier windows.
+        #[ink(message)]
+        pub fn set_fee_holidays(&mut self, windows: Vec<(Timestamp, Timestamp)>) -> Result<()> {
+            self.ensure_owner()?;
+            if windows.iter().any(|(start, end)| start >= end) {
+                return Err(Error::InvalidFeeHoliday);
+            }
+            self.fee_holidays = windows;
+            Ok(())
+        }
+
+        /// private helper function to check whether the current block falls in a fee holiday
+        fn is_fee_holiday(&self) -> bool {
+            let now = self.env().block_timestamp();
+            self.fee_holidays
+                .iter()
+                .any(|(start, end)| (*start..*end).contains(&now))
+        }
+
+        /// Returns the destinations sharing the unburned fee and their basis-point weights.
+        ///
+        /// Empty while all of it goes to the treasury.
+        #[ink(message)]
+        pub fn fee_destinations(&self) -> Vec<(AccountId, u16)> {
+            self.fee_destinations.clone()
+        }
+
+        /// Routes the unburned part of every fee to `destinations` by basis-point weight,
+        /// or back to the treasury with an empty list.
+        ///
+        /// The weights must sum to exactly 10000.
+        #[ink(message)]
+        pub fn set_fee_destinations(&mut self, destinations: Vec<(AccountId, u16)>) -> Result<()> {
+            self.ensure_owner()?;
+            let weights: u32 = destinations
+                .iter()
+                .map(|(_, weight)| u32::from(*weight))
+                .sum();
+            if !destinations.is_empty() && weights != BPS_DENOMINATOR {
+                return Err(Error::InvalidWeights);
+            }
+            self.fee_destinations = destinations;
+            Ok(())
+        }
+
+        /// Returns the partner NFT contract and the fee discount, in basis points, its holders get.
+        #[ink(message)]
+        pub fn nft_fee_discount(&self) -> (Option<AccountId>, u32) {
+            (self.nft_contract, self.nft_fee_discount)
+        }
+
+        /// Gives holders of `nft_contract` NFTs a `discount` on the flat fee, in basis points.
+        ///
+        /// Holdings are queried live on every fee computation; pass `None` to stop.
+        #[ink(message)]
+        pub fn set_nft_fee_discount(
+            &mut self,
+            nft_contract: Option<AccountId>,
+            discount: u32,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            if discount > BPS_DENOMINATOR {
+                return Err(Error::InvalidFeeDiscount);
+            }
+            self.nft_contract = nft_contract;
+            self.nft_fee_discount = discount;
+            Ok(())
+        }
+
+        /// Returns the transfer fees `account` has paid since it last claimed a rebate.
+        #[ink(message)]
+        pub fn fees_paid_of(&self, account: AccountId) -> Balance {
+            self.fees_paid.get(account).unwrap_or_default()
+        }
+
+        /// Returns the portion of paid fees `claim_rebate` mints back, in basis points.
+        #[ink(message)]
+        pub fn rebate_rate(&self) -> u32 {
+            self.rebate_bps
+        }
+
+        /// Rebates `rebate_bps` basis points of the fees each account pays.
+        #[ink(message)]
+        pub fn set_rebate_rate(&mut self, rebate_bps: u32) -> Result<()> {
+            self.ensure_owner()?;
+            if rebate_bps > BPS_DENOMINATOR {
+                return Err(Error::InvalidRebateRate);
+            }
+            self.rebate_bps = rebate_bps;
+            Ok(())
+        }
+
+        /// Mints the rebate on the fees the caller paid so far and returns it.
+        ///
+        /// Resets the caller's paid fees, unless they are still too small to earn a rebate.
+        #[ink(message)]
+        pub fn claim_rebate(&mut self) -> Result<Balance> {
+            self.non_reentrant(|contract| contract.claim_rebate_impl())
+        }
+
+        /// private helper function running `claim_rebate` under the reentrancy guard
+        fn claim_rebate_impl(&mut self) -> Result<Balance> {
+            ensure_compiled_in(cfg!(feature = "mintable"))?;
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let rebate = self.fees_paid_of(caller) * Balance::from(self.rebate_bps)
+                / Balance::from(BPS_DENOMINATOR);
+            if rebate == 0 {
+                return Ok(0);
+            }
+            self.check_mint(&caller, rebate)?;
+
+            self.fees_paid.remove(caller);
+            self.mint_impl(&caller, rebate);
+            Ok(rebate)
+        }
+
+        /// Returns the raffle's share of the unburned fee, in basis points, and the prize pool.
+        #[ink(message)]
+        pub fn raffle(&self) -> (u16, Balance) {
+            (self.raffle_fee_bps, self.raffle_pool)
+        }
+
+        /// Pays `share_bps` basis points of every unburned fee into the raffle prize pool.
+        #[ink(message)]
+        pub fn set_raffle_share(&mut self, share_bps: u16) -> Result<()> {
+            self.ensure_owner()?;
+            if u32::from(share_bps) > BPS_DENOMINATOR {
+                return Err(Error::InvalidRaffleShare);
+            }
+            self.raffle_fee_bps = share_bps;
+            Ok(())
+        }
+
+        /// Returns the accounts entered in the raffle draw.
+        #[ink(message)]
+        pub fn raffle_entrants(&self) -> Vec<AccountId> {
+            self.raffle_entrants.clone()
+        }
+
+        /// Enters the caller in the raffle draw, once and while fewer than
+        /// `MAX_RAFFLE_ENTRANTS` accounts are entered.
+        #[ink(message)]
+        pub fn enter_raffle(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            if self.raffle_entrants.contains(&caller)
+                || self.raffle_entrants.len() >= MAX_RAFFLE_ENTRANTS
+            {
+                return Err(Error::RaffleEntryRejected);
+            }
+            self.raffle_entrants.push(caller);
+            Ok(())
+        }
+
+        /// Withdraws the caller from the raffle draw.
+        #[ink(message)]
+        pub fn leave_raffle(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            self.raffle_entrants.retain(|entrant| *entrant != caller);
+            Ok(())
+        }
+
+        /// Awards the whole prize pool to an entrant picked at random, weighted by balance,
+        /// and returns the winner.
+        ///
+        /// The pick uses the chain's on-chain randomness seeded with the entrant
+        /// index. Block authors can predict and influence it, so the raffle is only
+        /// fit for prizes that are not worth manipulating a block for.
+        #[ink(message)]
+        pub fn draw_winner(&mut self) -> Result<AccountId> {
+            self.non_reentrant(|contract| contract.draw_winner_impl())
+        }
+
+        /// private helper function running `draw_winner` under the reentrancy guard
+        fn draw_winner_impl(&mut self) -> Result<AccountId> {
+            self.ensure_owner()?;
+            let prize = self.raffle_pool;
+            if prize == 0 {
+                return Err(Error::EmptyPrizePool);
+            }
+            let weights: Vec<Balance> = self
+                .raffle_entrants
+                .iter()
+                .map(|entrant| self.balance_of_impl(entrant))
+                .collect();
+            let total: Balance = weights.iter().sum();
+            if total == 0 {
+                return Err(Error::NoEligibleHolders);
+            }
+
+            let (seed, _) = self
+                .env()
+                .random(&(self.raffle_entrants.len() as u32).to_le_bytes());
+            let mut ticket_bytes = [0u8; 16];
+            ticket_bytes.copy_from_slice(&seed.as_ref()[..16]);
+            let mut ticket = u128::from_le_bytes(ticket_bytes) % total;
+            let index = weights
+                .iter()
+                .position(|weight| {
+                    if ticket < *weight {
+                        return true;
+                    }
+                    ticket -= weight;
+                    false
+                })
+                .unwrap_or_default();
+            let winner = self.raffle_entrants[index];
+
+            let pool = self.env().account_id();
+            self.check_transfer(&pool, &winner, prize)?;
+            self.raffle_pool = 0;
+            self.move_tokens(&pool, &winner, prize);
+            self.env().emit_event(RaffleDrawn { winner, prize });
+            Ok(winner)
+        }
+
+        /// private helper function to add `fee` to the fees `account` has paid
+        fn record_fee_paid(&mut self, account: &AccountId, fee: Balance) {
+            if fee > 0 {
+                self.fees_paid
+                    .insert(account, &(self.fees_paid_of(*account) + fee));
+            }
+        }
+
+        /// Returns whether balance changes of `account` are reported with `TaxableEvent`.
+        #[ink(message)]
+        pub fn report_enabled(&self, account: AccountId) -> bool {
+            self.report_enabled.get(account).unwrap_or_default()
+        }
+
+        /// Turns tax reporting of `account`'s transfers, mints and burns on or off.
+        #[ink(message)]
+        pub fn set_report_enabled(&mut self, account: AccountId, enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            self.report_enabled.insert(account, &enabled);
+            Ok(())
+        }
+
+        /// Returns the native payout per token, if wind-down is active.
+        #[ink(message)]
+        pub fn wind_down_rate(&self) -> Option<Balance> {
+            self.wind_down_rate
+        }
+
+        /// Winds the project down, letting holders redeem each token for `rate` native currency.
+        ///
+        /// Redemptions are paid from the contract's native reserves.
+        #[ink(message)]
+        pub fn enable_wind_down(&mut self, rate: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            self.wind_down_rate = Some(rate);
+            Ok(())
+        }
+
+        /// Burns the caller's unlocked balance for `balance * rate` native currency.
+        ///
+        /// Blacklisted and tainted accounts cannot redeem, as they cannot transfer.
+        /// The payout is sent before any tokens are burned, so a failed payout
+        /// leaves the caller's balance untouched.
+        #[ink(message)]
+        pub fn redeem_wind_down(&mut self) -> Result<()> {
+            self.non_reentrant(|contract| contract.redeem_wind_down_impl())
+        }
+
+        /// private helper function running `redeem_wind_down` under the reentrancy guard
+        fn redeem_wind_down_impl(&mut self) -> Result<()> {
+            self.ensure_not_paused()?;
+            let rate = self.wind_down_rate.ok_or(Error::WindDownNotActive)?;
+            let caller = self.env().caller();
+            if self.is_blacklisted_impl(&caller) {
+                return Err(Error::Blacklisted);
+            }
+            if self.tainted.contains(caller) {
+                return Err(Error::TaintedFunds);
+            }
+            let balance = self.unlocked_balance_of(&caller);
+            if balance == 0 {
+                return Err(Error::InsufficientBalance {
+                    required: 1,
+                    available: 0,
+                });
+            }
+            let payout = balance
+                .checked_mul(rate)
+                .filter(|payout| *payout <= self.native_reserves())
+                .ok_or(Error::InsufficientReserves)?;
+            self.env()
+                .transfer(caller, payout)
+                .map_err(|_| Error::NativeTransferFailed)?;
+
+            self.burn_impl(&caller, balance);
+            Ok(())
+        }
+
+        /// Returns the native currency paid per token by queued redemptions, if enabled.
+        #[ink(message)]
+        pub fn redemption_rate(&self) -> Option<Balance> {
+            self.redemption_rate
+        }
+
+        /// Sets the native payout per token for queued redemptions, or `None` to halt them.
+        #[ink(message)]
+        pub fn set_redemption_rate(&mut self, rate: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            self.redemption_rate = rate;
+            Ok(())
+        }
+
+        /// Escrows `amount` of the caller's tokens and queues them for redemption; returns the queue id.
+        ///
+        /// Queued redemptions are paid at the rate in force when they are processed.
+        #[ink(message)]
+        pub fn request_redemption(&mut self, amount: Balance) -> Result<u64> {
+            self.non_reentrant(|contract| contract.request_redemption_impl(amount))
+        }
+
+        /// private helper function running `request_redemption` under the reentrancy guard
+        fn request_redemption_impl(&mut self, amount: Balance) -> Result<u64> {
+            self.ensure_not_paused()?;
+            if self.redemption_rate.is_none() {
+                return Err(Error::RedemptionsNotEnabled);
+            }
+            let caller = self.env().caller();
+            self.check_escrow(&caller, amount)?;
+            self.ensure_spendable(&caller, amount)?;
+
+            let escrow = self.env().account_id();
+            self.move_tokens(&caller, &escrow, amount);
+            let id = self.next_redemption_id;
+            self.redemptions.insert(id, &(caller, amount));
+            self.track_pending_op(PendingOpKind::Redemption, id, &[caller]);
+            self.next_redemption_id += 1;
+            Ok(id)
+        }
+
+        /// Returns the queued redemption `id` as `(account, amount)` while it is still pending.
+        #[ink(message)]
+        pub fn redemption_of(&self, id: u64) -> Option<(AccountId, Balance)> {
+            self.redemptions.get(id)
+        }
+
+        /// Pays out up to `count` queued redemptions in FIFO order, as far as the reserves allow.
+        ///
+        /// Stops at the first redemption the reserves cannot cover, so later requests
+        /// never jump the queue. Callable by anyone; returns how many were paid.
+        #[ink(message)]
+        pub fn process_redemptions(&mut self, count: u32) -> Result<u32> {
+            self.non_reentrant(|contract| contract.process_redemptions_impl(count))
+        }
+
+        /// private helper function running `process_redemptions` under the reentrancy guard
+        fn process_redemptions_impl(&mut self, count: u32) -> Result<u32> {
+            self.ensure_not_paused()?;
+            let rate = self.redemption_rate.ok_or(Error::RedemptionsNotEnabled)?;
+            let escrow = self.env().account_id();
+            let mut processed = 0;
+            while processed < count && self.next_redemption_to_process < self.next_redemption_id {
+                let id = self.next_redemption_to_process;
+                let Some((account, amount)) = self.redemptions.get(id) else {
+                    break;
+                };
+                let payout = match amount.checked_mul(rate) {
+                    Some(payout) if payout <= self.native_reserves() => payout,
+                    _ => break,
+                };
+                self.env()
+                    .transfer(account, payout)
+                    .map_err(|_| Error::NativeTransferFailed)?;
+
+                self.burn_impl(&escrow, amount);
+                self.redemptions.remove(id);
+                self.untrack_pending_op(PendingOpKind::Redemption, id, &[account]);
+                self.next_redemption_to_process += 1;
+                processed += 1;
+            }
+            Ok(processed)
+        }
+
+        /// Mints the caller one token per unit of native currency attached, like WETH.
+        ///
+        /// Returning an error does not revert state, so the attached value is
+        /// refunded whenever the mint is rejected.
+        #[ink(message, payable)]
+        pub fn deposit(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            let value = self.env().transferred_value();
+            let minted = self.non_reentrant(|contract| contract.deposit_impl(&caller, value));
+            if let Err(error) = minted {
+                if value > 0 {
+                    self.env()
+                        .transfer(caller, value)
+                        .map_err(|_| Error::NativeTransferFailed)?;
+                }
+                return Err(error);
+            }
+            Ok(())
+        }
+
+        /// private helper function running `deposit` under the reentrancy guard
+        fn deposit_impl(&mut self, caller: &AccountId, value: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
+            self.check_mint(caller, value)?;
+
+            self.mint_impl(caller, value);
+            self.wrapped_supply += value;
+            self.env().emit_event(Deposit {
+                account: *caller,
+                value,
+            });
+            Ok(())
+        }
+
+        /// Burns `value` of the caller's tokens and pays out as much native currency.
+        ///
+        /// Only currency attached to `deposit` backs withdrawals, so tokens minted
+        /// any other way cannot drain reserves held for other purposes. The payout
+        /// is sent before any tokens are burned, so a failed payout leaves the
+        /// caller's balance untouched.
+        #[ink(message)]
+        pub fn withdraw(&mut self, value: Balance) -> Result<()> {
+            self.non_reentrant(|contract| contract.withdraw_impl(value))
+        }
+
+        /// private helper function running `withdraw` under the reentrancy guard
+        fn withdraw_impl(&mut self, value: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            self.ensure_spendable(&caller, value)?;
+            if value > self.wrapped_supply.min(self.env().balance()) {
+                return Err(Error::InsufficientReserves);
+            }
+            self.env()
+                .transfer(caller, value)
+                .map_err(|_| Error::NativeTransferFailed)?;
+
+            self.wrapped_supply -= value;
+            self.burn_impl(&caller, value);
+            self.env().emit_event(Withdrawal {
+                account: caller,
+                value,
+            });
+            Ok(())
+        }
+
+        /// private helper function to get the native currency redemptions may pay out
+        ///
+        /// Currency backing the tokens minted by `deposit` is held apart for `withdraw`.
+        fn native_reserves(&self) -> Balance {
+            self.env().balance().saturating_sub(self.wrapped_supply)
+        }
+
+        /// Returns the native currency backing tokens minted by `deposit`.
+        #[ink(message)]
+        pub fn wrapped_supply(&self) -> Balance {
+            self.wrapped_supply
+        }
+
+        /// Returns every per-account state of `account` packed into the `FLAG_*` bits.
+        #[ink(message)]
+        pub fn account_flags(&self, account: AccountId) -> u32 {
+            [
+                (self.is_blacklisted_impl(&account), FLAG_BLACKLISTED),
+                (self.tainted.contains(account), FLAG_TAINTED),
+                (
+                    self.large_transfer_optin.contains(account),
+                    FLAG_LARGE_TRANSFER_OPTIN,
+                ),
+                (self.report_enabled(account), FLAG_TAX_REPORTING),
+                (
+                    self.spender_allowlist_mode.contains(account),
+                    FLAG_SPENDER_ALLOWLIST,
+                ),
+                (self.owner == Some(account), FLAG_OWNER),
+                (self.treasury == account, FLAG_TREASURY),
+                (self.quarantine == Some(account), FLAG_QUARANTINE),
+            ]
+            .iter()
+            .filter(|(set, _)| *set)
+            .fold(0, |flags, (_, bit)| flags | bit)
+        }
+
+        /// Returns the debt `owner` still owes the protocol.
+        #[ink(message)]
+        pub fn debt_of(&self, owner: AccountId) -> Balance {
+            self.debt.get(owner).unwrap_or_default()
+        }
+
+        /// Adds `amount` to the debt `account` owes the protocol.
+        ///
+        /// Tokens later sent to `account` go to the contract owner until the debt is repaid.
+        #[ink(message)]
+        pub fn assign_debt(&mut self, account: AccountId, amount: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            self.debt.insert(account, &(self.debt_of(account) + amount));
+            Ok(())
+        }
+
+        /// Returns `true` if `account` is flagged as tainted.
+        #[ink(message)]
+        pub fn is_tainted(&self, account: AccountId) -> bool {
+            self.tainted.contains(account)
+        }
+
+        /// Flags or clears `account` as tainted, restricting it to quarantine transfers.
+        #[ink(message)]
+        pub fn set_tainted(&mut self, account: AccountId, tainted: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if tainted {
+                self.tainted.insert(account, &());
+            } else {
+                self.tainted.remove(account);
+            }
+            Ok(())
+        }
+
+        /// Sets the address tainted accounts may still send their tokens to.
+        #[ink(message)]
+        pub fn set_quarantine(&mut self, quarantine: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.quarantine = Some(quarantine);
+            Ok(())
+        }
+
+        /// Returns the current owner, or `None` once ownership has been renounced.
+        #[ink(message)]
+        pub fn owner(&self) -> Option<AccountId> {
+            self.owner
+        }
+
+        /// Returns the account proposed as the next owner, if any.
+        #[ink(message)]
+        pub fn pending_owner(&self) -> Option<AccountId> {
+            self.pending_owner
+        }
+
+        /// Proposes `new_owner` as the next owner; they take over once they call `accept_ownership`.
+        ///
+        /// Fails with `OwnershipChangeTooSoon` if the previous transfer happened
+        /// less than `ownership_cooldown` ago. A new proposal replaces a pending one.
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_ownership_cooldown_passed()?;
+            self.pending_owner = Some(new_owner);
+            Ok(())
+        }
+
+        /// Completes a handover, making the caller the owner if they are the pending owner.
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            if self.pending_owner != Some(caller) {
+                return Err(Error::NotPendingOwner);
+            }
+            self.ensure_ownership_cooldown_passed()?;
+
+            self.last_ownership_change = Some(self.env().block_timestamp());
+            self.set_owner(Some(caller));
+            Ok(())
+        }
+
+        /// private helper function to check that the ownership cooldown has passed
+        fn ensure_ownership_cooldown_passed(&self) -> Result<()> {
+            if let Some(last_change) = self.last_ownership_change {
+                if self.env().block_timestamp() < last_change + self.ownership_cooldown {
+                    return Err(Error::OwnershipChangeTooSoon);
+                }
+            }
+            Ok(())
+        }
+
+        /// Starts renouncing ownership; `finalize_renounce` can complete it once
+        /// `renounce_grace_period` has passed.
+        #[ink(message)]
+        pub fn initiate_renounce(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            self.renounce_initiated_at = Some(self.env().block_timestamp());
+            Ok(())
+        }
+
+        /// Aborts a pending renounce.
+        #[ink(message)]
+        pub fn cancel_renounce(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            self.renounce_initiated_at = None;
+            Ok(())
+        }
+
+        /// Permanently gives up ownership, leaving every owner-only message unusable.
+        ///
+        /// Fails with `RenounceNotReady` unless a renounce was initiated at least
+        /// `renounce_grace_period` ago.
+        #[ink(message)]
+        pub fn finalize_renounce(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            match self.renounce_initiated_at {
+                Some(initiated_at)
+                    if self.env().block_timestamp()
+                        >= initiated_at + self.renounce_grace_period => {}
+                _ => return Err(Error::RenounceNotReady),
+            }
+
+            self.set_owner(None);
+            Ok(())
+        }
+
+        /// Sets how long a renounce must stay pending before it can be finalized.
+        #[ink(message)]
+        pub fn set_renounce_grace_period(&mut self, period: Timestamp) -> Result<()> {
+            self.ensure_owner()?;
+            self.renounce_grace_period = period;
+            Ok(())
+        }
+
+        /// private helper function to replace the owner, dropping any pending renounce or handover
+        fn set_owner(&mut self, new_owner: Option<AccountId>) {
+            let previous_owner = self.owner.take();
+            self.owner = new_owner;
+            self.renounce_initiated_at = None;
+            self.pending_owner = None;
+
+            if let Some(previous_owner) = previous_owner {
+                self.env().emit_event(OwnershipTransferred {
+                    previous_owner,
+                    new_owner,
+                });
+            }
+        }
+
+        /// Sets the minimum time that must pass between two ownership transfers.
+        #[ink(message)]
+        pub fn set_ownership_cooldown(&mut self, cooldown: Timestamp) -> Result<()> {
+            self.ensure_owner()?;
+            self.ownership_cooldown = cooldown;
+            Ok(())
+        }
+
+        /// private helper function to check whether `account` is currently blacklisted
+        fn is_blacklisted_impl(&self, account: &AccountId) -> bool {
+            match self.blacklisted.get(account) {
+                Some(Some(until)) => self.env().block_timestamp() < until,
+                Some(None) => true,
+                None => false,
+            }
+        }
+
+        /// private helper function to put `account` on the denylist until `until`, or permanently
+        fn freeze(&mut self, account: AccountId, until: Option<Timestamp>) {
+            self.blacklisted.insert(account, &until);
+            self.env().emit_event(AccountFrozen { account, until });
+        }
+
+        /// private helper function to take `account` off the denylist, if it is listed
+        fn unfreeze(&mut self, account: AccountId) {
+            if self.blacklisted.contains(account) {
+                self.blacklisted.remove(account);
+                self.env().emit_event(AccountUnfrozen { account });
+            }
+        }
+
+        /// private helper function to reject callers other than the owner
+        fn ensure_owner(&self) -> Result<()> {
+            if self.owner != Some(self.env().caller()) {
+                return Err(Error::NotOwner);
+            }
+            Ok(())
+        }
+
+        /// Returns `true` if `account` holds `role`.
+        #[ink(message)]
+        pub fn has_role(&self, role: Role, account: AccountId) -> bool {
+            self.roles.contains((role, account))
+        }
+
+        /// Grants `role` to `account`.
+        #[ink(message)]
+        pub fn grant_role(&mut self, role: Role, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            if !self.has_role(role, account) {
+                self.roles.insert((role, account), &());
+                self.env().emit_event(RoleGranted { role, account });
+            }
+            Ok(())
+        }
+
+        /// Revokes `role` from `account`.
+        #[ink(message)]
+        pub fn revoke_role(&mut self, role: Role, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            if self.has_role(role, account) {
+                self.roles.remove((role, account));
+                self.env().emit_event(RoleRevoked { role, account });
+            }
+            Ok(())
+        }
+
+        /// Returns `(confirmations needed, lifetime)` of multisig proposals.
+        #[ink(message)]
+        pub fn multisig_config(&self) -> (u32, Timestamp) {
+            (self.proposal_threshold, self.proposal_lifetime)
+        }
+
+        /// Sets how many admins must confirm a proposal and how long they have to.
+        ///
+        /// The new threshold also applies to proposals already pending. Once the
+        /// threshold is above one, changes go through `Action::SetMultisigConfig`.
+        #[ink(message)]
+        pub fn set_multisig_config(&mut self, threshold: u32, lifetime: Timestamp) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_no_multisig()?;
+            self.set_multisig_config_impl(threshold, lifetime)
+        }
+
+        /// private helper function to store a validated multisig threshold and lifetime
+        fn set_multisig_config_impl(&mut self, threshold: u32, lifetime: Timestamp) -> Result<()> {
+            if threshold == 0 {
+                return Err(Error::InvalidThreshold);
+            }
+            self.proposal_threshold = threshold;
+            self.proposal_lifetime = lifetime;
+            Ok(())
+        }
+
+        /// private helper function to check that privileged calls need no multisig confirmations
+        ///
+        /// Once the threshold is above one, minting, pausing, upgrades and the
+        /// multisig config itself can only change through `propose_action`.
+        fn ensure_no_multisig(&self) -> Result<()> {
+            if self.proposal_threshold > 1 {
+                return Err(Error::MultisigRequired);
+            }
+            Ok(())
+        }
+
+        /// Returns pending proposal `id` as `(action, confirmations, expiry)`.
+        #[ink(message)]
+        pub fn proposal(&self, id: u64) -> Option<(Action, u32, Timestamp)> {
+            self.proposals.get(id)
+        }
+
+        /// Proposes `action` as an admin, counting as its first confirmation. Returns the proposal id.
+        ///
+        /// Callable by the owner and `Admin`s. With a threshold of one the
+        /// action executes right away.
+        #[ink(message)]
+        pub fn propose_action(&mut self, action: Action) -> Result<u64> {
+            self.non_reentrant(|contract| contract.propose_action_impl(action))
+        }
+
+        /// private helper function running `propose_action` under the reentrancy guard
+        fn propose_action_impl(&mut self, action: Action) -> Result<u64> {
+            self.ensure_role(Role::Admin)?;
+            let id = self.next_proposal_id;
+            let proposer = self.env().caller();
+            if self.proposal_threshold <= 1 {
+                self.execute_action(action)?;
+            } else {
+                let expires_at = self.env().block_timestamp() + self.proposal_lifetime;
+                self.proposals.insert(id, &(action, 1, expires_at));
+                self.proposal_confirmations.insert((id, proposer), &());
+            }
+            self.next_proposal_id += 1;
+
+            self.env().emit_event(ProposalCreated {
+                id,
+                proposer,
+                action,
+            });
+            if self.proposal_threshold <= 1 {
+                self.env().emit_event(ProposalExecuted { id });
+            }
+            Ok(id)
+        }
+
+        /// Confirms pending proposal `id`, executing it once the threshold is reached.
+        ///
+        /// If the action fails, the confirmation is not recorded and the proposal stays pending.
+        #[ink(message)]
+        pub fn confirm(&mut self, id: u64) -> Result<()> {
+            self.non_reentrant(|contract| contract.confirm_impl(id))
+        }
+
+        /// private helper function running `confirm` under the reentrancy guard
+        fn confirm_impl(&mut self, id: u64) -> Result<()> {
+            self.ensure_role(Role::Admin)?;
+            let (action, confirmations, expires_at) =
+                self.proposals.get(id).ok_or(Error::UnknownProposal)?;
+            if self.env().block_timestamp() > expires_at {
+                return Err(Error::ProposalExpired);
+            }
+            let caller = self.env().caller();
+            if self.proposal_confirmations.contains((id, caller)) {
+                return Err(Error::AlreadyConfirmed);
+            }
+
+            let confirmations = confirmations + 1;
+            if confirmations >= self.proposal_threshold {
+                self.execute_action(action)?;
+                self.proposals.remove(id);
+                self.env().emit_event(ProposalExecuted { id });
+            } else {
+                self.proposals
+                    .insert(id, &(action, confirmations, expires_at));
+            }
+            self.proposal_confirmations.insert((id, caller), &());
+            Ok(())
+        }
+
+        /// private helper function to run a confirmed multisig action
+        fn execute_action(&mut self, action: Action) -> Result<()> {
+            match action {
+                // The multisig mints as the contract account, against its own lifetime cap
+                Action::Mint(to, value) => self.mint_checked(&self.env().account_id(), &to, value),
+                Action::Pause => self.set_paused(true),
+                Action::Unpause => self.set_paused(false),
+                Action::Upgrade(code_hash) => self.upgrade_impl(code_hash),
+                Action::SetMultisigConfig(threshold, lifetime) => {
+                    self.set_multisig_config_impl(threshold, lifetime)
+                }
+            }
+        }
+
+        /// private helper function to run `body` while holding the reentrancy lock
+        ///
+        /// Guards messages that may call other contracts, e.g. through the
+        /// balance mirror or receiver hooks, so a callee cannot call back into them.
+        /// The queued mirror updates are sent last, still under the lock.
+        fn non_reentrant<T>(&mut self, body: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+            if self.reentrancy_lock.contains(()) {
+                return Err(Error::ReentrantCall);
+            }
+            self.reentrancy_lock.insert((), &());
+            let result = body(self);
+            self.flush_mirror_queue();
+            self.reentrancy_lock.remove(());
+            result
+        }
+
+        /// private helper function to check that the caller is the owner or holds `role`
+        fn ensure_role(&self, role: Role) -> Result<()> {
+            let caller = self.env().caller();
+            if self.owner != Some(caller) && !self.has_role(role, caller) {
+                return Err(Error::NotAuthorized);
+            }
+            Ok(())
+        }
+
+        /// private helper function to reject token movements and approvals while paused,
+        /// and by callers that are not thawed during a global freeze
+        fn ensure_not_paused(&self) -> Result<()> {
+            if self.paused {
+                return Err(Error::ContractPaused);
+            }
+            self.ensure_thawed(&self.env().caller())
+        }
+
+        /// private helper function to get the dividends accrued since the last settlement
+        fn accrued_dividends(&self, owner: &AccountId) -> Balance {
+            if *owner == self.env().account_id() {
+                return 0;
+            }
+            // Supply growth outside `check_mint`, such as interest, saturates rather than traps
+            (self
+                .stored_balance(owner)
+                .saturating_mul(self.dividends_per_share)
+                - self.dividend_debt.get(owner).unwrap_or_default())
+                / DIVIDEND_PRECISION
+        }
+
+        /// private helper function to move accrued dividends into the pending bucket
+        fn settle_dividends(&mut self, owner: &AccountId) {
+            let accrued = self.accrued_dividends(owner);
+            if accrued > 0 {
+                let pending = self.pending_dividends.get(owner).unwrap_or_default();
+                self.pending_dividends.insert(owner, &(pending + accrued));
+            }
+            self.reset_dividend_debt(owner);
+        }
+
+        /// private helper function to mark the current balance as fully accounted for
+        ///
+        /// Uses the stored balance like `accrued_dividends`, so decay collected
+        /// later cannot leave the debt above the accrual.
+        fn reset_dividend_debt(&mut self, owner: &AccountId) {
+            let debt = self
+                .stored_balance(owner)
+                .saturating_mul(self.dividends_per_share);
+            self.dividend_debt.insert(owner, &debt);
+        }
+    }
+
+    impl PSP22 for Erc20 {
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            self.total_supply
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> Balance {
+            self.balance_of_impl(&owner)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowance_impl(&owner, &spender)
+        }
+
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> core::result::Result<(), PSP22Error> {
+            Erc20::transfer(self, to, value).map_err(PSP22Error::from)
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> core::result::Result<(), PSP22Error> {
+            Erc20::transfer_from(self, from, to, value).map_err(PSP22Error::from)
+        }
+
+        #[ink(message)]
+        fn approve(
+            &mut self,
+            spender: AccountId,
+            value: Balance,
+        ) -> core::result::Result<(), PSP22Error> {
+            Erc20::approve(self, spender, value).map_err(PSP22Error::from)
+        }
+
+        #[ink(message)]
+        fn increase_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: Balance,
+        ) -> core::result::Result<(), PSP22Error> {
+            Erc20::increase_allowance(self, spender, delta_value).map_err(PSP22Error::from)
+        }
+
+        #[ink(message)]
+        fn decrease_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: Balance,
+        ) -> core::result::Result<(), PSP22Error> {
+            Erc20::decrease_allowance(self, spender, delta_value).map_err(PSP22Error::from)
+        }
+    }
+
+    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
+    /// module and test functions are marked with a `#[test]` attribute.
+    /// The below code is technically just normal Rust code.
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+
+        use ink_env::AccountId;
+        /// Imports `ink_lang` so we can use `#[ink::test]`.
+        use ink_lang as ink;
+
+        /// Sets the account that performs the next contract calls.
+        fn set_caller(caller: AccountId) {
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(caller);
+        }
+
+        type Event = <Erc20 as ::ink_lang::reflect::ContractEventBase>::Type;
+
+        /// Decodes all events emitted so far.
+        fn emitted_events() -> Vec<Event> {
+            ink_env::test::recorded_events()
+                .map(|event| {
+                    <Event as scale::Decode>::decode(&mut &event.data[..])
+                        .expect("encountered invalid contract event data buffer")
+                })
+                .collect()
+        }
+
+        /// Writes `value` straight into `account`'s balance, bypassing the supply.
+        fn seed_balance(contract: &mut Erc20, account: AccountId, value: Balance) {
+            contract.balances.insert(account, &value);
+        }
+
+        /// Gives the contract its own account, distinct from the deployer's.
+        fn set_contract_account() -> AccountId {
+            let contract = AccountId::from([0xFF; 32]);
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(contract);
+            contract
+        }
+
+        /// Test if the default constructor does its job.
+        #[ink::test]
+        fn new_works() {
+            let contract = Erc20::new_minimal(777);
+            assert_eq!(contract.total_supply(), 777);
+            assert_eq!(contract.token_name(), None);
+            assert_eq!(contract.token_symbol(), None);
+            assert_eq!(contract.token_decimals(), 18);
+        }
+
+        /// Test if the constructor stores the token metadata
+        #[ink::test]
+        fn metadata_works() {
+            let contract = Erc20::new(
+                777,
+                Some(String::from("Token")),
+                Some(String::from("TKN")),
+                12,
+                false,
+            );
+            assert_eq!(contract.token_name(), Some(String::from("Token")));
+            assert_eq!(contract.token_symbol(), Some(String::from("TKN")));
+            assert_eq!(contract.token_decimals(), 12);
+        }
+
+        /// We if balance works
+        #[ink::test]
+        fn balance_works() {
+            let contract = Erc20::new_minimal(100);
+            assert_eq!(contract.total_supply(), 100);
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 100);
+            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 0);
+        }
+
+        /// Test if transfer works
+        #[ink::test]
+        fn transfer_works() {
+            let mut contract = Erc20::new_minimal(100);
+            assert_eq!(contract.total_supply(), 100);
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 100);
+            assert_eq!(contract.balance_of(AccountId::from([0x2; 32])), 0);
+            assert_eq!(contract.transfer(AccountId::from([0x2; 32]), 50), Ok(()));
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 50);
+            assert_eq!(contract.balance_of(AccountId::from([0x2; 32])), 50);
+        }
+        #[ink::test]
+        fn transfer_from_works() {
+            let mut contract = Erc20::new_minimal(100);
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 100);
+            contract
+                .approve(AccountId::from([0x1; 32]), 20)
+                .unwrap_or_default();
+            contract
+                .transfer_from(AccountId::from([0x1; 32]), AccountId::from([0x2; 32]), 10)
+                .unwrap_or_default();
+            assert_eq!(contract.balance_of(AccountId::from([0x2; 32])), 10);
+        }
+
+        #[ink::test]
+        fn allowance_works() {
+            let mut contract = Erc20::new_minimal(100);
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 100);
+            contract
+                .approve(AccountId::from([0x1; 32]), 200)
+                .unwrap_or_default();
+            assert_eq!(
+                contract.allowance(AccountId::from([0x1; 32]), AccountId::from([0x1; 32])),
+                200
+            );
+            contract
+                .transfer_from(AccountId::from([0x1; 32]), AccountId::from([0x2; 32]), 50)
+                .unwrap_or_default();
+            assert_eq!(contract.balance_of(AccountId::from([0x2; 32])), 50);
+            assert_eq!(
+                contract.allowance(AccountId::from([0x1; 32]), AccountId::from([0x1; 32])),
+                150
+            );
+
+            contract
+                .transfer_from(AccountId::from([0x1; 32]), AccountId::from([0x2; 32]), 100)
+                .unwrap_or_default();
+            assert_eq!(contract.balance_of(AccountId::from([0x2; 32])), 50);
+            assert_eq!(
+                contract.allowance(AccountId::from([0x1; 32]), AccountId::from([0x1; 32])),
+                150
+            );
+        }
+
+        /// Test if deposited dividends are claimed pro-rata to balances
+        #[ink::test]
+        fn claim_dividends_works() {
+            let pool = set_contract_account();
+            let mut contract = Erc20::new_minimal(120);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.transfer(bob, 40), Ok(()));
+
+            assert_eq!(contract.deposit_dividends(20), Ok(()));
+            assert_eq!(contract.balance_of(pool), 20);
+            assert_eq!(contract.dividends_of(AccountId::from([0x1; 32])), 12);
+            assert_eq!(contract.dividends_of(bob), 8);
+
+            set_caller(bob);
+            assert_eq!(contract.claim_dividends(), Ok(8));
+            assert_eq!(contract.balance_of(bob), 48);
+            assert_eq!(contract.claim_dividends(), Ok(0));
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.claim_dividends(), Ok(12));
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 72);
+            assert_eq!(contract.balance_of(pool), 0);
+        }
+
+        /// Test if dividends accrued before a transfer stay with the sender
+        #[ink::test]
+        fn dividends_survive_transfers() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(120);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.transfer(bob, 40), Ok(()));
+            assert_eq!(contract.deposit_dividends(20), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(contract.transfer(AccountId::from([0x3; 32]), 40), Ok(()));
+            assert_eq!(contract.dividends_of(bob), 8);
+            assert_eq!(contract.dividends_of(AccountId::from([0x3; 32])), 0);
+        }
+
+        /// Test if dividends too large to account for are rejected instead of overflowing
+        #[ink::test]
+        fn deposit_dividends_rejects_overflow() {
+            set_contract_account();
+            let supply = 1_000_000_000_000_000_000_000_000_000;
+            let mut contract = Erc20::new_minimal(supply);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.transfer(bob, 1), Ok(()));
+            assert_eq!(contract.deposit_dividends(supply - 1), Err(Error::Overflow));
+            assert_eq!(contract.balance_of(bob), 1);
+            assert_eq!(contract.dividends_of(bob), 0);
+            assert_eq!(contract.deposit_dividends(1_000_000), Ok(()));
+        }
+
+        /// Test if only the owner can deposit dividends
+        #[ink::test]
+        fn deposit_dividends_requires_owner() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            set_caller(AccountId::from([0x2; 32]));
+            assert_eq!(contract.deposit_dividends(10), Err(Error::NotOwner));
+        }
+
+        /// Test if a denylist import reports newly flagged accounts and blocks them
+        #[ink::test]
+        fn import_denylist_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let django = AccountId::from([0x4; 32]);
+
+            assert_eq!(contract.import_denylist(vec![bob, charlie]), Ok(2));
+            assert_eq!(contract.import_denylist(vec![charlie, django]), Ok(1));
+            assert!(contract.is_blacklisted(django));
+            assert_eq!(contract.transfer(bob, 10), Err(Error::Blacklisted));
+
+            assert_eq!(contract.clear_denylist(vec![bob, django]), Ok(()));
+            assert!(!contract.is_blacklisted(bob));
+            assert!(contract.is_blacklisted(charlie));
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.transfer(charlie, 10), Err(Error::Blacklisted));
+        }
+
+        /// Test if only the owner can manage the denylist
+        #[ink::test]
+        fn import_denylist_requires_owner() {
+            let mut contract = Erc20::new_minimal(100);
+            set_caller(AccountId::from([0x2; 32]));
+            assert_eq!(
+                contract.import_denylist(vec![AccountId::from([0x3; 32])]),
+                Err(Error::NotOwner)
+            );
+            assert_eq!(contract.clear_denylist(vec![]), Err(Error::NotOwner));
+            assert_eq!(
+                contract.freeze_account(AccountId::from([0x3; 32])),
+                Err(Error::NotOwner)
+            );
+            assert_eq!(
+                contract.unfreeze_account(AccountId::from([0x3; 32])),
+                Err(Error::NotOwner)
+            );
+        }
+
+        /// Test if every denylist change emits an `AccountFrozen` or `AccountUnfrozen` event
+        #[ink::test]
+        fn denylist_changes_emit_events() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+
+            let before = emitted_events().len();
+            assert_eq!(contract.freeze_account(bob), Ok(()));
+            assert_eq!(contract.transfer(bob, 10), Err(Error::Blacklisted));
+            assert_eq!(contract.set_blacklisted_until(charlie, 30), Ok(()));
+            assert_eq!(contract.import_denylist(vec![charlie]), Ok(0));
+            let frozen: Vec<(AccountId, Option<Timestamp>)> = emitted_events()[before..]
+                .iter()
+                .map(|event| match event {
+                    Event::AccountFrozen(AccountFrozen { account, until }) => (*account, *until),
+                    _ => panic!("expected an AccountFrozen event"),
+                })
+                .collect();
+            assert_eq!(frozen, [(bob, None), (charlie, Some(30)), (charlie, None)]);
+
+            // Only listed accounts are reported as unfrozen
+            let before = emitted_events().len();
+            assert_eq!(contract.unfreeze_account(bob), Ok(()));
+            assert_eq!(contract.clear_denylist(vec![bob, charlie]), Ok(()));
+            let unfrozen: Vec<AccountId> = emitted_events()[before..]
+                .iter()
+                .map(|event| match event {
+                    Event::AccountUnfrozen(AccountUnfrozen { account }) => *account,
+                    _ => panic!("expected an AccountUnfrozen event"),
+                })
+                .collect();
+            assert_eq!(unfrozen, [bob, charlie]);
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+        }
+
+        /// Test if a split transfer pays each recipient by weight with dust to the first
+        #[ink::test]
+        fn transfer_split_works() {
+            let mut contract = Erc20::new_minimal(1000);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let django = AccountId::from([0x4; 32]);
+
+            assert_eq!(
+                contract.transfer_split(vec![(bob, 5000), (charlie, 3000), (django, 2000)], 101),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(bob), 51);
+            assert_eq!(contract.balance_of(charlie), 30);
+            assert_eq!(contract.balance_of(django), 20);
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 899);
+        }
+
+        /// Test if split weights must sum to exactly 10000
+        #[ink::test]
+        fn transfer_split_rejects_invalid_weights() {
+            let mut contract = Erc20::new_minimal(1000);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+
+            assert_eq!(
+                contract.transfer_split(vec![(bob, 5000), (charlie, 4000)], 100),
+                Err(Error::InvalidWeights)
+            );
+            assert_eq!(
+                contract.transfer_split(vec![], 100),
+                Err(Error::InvalidWeights)
+            );
+            assert_eq!(contract.balance_of(bob), 0);
+        }
+
+        /// Test if a split whose weighted shares overflow fails instead of wrapping
+        #[ink::test]
+        fn transfer_split_rejects_overflow() {
+            let mut contract = Erc20::new_minimal(1000);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+
+            assert_eq!(
+                contract.transfer_split(vec![(bob, 5000), (charlie, 5000)], Balance::MAX),
+                Err(Error::Overflow)
+            );
+            assert_eq!(contract.balance_of(bob), 0);
+        }
+
+        /// Test if ownership cannot change hands again within the cooldown
+        #[ink::test]
+        fn transfer_ownership_respects_cooldown() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_ownership_cooldown(60), Ok(()));
+            assert_eq!(contract.transfer_ownership(bob), Ok(()));
+            set_caller(bob);
+            assert_eq!(contract.accept_ownership(), Ok(()));
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.transfer_ownership(charlie), Err(Error::NotOwner));
+
+            set_caller(bob);
+            assert_eq!(
+                contract.transfer_ownership(charlie),
+                Err(Error::OwnershipChangeTooSoon)
+            );
+            for _ in 0..10 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(contract.transfer_ownership(charlie), Ok(()));
+        }
+
+        /// Test if freezing allowances blocks transfer_from but not transfer
+        #[ink::test]
+        fn allowance_freeze_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.approve(alice, 50), Ok(()));
+            assert_eq!(contract.set_allowances_frozen(true), Ok(()));
+            assert!(contract.allowances_frozen());
+
+            assert_eq!(
+                contract.transfer_from(alice, bob, 10),
+                Err(Error::AllowancesFrozen)
+            );
+            assert_eq!(contract.allowance(alice, alice), 50);
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+
+            assert_eq!(contract.set_allowances_frozen(false), Ok(()));
+            assert_eq!(contract.transfer_from(alice, bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(bob), 20);
+        }
+
+        /// Test if senders accrue points at the configured rate
+        #[ink::test]
+        fn points_accrue_on_transfer() {
+            let mut contract = Erc20::new_minimal(1000);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.transfer(bob, 100), Ok(()));
+            assert_eq!(contract.points_of(alice), 0);
+
+            assert_eq!(contract.set_points_rate(2500), Ok(()));
+            assert_eq!(contract.transfer(bob, 100), Ok(()));
+            assert_eq!(contract.transfer(bob, 40), Ok(()));
+            assert_eq!(contract.points_of(alice), 35);
+            assert_eq!(contract.points_of(bob), 0);
+        }
+
+        /// Test if points saturate instead of overflowing on huge transfers
+        #[ink::test]
+        fn points_saturate() {
+            let mut contract = Erc20::new_minimal(1000);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_points_rate(u32::MAX), Ok(()));
+
+            seed_balance(&mut contract, bob, Balance::MAX);
+            set_caller(bob);
+            assert_eq!(contract.transfer(charlie, Balance::MAX - 1), Ok(()));
+            assert_eq!(contract.points_of(bob), Balance::MAX / 10_000);
+
+            contract.points.insert(bob, &(Balance::MAX - 1));
+            assert_eq!(contract.transfer(charlie, 1), Ok(()));
+            assert_eq!(contract.points_of(bob), Balance::MAX);
+        }
+
+        /// Test if a temporary blacklist entry expires on its own
+        #[ink::test]
+        fn temporary_blacklist_expires() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_blacklisted_until(bob, 30), Ok(()));
+            assert!(contract.is_blacklisted(bob));
+            assert_eq!(contract.transfer(bob, 10), Err(Error::Blacklisted));
+
+            for _ in 0..5 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert!(!contract.is_blacklisted(bob));
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+
+            // An expired entry counts as newly added when imported permanently
+            assert_eq!(contract.import_denylist(vec![bob]), Ok(1));
+            assert_eq!(contract.transfer(bob, 10), Err(Error::Blacklisted));
+        }
+
+        /// Test if a tainted account can only send to the quarantine address
+        #[ink::test]
+        fn tainted_account_only_reaches_quarantine() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let quarantine = AccountId::from([0x9; 32]);
+            assert_eq!(contract.set_quarantine(quarantine), Ok(()));
+            assert_eq!(contract.set_tainted(alice, true), Ok(()));
+
+            assert_eq!(contract.transfer(bob, 10), Err(Error::TaintedFunds));
+            assert_eq!(contract.transfer(quarantine, 10), Ok(()));
+            assert_eq!(contract.balance_of(quarantine), 10);
+
+            assert_eq!(contract.set_tainted(alice, false), Ok(()));
+            assert!(!contract.is_tainted(alice));
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+        }
+
+        /// Test if a batch preview matches the outcome of sequential transfers
+        #[ink::test]
+        fn preview_transfer_batch_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let batch = vec![
+                (AccountId::from([0x2; 32]), 60),
+                (AccountId::from([0x3; 32]), 50),
+                (AccountId::from([0x4; 32]), 40),
+            ];
+
+            let preview = contract.preview_transfer_batch(alice, batch.clone());
+            assert_eq!(
+                preview.results,
+                vec![
+                    Ok(60),
+                    Err(Error::InsufficientBalance {
+                        required: 50,
+                        available: 40
+                    }),
+                    Ok(40)
+                ]
+            );
+            assert_eq!(preview.final_balance, 0);
+            assert_eq!(contract.balance_of(alice), 100);
+
+            let actual: Vec<Result<Balance>> = batch
+                .into_iter()
+                .map(|(to, value)| contract.transfer(to, value).map(|_| value))
+                .collect();
+            assert_eq!(actual, preview.results);
+            assert_eq!(contract.balance_of(alice), preview.final_balance);
+        }
+
+        /// Test if allowance utilization tracks spending against the latest approval
+        #[ink::test]
+        fn allowance_utilization_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.allowance_utilization(alice, alice), (0, 0));
+
+            assert_eq!(contract.approve(alice, 50), Ok(()));
+            assert_eq!(contract.transfer_from(alice, bob, 20), Ok(()));
+            assert_eq!(contract.transfer_from(alice, bob, 5), Ok(()));
+            assert_eq!(contract.allowance_utilization(alice, alice), (25, 50));
+
+            assert_eq!(contract.approve(alice, 30), Ok(()));
+            assert_eq!(contract.allowance_utilization(alice, alice), (0, 30));
+        }
+
+        /// Test if incoming tokens repay an account's debt before crediting it
+        #[ink::test]
+        fn transfer_repays_debt_first() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(charlie, 50), Ok(()));
+            assert_eq!(contract.assign_debt(bob, 30), Ok(()));
+
+            set_caller(charlie);
+            assert_eq!(contract.transfer(bob, 20), Ok(()));
+            assert_eq!(contract.debt_of(bob), 10);
+            assert_eq!(contract.balance_of(bob), 0);
+            assert_eq!(contract.balance_of(alice), 70);
+
+            assert_eq!(contract.transfer(bob, 25), Ok(()));
+            assert_eq!(contract.debt_of(bob), 0);
+            assert_eq!(contract.balance_of(bob), 15);
+            assert_eq!(contract.balance_of(alice), 80);
+            assert_eq!(contract.balance_of(charlie), 5);
+        }
+
+        /// Test if tokens cannot be sent to the contract's own address
+        #[ink::test]
+        fn transfer_to_contract_fails() {
+            let contract_account = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            assert_eq!(
+                contract.transfer(contract_account, 10),
+                Err(Error::TransferToContract)
+            );
+            assert_eq!(contract.approve(AccountId::from([0x1; 32]), 10), Ok(()));
+            assert_eq!(
+                contract.transfer_from(AccountId::from([0x1; 32]), contract_account, 10),
+                Err(Error::TransferToContract)
+            );
+
+            // Depositing dividends is the one path meant to fund the contract account
+            assert_eq!(contract.deposit_dividends(10), Ok(()));
+            assert_eq!(contract.balance_of(contract_account), 10);
+        }
+
+        /// Test if the recent-transfers log keeps the latest entries in order
+        #[cfg(feature = "recent-transfers")]
+        #[ink::test]
+        fn recent_transfers_works() {
+            let mut contract = Erc20::new_minimal(1000);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.transfer(bob, 1), Ok(()));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer(bob, 2), Ok(()));
+            assert_eq!(
+                contract.recent_transfers(),
+                vec![(alice, bob, 1, 0), (alice, bob, 2, 1)]
+            );
+
+            for value in 3..=20 {
+                assert_eq!(contract.transfer(bob, value), Ok(()));
+            }
+            let recent = contract.recent_transfers();
+            assert_eq!(recent.len() as u32, RECENT_TRANSFERS_LEN);
+            assert_eq!(recent.first(), Some(&(alice, bob, 5, 1)));
+            assert_eq!(recent.last(), Some(&(alice, bob, 20, 1)));
+        }
+
+        /// Test if renouncing ownership only takes effect after the grace period
+        #[ink::test]
+        fn renounce_ownership_respects_grace_period() {
+            let mut contract = Erc20::new_minimal(100);
+            assert_eq!(contract.set_renounce_grace_period(30), Ok(()));
+            assert_eq!(contract.finalize_renounce(), Err(Error::RenounceNotReady));
+
+            assert_eq!(contract.initiate_renounce(), Ok(()));
+            assert_eq!(contract.finalize_renounce(), Err(Error::RenounceNotReady));
+            assert_eq!(contract.cancel_renounce(), Ok(()));
+            for _ in 0..5 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(contract.finalize_renounce(), Err(Error::RenounceNotReady));
+
+            assert_eq!(contract.initiate_renounce(), Ok(()));
+            for _ in 0..5 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(contract.finalize_renounce(), Ok(()));
+            assert_eq!(contract.set_points_rate(1), Err(Error::NotOwner));
+        }
+
+        /// Test if a flat fee is deducted from transfers and sent to the treasury
+        #[ink::test]
+        fn flat_fee_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let treasury = AccountId::from([0x9; 32]);
+            assert_eq!(contract.set_treasury(treasury), Ok(()));
+            assert_eq!(contract.set_flat_fee(3), Ok(()));
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, true), Ok(()));
+
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(bob), 7);
+            assert_eq!(contract.balance_of(treasury), 3);
+            assert_eq!(contract.balance_of(alice), 90);
+
+            assert_eq!(contract.transfer(bob, 2), Err(Error::AmountBelowFee));
+            assert_eq!(contract.balance_of(alice), 90);
+            assert_eq!(
+                contract
+                    .preview_transfer_batch(alice, vec![(bob, 10), (bob, 2)])
+                    .results,
+                vec![Ok(7), Err(Error::AmountBelowFee)]
+            );
+        }
+
+        /// Test if merging moves an account's balance and dividends to another account
+        #[ink::test]
+        fn merge_accounts_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(120);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(bob, 40), Ok(()));
+            assert_eq!(contract.deposit_dividends(20), Ok(()));
+            assert_eq!(contract.assign_debt(bob, 5), Ok(()));
+
+            assert_eq!(contract.merge_accounts(bob, charlie, true), Ok(()));
+            assert_eq!(contract.balance_of(bob), 0);
+            assert_eq!(contract.balance_of(charlie), 40);
+            assert_eq!(contract.dividends_of(bob), 0);
+            assert_eq!(contract.dividends_of(charlie), 8);
+            assert_eq!(contract.debt_of(charlie), 5);
+            assert!(contract.is_blacklisted(bob));
+        }
+
+        /// Test if merging moves staked shares and vesting schedules along with the balance
+        #[ink::test]
+        fn merge_accounts_moves_positions() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer(bob, 40), Ok(()));
+            assert_eq!(
+                contract.create_vesting_schedule(charlie, now, 0, 60, 6),
+                Ok(0)
+            );
+            assert_eq!(contract.create_vesting_schedule(bob, now, 0, 60, 30), Ok(0));
+
+            set_caller(bob);
+            assert_eq!(contract.stake(10), Ok(10));
+            assert_eq!(contract.merge_accounts(bob, charlie, false), Ok(()));
+            assert_eq!(contract.balance_of(charlie), 30);
+            assert_eq!(contract.shares_of(bob), 0);
+            assert_eq!(contract.shares_of(charlie), 10);
+            assert_eq!(contract.vesting_schedule(bob, 0), None);
+            assert_eq!(
+                contract.vesting_schedule(charlie, 1),
+                Some((now, 0, 60, 30, 0))
+            );
+            assert_eq!(contract.releasable_amount(bob), 0);
+        }
+
+        /// Test if restricted or locked balances cannot be merged away
+        #[ink::test]
+        fn merge_accounts_respects_restrictions() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(bob, 40), Ok(()));
+            assert_eq!(contract.set_locked_amount(bob, 10), Ok(()));
+            assert_eq!(
+                contract.merge_accounts(bob, charlie, false),
+                Err(Error::AmountLocked)
+            );
+            assert_eq!(contract.set_blacklisted_until(bob, Timestamp::MAX), Ok(()));
+            assert_eq!(
+                contract.merge_accounts(bob, charlie, false),
+                Err(Error::Blacklisted)
+            );
+            assert_eq!(contract.balance_of(bob), 40);
+            assert_eq!(contract.balance_of(charlie), 0);
+        }
+
+        /// Test if only the merged account itself or the owner can merge it
+        #[ink::test]
+        fn merge_accounts_requires_authorization() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(bob, 40), Ok(()));
+
+            set_caller(charlie);
+            assert_eq!(
+                contract.merge_accounts(bob, charlie, false),
+                Err(Error::NotOwner)
+            );
+            set_caller(bob);
+            assert_eq!(contract.merge_accounts(bob, charlie, false), Ok(()));
+            assert_eq!(contract.balance_of(charlie), 40);
+            assert!(!contract.is_blacklisted(bob));
+        }
+
+        /// Test if the owner manages the code hash allowlist. Strict-mode transfers
+        /// query `code_hash`, which the off-chain environment does not support.
+        #[ink::test]
+        fn code_hash_allowlist_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let code_hash = Hash::from([0x7; 32]);
+            assert!(!contract.is_code_hash_allowed(code_hash));
+            assert_eq!(contract.set_code_hash_allowed(code_hash, true), Ok(()));
+            assert!(contract.is_code_hash_allowed(code_hash));
+            assert_eq!(contract.set_code_hash_allowed(code_hash, false), Ok(()));
+            assert!(!contract.is_code_hash_allowed(code_hash));
+
+            set_caller(AccountId::from([0x2; 32]));
+            assert_eq!(
+                contract.set_code_hash_allowed(code_hash, true),
+                Err(Error::NotOwner)
+            );
+            assert_eq!(contract.set_code_hash_strict(true), Err(Error::NotOwner));
+        }
+
+        /// Test if dividend claims are rate limited by the claim cooldown
+        #[ink::test]
+        fn claim_cooldown_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(110);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.transfer(bob, 40), Ok(()));
+            assert_eq!(contract.set_claim_cooldown(30), Ok(()));
+            assert_eq!(contract.deposit_dividends(10), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(contract.claim_dividends(), Ok(4));
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.deposit_dividends(10), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(contract.claim_dividends(), Err(Error::ClaimCooldownActive));
+            for _ in 0..5 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(contract.claim_dividends(), Ok(4));
+        }
+
+        /// Test if only the owner can withdraw other tokens held by the contract.
+        /// The withdrawal itself is a cross-contract call and needs an on-chain test.
+        #[ink::test]
+        fn treasury_withdraw_requires_owner() {
+            let mut contract = Erc20::new_minimal(100);
+            set_caller(AccountId::from([0x2; 32]));
+            assert_eq!(
+                contract.treasury_withdraw(
+                    AccountId::from([0x8; 32]),
+                    AccountId::from([0x2; 32]),
+                    10
+                ),
+                Err(Error::NotOwner)
+            );
+        }
+
+        /// Test if transfers above the threshold need an opted-in recipient
+        #[ink::test]
+        fn large_transfer_optin_works() {
+            let mut contract = Erc20::new_minimal(1000);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_large_transfer_threshold(Some(100)), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(contract.set_large_transfer_optin(true), Ok(()));
+            assert!(contract.accepts_large_transfers(bob));
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.transfer(bob, 500), Ok(()));
+            assert_eq!(
+                contract.transfer(charlie, 500),
+                Err(Error::RecipientNotOptedIn)
+            );
+            assert_eq!(contract.transfer(charlie, 100), Ok(()));
+            assert_eq!(contract.balance_of(charlie), 100);
+        }
+
+        /// Test if claims above the per-claim cap pay the cap and carry the rest forward
+        #[ink::test]
+        fn max_reward_per_claim_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(110);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.transfer(bob, 50), Ok(()));
+            assert_eq!(contract.deposit_dividends(10), Ok(()));
+            assert_eq!(contract.set_max_reward_per_claim(Some(3)), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(contract.claim_dividends(), Ok(3));
+            assert_eq!(contract.dividends_of(bob), 2);
+            assert_eq!(contract.claim_dividends(), Ok(2));
+            assert_eq!(contract.dividends_of(bob), 0);
+            assert_eq!(contract.balance_of(bob), 55);
+        }
+
+        /// Test if a tip is forwarded alongside the token transfer and refunded on failure
+        #[ink::test]
+        fn transfer_with_tip_works() {
+            let contract_account = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let native_balance = |account| {
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(account)
+                    .unwrap_or_default()
+            };
+            // Simulate the attached value arriving at the contract
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(
+                contract_account,
+                native_balance(contract_account) + 50,
+            );
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(50);
+            let bob_native = native_balance(bob);
+
+            assert_eq!(contract.transfer_with_tip(bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(bob), 10);
+            assert_eq!(native_balance(bob), bob_native + 50);
+
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(
+                contract_account,
+                native_balance(contract_account) + 50,
+            );
+            let alice_native = native_balance(alice);
+            assert_eq!(
+                contract.transfer_with_tip(bob, 1000),
+                Err(Error::InsufficientBalance {
+                    required: 1000,
+                    available: 90
+                })
+            );
+            assert_eq!(contract.balance_of(bob), 10);
+            assert_eq!(native_balance(bob), bob_native + 50);
+            assert_eq!(native_balance(alice), alice_native + 50);
+
+            assert_eq!(contract.set_locked_amount(alice, 85), Ok(()));
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(
+                contract_account,
+                native_balance(contract_account) + 50,
+            );
+            assert_eq!(
+                contract.transfer_with_tip(bob, 10),
+                Err(Error::AmountLocked)
+            );
+            assert_eq!(contract.balance_of(bob), 10);
+            assert_eq!(native_balance(bob), bob_native + 50);
+            assert_eq!(native_balance(alice), alice_native + 100);
+        }
+
+        /// Test if the owner can revoke a subset of an account's approvals
+        #[ink::test]
+        fn revoke_allowances_of_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let django = AccountId::from([0x4; 32]);
+            set_caller(bob);
+            assert_eq!(contract.approve(charlie, 10), Ok(()));
+            assert_eq!(contract.approve(django, 20), Ok(()));
+            assert_eq!(
+                contract.revoke_allowances_of(bob, vec![charlie]),
+                Err(Error::NotOwner)
+            );
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.revoke_allowances_of(bob, vec![charlie]), Ok(()));
+            assert_eq!(contract.allowance(bob, charlie), 0);
+            assert_eq!(contract.allowance(bob, django), 20);
+            match emitted_events().last() {
+                Some(Event::Approval(Approval {
+                    owner,
+                    spender,
+                    value,
+                })) => assert_eq!((*owner, *spender, *value), (bob, charlie, 0)),
+                _ => panic!("expected an Approval event"),
+            }
+        }
+
+        /// Test if the activity cap blocks an account until the next window
+        #[ink::test]
+        fn daily_activity_cap_works() {
+            let mut contract = Erc20::new_minimal(1000);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_activity_window(60), Ok(()));
+            assert_eq!(contract.set_daily_activity_cap(Some(50)), Ok(()));
+            assert_eq!(contract.transfer(bob, 200), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(contract.transfer(charlie, 30), Ok(()));
+            assert_eq!(contract.transfer(charlie, 20), Ok(()));
+            assert_eq!(contract.daily_activity_of(bob), 50);
+            assert_eq!(
+                contract.transfer(charlie, 1),
+                Err(Error::DailyActivityCapExceeded)
+            );
+            assert_eq!(contract.set_activity_window(0), Err(Error::NotOwner));
+
+            for _ in 0..10 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(contract.daily_activity_of(bob), 0);
+            assert_eq!(contract.transfer(charlie, 50), Ok(()));
+            assert_eq!(contract.balance_of(charlie), 100);
+        }
+
+        /// Test if turning events off only suppresses the non-essential ones
+        #[ink::test]
+        fn emit_events_toggle_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.approve(alice, 20), Ok(()));
+            let before = emitted_events().len();
+            assert_eq!(contract.transfer_from(alice, bob, 5), Ok(()));
+            assert_eq!(contract.transfer(alice, 5), Ok(()));
+            assert_eq!(emitted_events().len(), before + 3);
+
+            assert_eq!(contract.set_emit_events(false), Ok(()));
+            assert!(!contract.emit_events());
+            let before = emitted_events().len();
+            assert_eq!(contract.transfer_from(alice, bob, 5), Ok(()));
+            assert_eq!(contract.transfer(alice, 5), Ok(()));
+            let events = emitted_events();
+            assert_eq!(events.len(), before + 1);
+            match events.last() {
+                Some(Event::Transfer(Transfer { from, to, value })) => {
+                    assert_eq!((*from, *to, *value), (Some(alice), Some(bob), 5))
+                }
+                _ => panic!("expected a Transfer event"),
+            }
+
+            set_caller(bob);
+            assert_eq!(contract.set_emit_events(true), Err(Error::NotOwner));
+        }
+
+        /// Test if a transfer's events are emitted only after all its writes
+        #[ink::test]
+        fn transfer_events_follow_effects() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let treasury = AccountId::from([0x5; 32]);
+            assert_eq!(contract.set_treasury(treasury), Ok(()));
+            assert_eq!(contract.set_flat_fee(2), Ok(()));
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, true), Ok(()));
+            assert_eq!(contract.assign_debt(bob, 3), Ok(()));
+            let before = emitted_events().len();
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+
+            let legs: Vec<_> = emitted_events()[before..]
+                .iter()
+                .map(|event| match event {
+                    Event::Transfer(Transfer { from, to, value }) => (*from, *to, *value),
+                    _ => panic!("expected a Transfer event"),
+                })
+                .collect();
+            assert_eq!(
+                legs,
+                vec![
+                    (Some(alice), Some(treasury), 2),
+                    (Some(alice), Some(alice), 3),
+                    (Some(alice), Some(bob), 5),
+                ]
+            );
+            assert_eq!(contract.balance_of(alice), 93);
+            assert_eq!(contract.balance_of(bob), 5);
+            assert_eq!(contract.debt_of(bob), 0);
+        }
+
+        /// Test if allowlist mode rejects approvals to spenders not on the allowlist
+        #[ink::test]
+        fn spender_allowlist_mode_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.approve(charlie, 5), Ok(()));
+            assert_eq!(contract.set_spender_allowlist_mode(true), Ok(()));
+            assert!(contract.spender_allowlist_mode(alice));
+            assert_eq!(contract.set_approved_spender(bob, true), Ok(()));
+            assert!(contract.is_approved_spender(alice, bob));
+
+            assert_eq!(contract.approve(bob, 10), Ok(()));
+            assert_eq!(contract.approve(charlie, 10), Err(Error::SpenderNotAllowed));
+            assert_eq!(contract.allowance(alice, charlie), 5);
+            assert_eq!(contract.approve(charlie, 0), Ok(()));
+
+            assert_eq!(contract.set_spender_allowlist_mode(false), Ok(()));
+            assert_eq!(contract.approve(charlie, 10), Ok(()));
+        }
+
+        /// Test if transfers only reach recipients that already hold the minimum balance
+        #[ink::test]
+        fn min_recipient_balance_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.set_min_recipient_balance(Some(10)), Ok(()));
+            assert_eq!(contract.min_recipient_balance(), Some(10));
+
+            assert_eq!(contract.transfer(bob, 1), Ok(()));
+            assert_eq!(
+                contract.transfer(charlie, 50),
+                Err(Error::RecipientBalanceTooLow)
+            );
+            assert_eq!(contract.balance_of(charlie), 0);
+
+            set_caller(bob);
+            assert_eq!(
+                contract.set_min_recipient_balance(None),
+                Err(Error::NotOwner)
+            );
+        }
+
+        /// Test if the partner NFT discount is configured by the owner only
+        #[ink::test]
+        fn set_nft_fee_discount_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let nft = AccountId::from([0x7; 32]);
+            assert_eq!(
+                contract.set_nft_fee_discount(Some(nft), 10_001),
+                Err(Error::InvalidFeeDiscount)
+            );
+            assert_eq!(contract.set_nft_fee_discount(Some(nft), 5_000), Ok(()));
+            assert_eq!(contract.nft_fee_discount(), (Some(nft), 5_000));
+
+            // Without a flat fee there is nothing to discount, so no call is made
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(contract.set_nft_fee_discount(None, 0), Err(Error::NotOwner));
+        }
+
+        /// Test if the fee flag switches the flat fee on and off
+        #[ink::test]
+        fn feature_flags_work() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_flat_fee(2), Ok(()));
+            assert!(!contract.feature_enabled(FEATURE_FEES));
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(bob), 10);
+
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, true), Ok(()));
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(bob), 18);
+
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, false), Ok(()));
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(bob), 28);
+
+            set_caller(bob);
+            assert_eq!(
+                contract.set_feature_flag(FEATURE_FEES, true),
+                Err(Error::NotOwner)
+            );
+        }
+
+        /// Test if a pair limit of one allows a single transfer between the pair
+        #[ink::test]
+        fn pair_transfer_limit_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(
+                contract.set_pair_transfer_limit(alice, bob, Some(1)),
+                Ok(())
+            );
+
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.pair_transfers(alice, bob), Some((1, 1)));
+            assert_eq!(
+                contract.transfer(bob, 10),
+                Err(Error::PairTransferLimitReached)
+            );
+            assert_eq!(contract.balance_of(bob), 10);
+            assert_eq!(contract.transfer(charlie, 10), Ok(()));
+            assert_eq!(
+                contract.transfer_split(vec![(charlie, 5_000), (bob, 5_000)], 10),
+                Err(Error::PairTransferLimitReached)
+            );
+            assert_eq!(contract.balance_of(charlie), 10);
+
+            set_caller(bob);
+            assert_eq!(
+                contract.set_pair_transfer_limit(alice, bob, None),
+                Err(Error::NotOwner)
+            );
+        }
+
+        /// Test if transfers are only allowed between permitted regions
+        #[ink::test]
+        fn region_restrictions_work() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_region(alice, Some(1)), Ok(()));
+            assert_eq!(contract.set_region(bob, Some(2)), Ok(()));
+            assert_eq!(contract.region_of(bob), Some(2));
+
+            assert_eq!(contract.transfer(bob, 10), Err(Error::RegionRestricted));
+            assert_eq!(contract.set_region_pair_allowed(1, 2, true), Ok(()));
+            assert!(contract.is_region_pair_allowed(1, 2));
+            assert!(!contract.is_region_pair_allowed(2, 1));
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+
+            assert_eq!(contract.transfer(charlie, 10), Ok(()));
+            assert_eq!(contract.set_untagged_restricted(true), Ok(()));
+            assert_eq!(contract.transfer(charlie, 10), Err(Error::RegionRestricted));
+            assert_eq!(contract.balance_of(charlie), 10);
+
+            set_caller(bob);
+            assert_eq!(contract.set_region(bob, None), Err(Error::NotOwner));
+        }
+
+        /// Test if holders redeem for native currency until the reserves run out
+        #[ink::test]
+        fn redeem_wind_down_works() {
+            let contract_account = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let native_balance = |account| {
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(account)
+                    .unwrap_or_default()
+            };
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(
+                contract_account,
+                150,
+            );
+            assert_eq!(contract.transfer(bob, 40), Ok(()));
+            assert_eq!(contract.redeem_wind_down(), Err(Error::WindDownNotActive));
+            assert_eq!(contract.enable_wind_down(2), Ok(()));
+            assert_eq!(contract.wind_down_rate(), Some(2));
+
+            set_caller(bob);
+            let bob_native = native_balance(bob);
+            assert_eq!(contract.redeem_wind_down(), Ok(()));
+            assert_eq!(contract.balance_of(bob), 0);
+            assert_eq!(contract.total_supply(), 60);
+            assert_eq!(native_balance(bob), bob_native + 80);
+            assert_eq!(
+                contract.redeem_wind_down(),
+                Err(Error::InsufficientBalance {
+                    required: 1,
+                    available: 0
+                })
+            );
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(
+                contract.redeem_wind_down(),
+                Err(Error::InsufficientReserves)
+            );
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 60);
+            assert_eq!(contract.total_supply(), 60);
+        }
+
+        /// Test if wind-down redemptions skip locked tokens and restricted accounts
+        #[ink::test]
+        fn redeem_wind_down_respects_restrictions() {
+            let contract_account = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(
+                contract_account,
+                1000,
+            );
+            assert_eq!(contract.transfer(bob, 40), Ok(()));
+            assert_eq!(contract.transfer(charlie, 20), Ok(()));
+            assert_eq!(contract.enable_wind_down(2), Ok(()));
+            assert_eq!(contract.set_locked_amount(bob, 30), Ok(()));
+            assert_eq!(contract.import_denylist(vec![charlie]), Ok(1));
+            assert_eq!(
+                contract.set_tainted(AccountId::from([0x1; 32]), true),
+                Ok(())
+            );
+            assert_eq!(contract.redeem_wind_down(), Err(Error::TaintedFunds));
+
+            set_caller(charlie);
+            assert_eq!(contract.redeem_wind_down(), Err(Error::Blacklisted));
+            assert_eq!(contract.balance_of(charlie), 20);
+
+            set_caller(bob);
+            assert_eq!(contract.redeem_wind_down(), Ok(()));
+            assert_eq!(contract.balance_of(bob), 30);
+            assert_eq!(contract.total_supply(), 90);
+        }
+
+        /// Test if only the owner can mint and minting grows the supply
+        #[cfg(feature = "mintable")]
+        #[ink::test]
+        fn mint_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.mint(bob, 50), Ok(()));
+            assert_eq!(contract.balance_of(bob), 50);
+            assert_eq!(contract.total_supply(), 150);
+            match emitted_events().last() {
+                Some(Event::Transfer(Transfer { from, to, value })) => {
+                    assert_eq!((*from, *to, *value), (None, Some(bob), 50))
+                }
+                _ => panic!("expected a Transfer event"),
+            }
+            assert_eq!(contract.mint(bob, Balance::MAX), Err(Error::Overflow));
+            assert_eq!(contract.total_supply(), 150);
+
+            set_caller(bob);
+            assert_eq!(contract.mint(bob, 50), Err(Error::NotAuthorized));
+            assert_eq!(contract.balance_of(bob), 50);
+            assert_eq!(contract.total_supply(), 150);
+        }
+
+        /// Test if a scheduled transfer only executes once due and can be cancelled before
+        #[ink::test]
+        fn scheduled_transfers_work() {
+            let escrow = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.schedule_transfer(bob, 30, now + 12), Ok(0));
+            assert_eq!(contract.schedule_transfer(bob, 20, now + 12), Ok(1));
+            assert_eq!(contract.balance_of(alice), 50);
+            assert_eq!(contract.balance_of(escrow), 50);
+
+            set_caller(bob);
+            assert_eq!(contract.execute_scheduled(0), Err(Error::NotYetDue));
+            assert_eq!(contract.cancel_scheduled(1), Err(Error::NotScheduler));
+            for _ in 0..2 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(contract.execute_scheduled(0), Ok(()));
+            assert_eq!(contract.balance_of(bob), 30);
+            assert_eq!(contract.execute_scheduled(0), Err(Error::UnknownSchedule));
+
+            set_caller(alice);
+            assert_eq!(contract.cancel_scheduled(1), Ok(()));
+            assert_eq!(contract.scheduled_transfer(1), None);
+            assert_eq!(contract.balance_of(alice), 70);
+            assert_eq!(contract.balance_of(escrow), 0);
+        }
+
+        /// Test if taxable events are only emitted for accounts with reporting enabled
+        #[cfg(feature = "mintable")]
+        #[ink::test]
+        fn taxable_events_work() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let taxable = || {
+                emitted_events()
+                    .into_iter()
+                    .filter_map(|event| match event {
+                        Event::TaxableEvent(TaxableEvent {
+                            account,
+                            kind,
+                            amount,
+                            ..
+                        }) => Some((account, kind, amount)),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert!(taxable().is_empty());
+
+            assert_eq!(contract.set_report_enabled(bob, true), Ok(()));
+            assert!(contract.report_enabled(bob));
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.mint(bob, 5), Ok(()));
+            assert_eq!(contract.mint(alice, 5), Ok(()));
+            assert_eq!(
+                taxable(),
+                vec![
+                    (bob, TaxableKind::TransferIn, 10),
+                    (bob, TaxableKind::Mint, 5),
+                ]
+            );
+
+            set_caller(bob);
+            assert_eq!(
+                contract.set_report_enabled(bob, false),
+                Err(Error::NotOwner)
+            );
+        }
+
+        /// Test if burning reduces the supply and cannot exceed the balance
+        #[cfg(feature = "burnable")]
+        #[ink::test]
+        fn burn_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            assert_eq!(contract.burn(30), Ok(()));
+            assert_eq!(contract.balance_of(alice), 70);
+            assert_eq!(contract.total_supply(), 70);
+            match emitted_events().last() {
+                Some(Event::Transfer(Transfer { from, to, value })) => {
+                    assert_eq!((*from, *to, *value), (Some(alice), None, 30))
+                }
+                _ => panic!("expected a Transfer event"),
+            }
+            assert_eq!(
+                contract.burn(71),
+                Err(Error::InsufficientBalance {
+                    required: 71,
+                    available: 70
+                })
+            );
+            assert_eq!(contract.total_supply(), 70);
+        }
+
+        /// Test if burn_from spends the allowance and reduces the supply
+        #[cfg(feature = "burnable")]
+        #[ink::test]
+        fn burn_from_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.approve(bob, 20), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(
+                contract.burn_from(alice, 21),
+                Err(Error::InsufficientAllowance {
+                    required: 21,
+                    available: 20
+                })
+            );
+            assert_eq!(contract.burn_from(alice, 15), Ok(()));
+            assert_eq!(contract.allowance(alice, bob), 5);
+            match emitted_events().last() {
+                Some(Event::Approval(Approval { value, .. })) => assert_eq!(*value, 5),
+                _ => panic!("expected an Approval event"),
+            }
+            assert_eq!(contract.balance_of(alice), 85);
+            assert_eq!(contract.total_supply(), 85);
+        }
+
+        /// Test if an unlimited allowance scores higher than a bounded one
+        #[ink::test]
+        fn approval_risk_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.approval_risk(alice, bob), 0);
+            assert_eq!(contract.set_approved_spender(bob, true), Ok(()));
+            assert_eq!(contract.set_approved_spender(charlie, true), Ok(()));
+            assert_eq!(contract.approve(bob, 10), Ok(()));
+            assert_eq!(contract.approve(charlie, Balance::MAX), Ok(()));
+
+            assert_eq!(contract.approval_risk(alice, bob), 0);
+            assert_eq!(contract.approval_risk(alice, charlie), 40);
+        }
+
+        /// Test if crediting a balance near the maximum fails instead of wrapping
+        #[ink::test]
+        fn transfer_to_full_balance_overflows() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            seed_balance(&mut contract, bob, Balance::MAX - 5);
+
+            assert_eq!(contract.transfer(bob, 10), Err(Error::Overflow));
+            assert_eq!(contract.balance_of(alice), 100);
+            assert_eq!(contract.balance_of(bob), Balance::MAX - 5);
+            assert_eq!(contract.transfer(bob, 5), Ok(()));
+            assert_eq!(contract.balance_of(bob), Balance::MAX);
+        }
+
+        /// Test if increases accumulate and decreases saturate at zero
+        #[ink::test]
+        fn increase_decrease_allowance_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.increase_allowance(bob, 10), Ok(()));
+            assert_eq!(contract.increase_allowance(bob, 15), Ok(()));
+            assert_eq!(contract.allowance(alice, bob), 25);
+            match emitted_events().last() {
+                Some(Event::Approval(Approval { value, .. })) => assert_eq!(*value, 25),
+                _ => panic!("expected an Approval event"),
+            }
+            assert_eq!(
+                contract.increase_allowance(bob, Balance::MAX),
+                Err(Error::Overflow)
+            );
+
+            set_caller(bob);
+            assert_eq!(contract.transfer_from(alice, charlie, 5), Ok(()));
+            set_caller(alice);
+            assert_eq!(contract.decrease_allowance(bob, 10), Ok(()));
+            assert_eq!(contract.allowance(alice, bob), 10);
+            assert_eq!(contract.allowance_utilization(alice, bob), (5, 15));
+            assert_eq!(contract.decrease_allowance(bob, 50), Ok(()));
+            assert_eq!(contract.allowance(alice, bob), 0);
+        }
+
+        /// Test if a conditional transfer is refunded only after its timeout
+        #[ink::test]
+        fn conditional_transfer_refund_works() {
+            let escrow = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_condition_timeout(12), Ok(()));
+            assert_eq!(contract.conditional_transfer(bob, 30, 7), Ok(0));
+            assert_eq!(contract.balance_of(escrow), 30);
+
+            // Without an oracle the condition cannot be checked
+            assert_eq!(contract.settle_condition(0), Err(Error::OracleCallFailed));
+            assert_eq!(contract.refund_conditional(0), Err(Error::NotYetDue));
+            for _ in 0..2 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            set_caller(bob);
+            assert_eq!(contract.refund_conditional(0), Ok(()));
+            assert_eq!(contract.balance_of(alice), 100);
+            assert_eq!(contract.balance_of(bob), 0);
+            assert_eq!(contract.conditional_transfer_of(0), None);
+            assert_eq!(contract.settle_condition(0), Err(Error::UnknownSchedule));
+        }
+
+        /// Test if an unlimited allowance is not decremented by transfer_from
+        #[ink::test]
+        fn unlimited_allowance_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.approve(bob, Balance::MAX), Ok(()));
+
+            set_caller(bob);
+            for _ in 0..3 {
+                assert_eq!(contract.transfer_from(alice, charlie, 10), Ok(()));
+            }
+            #[cfg(feature = "burnable")]
+            assert_eq!(contract.burn_from(alice, 10), Ok(()));
+            assert_eq!(contract.allowance(alice, bob), Balance::MAX);
+            assert_eq!(contract.balance_of(charlie), 30);
+        }
+
+        /// Test if a minter is blocked once it reaches its lifetime mint cap
+        #[cfg(feature = "mintable")]
+        #[ink::test]
+        fn lifetime_mint_cap_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_max_lifetime_mint(Some(50)), Ok(()));
+            assert_eq!(contract.mint(bob, 20), Ok(()));
+            assert_eq!(contract.mint(bob, 30), Ok(()));
+            assert_eq!(contract.lifetime_minted_of(alice), 50);
+            assert_eq!(contract.mint(bob, 1), Err(Error::LifetimeMintCapExceeded));
+            assert_eq!(contract.total_supply(), 150);
+
+            // Burning does not restore minting authority
+            set_caller(bob);
+            assert_eq!(contract.burn(50), Ok(()));
+            set_caller(alice);
+            assert_eq!(contract.mint(bob, 1), Err(Error::LifetimeMintCapExceeded));
+        }
+
+        /// Test if a planned path is checked hop by hop against the simulated balances
+        #[ink::test]
+        fn path_allowed_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let django = AccountId::from([0x4; 32]);
+            assert_eq!(
+                contract.path_allowed(vec![(alice, bob, 50), (bob, charlie, 50)]),
+                Ok(())
+            );
+            assert_eq!(
+                contract.path_allowed(vec![(alice, bob, 50), (bob, charlie, 60)]),
+                Err(Error::InsufficientBalance {
+                    required: 60,
+                    available: 50
+                })
+            );
+
+            assert_eq!(
+                contract.set_blacklisted_until(django, Timestamp::MAX),
+                Ok(())
+            );
+            assert_eq!(
+                contract.path_allowed(vec![(alice, bob, 50), (bob, django, 50)]),
+                Err(Error::Blacklisted)
+            );
+            assert_eq!(contract.balance_of(bob), 0);
+        }
+
+        /// Test if pausing halts token movements until unpaused
+        #[cfg(feature = "pausable")]
+        #[ink::test]
+        fn pause_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.pause(), Ok(()));
+            assert!(contract.paused());
+            assert_eq!(contract.transfer(bob, 10), Err(Error::ContractPaused));
+            assert_eq!(contract.approve(bob, 10), Err(Error::ContractPaused));
+            assert_eq!(contract.mint(bob, 10), Err(Error::ContractPaused));
+            assert_eq!(contract.burn(10), Err(Error::ContractPaused));
+            assert_eq!(contract.balance_of(alice), 100);
+            assert_eq!(contract.allowance(alice, bob), 0);
+
+            set_caller(bob);
+            assert_eq!(contract.unpause(), Err(Error::NotAuthorized));
+            set_caller(alice);
+            assert_eq!(contract.unpause(), Ok(()));
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(bob), 10);
+        }
+
+        /// Test if ownership only moves once the proposed owner accepts it
+        #[ink::test]
+        fn two_step_ownership_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer_ownership(bob), Ok(()));
+            assert_eq!(contract.owner(), Some(alice));
+            assert_eq!(contract.pending_owner(), Some(bob));
+
+            set_caller(charlie);
+            assert_eq!(contract.accept_ownership(), Err(Error::NotPendingOwner));
+            let before = emitted_events().len();
+            set_caller(bob);
+            assert_eq!(contract.accept_ownership(), Ok(()));
+            assert_eq!(contract.owner(), Some(bob));
+            assert_eq!(contract.pending_owner(), None);
+            match emitted_events()[before..].last() {
+                Some(Event::OwnershipTransferred(OwnershipTransferred {
+                    previous_owner,
+                    new_owner,
+                })) => assert_eq!((*previous_owner, *new_owner), (alice, Some(bob))),
+                _ => panic!("expected an OwnershipTransferred event"),
+            }
+        }
+
+        /// Test if a batch transfer is applied all or nothing
+        #[ink::test]
+        fn transfer_batch_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(
+                contract.transfer_batch(vec![bob, charlie], vec![10]),
+                Err(Error::LengthMismatch)
+            );
+            assert_eq!(
+                contract.transfer_batch(vec![bob, charlie], vec![60, 50]),
+                Err(Error::InsufficientBalance {
+                    required: 110,
+                    available: 100
+                })
+            );
+            assert_eq!(
+                contract.transfer_batch(vec![bob, charlie], vec![Balance::MAX, 2]),
+                Err(Error::Overflow)
+            );
+            assert_eq!(contract.balance_of(alice), 100);
+            assert_eq!(contract.balance_of(bob), 0);
+
+            let before = emitted_events().len();
+            assert_eq!(
+                contract.transfer_batch(vec![bob, charlie], vec![60, 40]),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(bob), 60);
+            assert_eq!(contract.balance_of(charlie), 40);
+            assert_eq!(emitted_events().len(), before + 2);
+        }
+
+        /// Test if queued redemptions are paid in order as reserves allow
+        #[ink::test]
+        fn redemption_queue_works() {
+            let contract_account = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let native_balance = |account| {
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(account)
+                    .unwrap_or_default()
+            };
+            assert_eq!(
+                contract.request_redemption(10),
+                Err(Error::RedemptionsNotEnabled)
+            );
+            assert_eq!(contract.set_redemption_rate(Some(2)), Ok(()));
+            assert_eq!(contract.transfer(bob, 30), Ok(()));
+            assert_eq!(contract.transfer(charlie, 30), Ok(()));
+            set_caller(bob);
+            assert_eq!(contract.request_redemption(30), Ok(0));
+            set_caller(charlie);
+            assert_eq!(contract.request_redemption(30), Ok(1));
+            assert_eq!(contract.balance_of(contract_account), 60);
+
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(
+                contract_account,
+                100,
+            );
+            let bob_native = native_balance(bob);
+            assert_eq!(contract.process_redemptions(5), Ok(1));
+            assert_eq!(native_balance(bob), bob_native + 60);
+            assert_eq!(contract.redemption_of(0), None);
+            assert_eq!(contract.redemption_of(1), Some((charlie, 30)));
+            assert_eq!(contract.total_supply(), 70);
+
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(
+                contract_account,
+                native_balance(contract_account) + 60,
+            );
+            assert_eq!(contract.process_redemptions(5), Ok(1));
+            assert_eq!(contract.total_supply(), 40);
+            assert_eq!(contract.balance_of(contract_account), 0);
+        }
+
+        /// Test if redemptions respect transfer restrictions and leave the wrapped backing alone
+        #[ink::test]
+        fn redemption_respects_restrictions() {
+            let contract_account = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_redemption_rate(Some(1)), Ok(()));
+            assert_eq!(contract.transfer(bob, 30), Ok(()));
+            assert_eq!(contract.transfer(charlie, 30), Ok(()));
+            assert_eq!(contract.set_locked_amount(bob, 25), Ok(()));
+            assert_eq!(contract.import_denylist(vec![charlie]), Ok(1));
+
+            set_caller(charlie);
+            assert_eq!(contract.request_redemption(10), Err(Error::Blacklisted));
+            set_caller(bob);
+            assert_eq!(contract.request_redemption(10), Err(Error::AmountLocked));
+            assert_eq!(contract.request_redemption(5), Ok(0));
+
+            // Native currency that only backs wrapped tokens
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract_account, 40);
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(40);
+            assert_eq!(contract.deposit(), Ok(()));
+            assert_eq!(contract.process_redemptions(5), Ok(0));
+            assert_eq!(contract.redemption_of(0), Some((bob, 5)));
+        }
+
+        /// Test if supports_feature reflects the optional messages compiled in
+        #[ink::test]
+        fn supports_feature_works() {
+            let mut contract = Erc20::new_minimal(100);
+            assert_eq!(contract.contract_version(), CONTRACT_VERSION);
+            assert_eq!(
+                contract.supports_feature(ink_lang::selector_bytes!("mint")),
+                cfg!(feature = "mintable")
+            );
+            assert_eq!(
+                contract.supports_feature(ink_lang::selector_bytes!("burn_from")),
+                cfg!(feature = "burnable")
+            );
+            assert_eq!(
+                contract.supports_feature(ink_lang::selector_bytes!("pause")),
+                cfg!(feature = "pausable")
+            );
+            assert!(!contract.supports_feature(ink_lang::selector_bytes!("transfer")));
+
+            #[cfg(not(feature = "mintable"))]
+            assert_eq!(
+                contract.mint(AccountId::from([0x2; 32]), 1),
+                Err(Error::FeatureNotSupported)
+            );
+            #[cfg(feature = "mintable")]
+            assert_eq!(contract.mint(AccountId::from([0x2; 32]), 1), Ok(()));
+        }
+
+        /// Test if account_flags sets exactly the bits of the account's states
+        #[ink::test]
+        fn account_flags_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.account_flags(alice), FLAG_OWNER | FLAG_TREASURY);
+            assert_eq!(contract.account_flags(bob), 0);
+
+            assert_eq!(contract.set_tainted(bob, true), Ok(()));
+            assert_eq!(contract.set_report_enabled(bob, true), Ok(()));
+            set_caller(bob);
+            assert_eq!(contract.set_large_transfer_optin(true), Ok(()));
+            assert_eq!(
+                contract.account_flags(bob),
+                FLAG_TAINTED | FLAG_TAX_REPORTING | FLAG_LARGE_TRANSFER_OPTIN
+            );
+        }
+
+        /// Test if a signed permit sets the allowance once and cannot be replayed
+        #[ink::test]
+        fn permit_works() {
+            use secp256k1::{Message, PublicKey, SecretKey, SECP256K1};
+
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let secret_key = SecretKey::from_slice(&[0x7; 32]).unwrap();
+            let public_key = PublicKey::from_secret_key(SECP256K1, &secret_key).serialize();
+            let mut owner = [0u8; 32];
+            ink_env::hash_bytes::<ink_env::hash::Blake2x256>(&public_key, &mut owner);
+            let owner = AccountId::from(owner);
+            let sign = |digest: [u8; 32]| {
+                let message = Message::from_slice(&digest).unwrap();
+                let (recovery_id, compact) = SECP256K1
+                    .sign_ecdsa_recoverable(&message, &secret_key)
+                    .serialize_compact();
+                let mut signature = [0u8; 65];
+                signature[..64].copy_from_slice(&compact);
+                signature[64] = recovery_id.to_i32() as u8;
+                signature
+            };
+
+            let signature = sign(contract.permit_digest(owner, bob, 40, 100));
+            assert_eq!(
+                contract.permit(owner, bob, 50, 100, signature),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(contract.permit(owner, bob, 40, 100, signature), Ok(()));
+            assert_eq!(contract.allowance(owner, bob), 40);
+            assert_eq!(contract.nonce_of(owner), 1);
+            assert_eq!(
+                contract.permit(owner, bob, 40, 100, signature),
+                Err(Error::InvalidSignature)
+            );
+
+            for _ in 0..20 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            let signature = sign(contract.permit_digest(owner, bob, 10, 100));
+            assert_eq!(
+                contract.permit(owner, bob, 10, 100, signature),
+                Err(Error::PermitExpired)
+            );
+            assert_eq!(contract.allowance(owner, bob), 40);
+        }
+
+        /// Test if a 30/70 fee split burns and forwards the right parts of the fee
+        #[ink::test]
+        fn fee_split_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(1000);
+            let bob = AccountId::from([0x2; 32]);
+            let treasury = AccountId::from([0x9; 32]);
+            assert_eq!(contract.set_treasury(treasury), Ok(()));
+            assert_eq!(contract.set_flat_fee(10), Ok(()));
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, true), Ok(()));
+            assert_eq!(
+                contract.set_fee_split_bps(10_001),
+                Err(Error::InvalidFeeSplit)
+            );
+            assert_eq!(contract.set_fee_split_bps(3_000), Ok(()));
+
+            assert_eq!(contract.transfer(bob, 100), Ok(()));
+            assert_eq!(contract.balance_of(bob), 90);
+            assert_eq!(contract.balance_of(treasury), 7);
+            assert_eq!(contract.total_supply(), 997);
+            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 900);
+        }
+
+        /// Test if snapshots keep the balances from before later transfers
+        #[ink::test]
+        fn snapshots_work() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.snapshot(), Ok(1));
+            assert_eq!(contract.transfer(bob, 20), Ok(()));
+            assert_eq!(contract.snapshot(), Ok(2));
+            assert_eq!(contract.snapshot(), Ok(3));
+            assert_eq!(contract.transfer(bob, 30), Ok(()));
+
+            assert_eq!(contract.balance_of_at(bob, 1), 10);
+            assert_eq!(contract.balance_of_at(alice, 1), 90);
+            assert_eq!(contract.balance_of_at(bob, 2), 30);
+            assert_eq!(contract.balance_of_at(bob, 3), 30);
+            assert_eq!(contract.balance_of_at(bob, 4), 60);
+            assert_eq!(contract.balance_of(bob), 60);
+
+            set_caller(bob);
+            assert_eq!(contract.snapshot(), Err(Error::NotOwner));
+        }
+
+        /// Test if transfers need the current terms of service accepted by both parties
+        #[ink::test]
+        fn terms_of_service_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_feature_flag(FEATURE_TOS, true), Ok(()));
+            assert_eq!(contract.bump_tos_version(), Ok(1));
+            assert_eq!(contract.transfer(bob, 10), Err(Error::TosNotAccepted));
+
+            assert_eq!(contract.accept_tos(2), Err(Error::UnknownTosVersion));
+            assert_eq!(contract.accept_tos(1), Ok(()));
+            set_caller(bob);
+            assert_eq!(contract.accept_tos(1), Ok(()));
+            assert_eq!(contract.accepted_tos(bob), 1);
+            set_caller(alice);
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+
+            assert_eq!(contract.bump_tos_version(), Ok(2));
+            assert_eq!(contract.transfer(bob, 10), Err(Error::TosNotAccepted));
+            assert_eq!(contract.accept_tos(2), Ok(()));
+            assert_eq!(contract.transfer(bob, 10), Err(Error::TosNotAccepted));
+            set_caller(bob);
+            assert_eq!(contract.accept_tos(2), Ok(()));
+            set_caller(alice);
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(bob), 20);
+
+            set_caller(bob);
+            assert_eq!(contract.bump_tos_version(), Err(Error::NotOwner));
+        }
+
+        /// Test if only holders as of a snapshot claim its reward, once each
+        #[ink::test]
+        fn claim_if_held_at_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(bob, 20), Ok(()));
+            assert_eq!(contract.snapshot(), Ok(1));
+            assert_eq!(contract.transfer(charlie, 20), Ok(()));
+            assert_eq!(contract.set_snapshot_reward(1, Some((10, 5))), Ok(()));
+
+            // Rewards can only be offered for snapshots already taken
+            assert_eq!(
+                contract.set_snapshot_reward(0, Some((10, 5))),
+                Err(Error::UnknownSnapshot)
+            );
+            assert_eq!(
+                contract.set_snapshot_reward(2, Some((10, 5))),
+                Err(Error::UnknownSnapshot)
+            );
+            assert_eq!(contract.snapshot_reward(2), None);
+
+            set_caller(bob);
+            assert_eq!(contract.claim_if_held_at(1, 6), Err(Error::RewardMismatch));
+            assert_eq!(contract.claim_if_held_at(1, 5), Ok(()));
+            assert!(contract.has_claimed_at(bob, 1));
+            assert_eq!(contract.claim_if_held_at(1, 5), Err(Error::AlreadyClaimed));
+            assert_eq!(contract.balance_of(bob), 25);
+            assert_eq!(contract.balance_of(alice), 55);
+
+            // Charlie only received tokens after the snapshot
+            set_caller(charlie);
+            assert_eq!(contract.claim_if_held_at(1, 5), Err(Error::NotEligible));
+            assert_eq!(contract.claim_if_held_at(2, 5), Err(Error::NotEligible));
+            assert_eq!(
+                contract.set_snapshot_reward(2, Some((0, 5))),
+                Err(Error::NotOwner)
+            );
+        }
+
+        /// Test if a transfer fails when the fee leaves the recipient less than its minimum
+        #[ink::test]
+        fn transfer_min_received_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_flat_fee(2), Ok(()));
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, true), Ok(()));
+
+            assert_eq!(
+                contract.transfer_min_received(bob, 10, 9),
+                Err(Error::SlippageExceeded)
+            );
+            assert_eq!(contract.balance_of(bob), 0);
+            assert_eq!(contract.transfer_min_received(bob, 10, 8), Ok(()));
+            assert_eq!(contract.balance_of(bob), 8);
+        }
+
+        /// Test if the cap can only be lowered, and never below the total supply
+        #[ink::test]
+        fn lower_cap_works() {
+            let mut contract = Erc20::new_minimal(100);
+            assert_eq!(contract.cap(), None);
+            assert_eq!(contract.lower_cap(99), Err(Error::InvalidCap));
+            assert_eq!(contract.lower_cap(200), Ok(()));
+            assert_eq!(contract.lower_cap(150), Ok(()));
+            assert_eq!(contract.cap(), Some(150));
+            match emitted_events().last() {
+                Some(Event::CapLowered(CapLowered { old_cap, new_cap })) => {
+                    assert_eq!((*old_cap, *new_cap), (Some(200), 150))
+                }
+                _ => panic!("expected a CapLowered event"),
+            }
+
+            assert_eq!(contract.lower_cap(150), Err(Error::InvalidCap));
+            assert_eq!(contract.lower_cap(180), Err(Error::InvalidCap));
+            assert_eq!(contract.lower_cap(100), Ok(()));
+            #[cfg(feature = "mintable")]
+            assert_eq!(
+                contract.mint(AccountId::from([0x2; 32]), 1),
+                Err(Error::CapExceeded)
+            );
+
+            set_caller(AccountId::from([0x2; 32]));
+            assert_eq!(contract.lower_cap(100), Err(Error::NotOwner));
+        }
+
+        /// Test if grouped accounts only transfer within their group, and ungrouped ones anywhere
+        #[ink::test]
+        fn group_restrictions_work() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let django = AccountId::from([0x4; 32]);
+            assert_eq!(contract.set_group(alice, 1), Ok(()));
+            assert_eq!(contract.set_group(bob, 1), Ok(()));
+            assert_eq!(contract.set_group(charlie, 2), Ok(()));
+            assert_eq!(contract.group_of(charlie), 2);
+            assert_eq!(contract.transfer(charlie, 10), Ok(()));
+
+            assert_eq!(contract.set_feature_flag(FEATURE_GROUPS, true), Ok(()));
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(
+                contract.transfer(charlie, 10),
+                Err(Error::CrossGroupTransfer)
+            );
+            assert_eq!(contract.transfer(django, 10), Ok(()));
+            set_caller(django);
+            assert_eq!(contract.transfer(charlie, 5), Ok(()));
+
+            set_caller(alice);
+            assert_eq!(contract.set_group(charlie, 0), Ok(()));
+            assert_eq!(contract.transfer(charlie, 10), Ok(()));
+            assert_eq!(contract.balance_of(charlie), 25);
+
+            set_caller(bob);
+            assert_eq!(contract.set_group(bob, 2), Err(Error::NotOwner));
+        }
+
+        /// Test if holder-seconds add up the balance held over time
+        #[ink::test]
+        fn holder_seconds_work() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let advance_seconds = |seconds: u64| {
+                // Each block advances the timestamp by 6 milliseconds
+                for _ in 0..seconds * 1000 / 6 {
+                    ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+                }
+            };
+
+            advance_seconds(3);
+            assert_eq!(contract.holder_seconds(alice), 300);
+            assert_eq!(contract.transfer(bob, 40), Ok(()));
+            advance_seconds(3);
+            assert_eq!(contract.holder_seconds(alice), 300 + 60 * 3);
+            assert_eq!(contract.holder_seconds(bob), 40 * 3);
+
+            set_caller(bob);
+            assert_eq!(contract.transfer(alice, 40), Ok(()));
+            advance_seconds(3);
+            assert_eq!(contract.holder_seconds(alice), 300 + 60 * 3 + 100 * 3);
+            assert_eq!(contract.holder_seconds(bob), 40 * 3);
+        }
+
+        /// Test if a locked part of a balance cannot be transferred while the rest can
+        #[ink::test]
+        fn locked_amount_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_locked_amount(alice, 70), Ok(()));
+            assert_eq!(contract.locked_amount_of(alice), 70);
+
+            assert_eq!(contract.transfer(bob, 20), Ok(()));
+            assert_eq!(contract.transfer(bob, 11), Err(Error::AmountLocked));
+            assert_eq!(
+                contract.transfer_batch(vec![bob, bob], vec![5, 6]),
+                Err(Error::AmountLocked)
+            );
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(alice), 70);
+
+            assert_eq!(contract.set_locked_amount(alice, 0), Ok(()));
+            assert_eq!(contract.transfer(bob, 70), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(contract.set_locked_amount(bob, 0), Err(Error::NotOwner));
+        }
+
+        /// Test if the health read tells a healthy contract from a degraded one
+        #[ink::test]
+        fn health_works() {
+            let contract_account = set_contract_account();
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(
+                contract_account,
+                200,
+            );
+            let mut contract = Erc20::new_minimal(100);
+            assert_eq!(contract.set_redemption_rate(Some(2)), Ok(()));
+            assert_eq!(
+                contract.health(),
+                Health {
+                    paused: false,
+                    backing_ratio_ok: true,
+                    reserves: 200,
+                    minting_disabled: !cfg!(feature = "mintable"),
+                }
+            );
+
+            assert_eq!(contract.set_redemption_rate(Some(3)), Ok(()));
+            assert_eq!(contract.lower_cap(100), Ok(()));
+            #[cfg(feature = "pausable")]
+            assert_eq!(contract.pause(), Ok(()));
+            assert_eq!(
+                contract.health(),
+                Health {
+                    paused: cfg!(feature = "pausable"),
+                    backing_ratio_ok: false,
+                    reserves: 200,
+                    minting_disabled: true,
+                }
+            );
+        }
+
+        /// Test if sweeping only revokes allowances untouched since before the cutoff
+        #[ink::test]
+        fn sweep_stale_allowances_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let django = AccountId::from([0x4; 32]);
+            assert_eq!(contract.approve(bob, 10), Ok(()));
+            assert_eq!(contract.approve(charlie, 10), Ok(()));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            let cutoff = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.approve(django, 10), Ok(()));
+            // Spending from an allowance counts as touching it
+            set_caller(charlie);
+            assert_eq!(contract.transfer_from(alice, charlie, 1), Ok(()));
+
+            set_caller(alice);
+            let pairs = vec![
+                (alice, bob),
+                (alice, charlie),
+                (alice, django),
+                (bob, alice),
+            ];
+            let before = emitted_events().len();
+            assert_eq!(
+                contract.sweep_stale_allowances(pairs.clone(), cutoff),
+                Ok(1)
+            );
+            assert_eq!(emitted_events().len(), before + 1);
+            assert_eq!(contract.allowance(alice, bob), 0);
+            assert_eq!(contract.allowance(alice, charlie), 9);
+            assert_eq!(contract.allowance(alice, django), 10);
+            assert_eq!(contract.sweep_stale_allowances(pairs, cutoff), Ok(0));
+
+            set_caller(bob);
+            assert_eq!(
+                contract.sweep_stale_allowances(Vec::new(), cutoff),
+                Err(Error::NotOwner)
+            );
+        }
+
+        /// Test if a swap escrows the creator's tokens and only they can cancel it
+        #[ink::test]
+        fn swaps_work() {
+            let escrow = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let token = AccountId::from([0x9; 32]);
+            assert_eq!(contract.create_swap(bob, 30, token, 5), Ok(0));
+            assert_eq!(contract.swap(0), Some((alice, bob, 30, token, 5)));
+            assert_eq!(contract.balance_of(escrow), 30);
+            assert_eq!(contract.accept_swap(0), Err(Error::WrongCounterparty));
+            assert_eq!(contract.accept_swap(1), Err(Error::SwapNotFound));
+
+            set_caller(bob);
+            assert_eq!(contract.cancel_swap(0), Err(Error::WrongCounterparty));
+
+            set_caller(alice);
+            assert_eq!(contract.cancel_swap(0), Ok(()));
+            assert_eq!(contract.swap(0), None);
+            assert_eq!(contract.balance_of(alice), 100);
+            assert_eq!(contract.cancel_swap(0), Err(Error::SwapNotFound));
+        }
+
+        /// Test if fees are routed to several destinations by weight, with dust to the first
+        #[ink::test]
+        fn fee_destinations_work() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let treasury = AccountId::from([0x5; 32]);
+            let dev_fund = AccountId::from([0x6; 32]);
+            let charity = AccountId::from([0x7; 32]);
+            assert_eq!(contract.set_flat_fee(10), Ok(()));
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, true), Ok(()));
+            assert_eq!(
+                contract.set_fee_destinations(vec![(treasury, 5000), (dev_fund, 3000)]),
+                Err(Error::InvalidWeights)
+            );
+            let destinations = vec![(treasury, 3334), (dev_fund, 3333), (charity, 3333)];
+            assert_eq!(contract.set_fee_destinations(destinations.clone()), Ok(()));
+            assert_eq!(contract.fee_destinations(), destinations);
+
+            let before = emitted_events().len();
+            assert_eq!(contract.transfer(bob, 50), Ok(()));
+            assert_eq!(emitted_events().len(), before + 4);
+            assert_eq!(contract.balance_of(treasury), 4);
+            assert_eq!(contract.balance_of(dev_fund), 3);
+            assert_eq!(contract.balance_of(charity), 3);
+            assert_eq!(contract.balance_of(bob), 40);
+            assert_eq!(contract.total_supply(), 100);
+
+            set_caller(bob);
+            assert_eq!(
+                contract.set_fee_destinations(Vec::new()),
+                Err(Error::NotOwner)
+            );
+        }
+
+        /// Test if only the first claimants of an airdrop get tokens, once each
+        #[cfg(feature = "mintable")]
+        #[ink::test]
+        fn first_come_airdrop_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let django = AccountId::from([0x4; 32]);
+            assert_eq!(contract.set_first_come_airdrop(2, 10), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(contract.claim_first_come(20), Err(Error::RewardMismatch));
+            assert_eq!(contract.claim_first_come(10), Ok(()));
+            assert_eq!(contract.claim_first_come(10), Err(Error::AlreadyClaimed));
+            set_caller(charlie);
+            assert_eq!(contract.claim_first_come(10), Ok(()));
+            set_caller(django);
+            assert_eq!(contract.claim_first_come(10), Err(Error::AirdropExhausted));
+
+            assert_eq!(contract.balance_of(bob), 10);
+            assert_eq!(contract.balance_of(charlie), 10);
+            assert_eq!(contract.balance_of(django), 0);
+            assert_eq!(contract.total_supply(), 120);
+            assert_eq!(contract.first_come_airdrop(), (0, 10));
+        }
+
+        /// Test if only the owner sets the mirror target, and transfers work without one
+        #[ink::test]
+        fn mirror_target_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let mirror = AccountId::from([0x9; 32]);
+            assert_eq!(contract.mirror_target(), None);
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.set_mirror_target(Some(mirror)), Ok(()));
+            assert_eq!(contract.mirror_target(), Some(mirror));
+            assert_eq!(contract.set_mirror_target(None), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(contract.set_mirror_target(Some(bob)), Err(Error::NotOwner));
+        }
+
+        /// Test if mirror updates wait until the transfer is written and its events are emitted
+        #[ink::test]
+        fn mirror_updates_are_sent_last() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(
+                contract.set_mirror_target(Some(AccountId::from([0x9; 32]))),
+                Ok(())
+            );
+            let events_before = emitted_events().len();
+            let queued = contract.non_reentrant(|contract| {
+                contract.transfer_from_to(&alice, &bob, 10)?;
+                let queued = contract.mirror_queue.clone();
+                // The off-chain environment cannot call the mirror, so drop it before the flush
+                contract.mirror_target = None;
+                Ok(queued)
+            });
+            assert_eq!(queued, Ok(vec![(alice, 100, 90), (bob, 0, 10)]));
+            assert_eq!(emitted_events().len(), events_before + 1);
+            assert!(contract.mirror_queue.is_empty());
+        }
+
+        /// Test if transfers are blocked, then capped, then unrestricted as the launch advances
+        #[ink::test]
+        fn launch_phases_work() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_feature_flag(FEATURE_LAUNCH, true), Ok(()));
+            assert_eq!(contract.set_launch_transfer_cap(5), Ok(()));
+            assert_eq!(contract.transfer(bob, 50), Ok(()));
+            assert_eq!(contract.transfer(charlie, 20), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(contract.transfer(alice, 1), Err(Error::LaunchRestricted));
+
+            set_caller(alice);
+            assert_eq!(contract.advance_launch_phase(), Ok(LAUNCH_PHASE_CAPPED));
+            assert_eq!(contract.set_launch_allowlisted(charlie, true), Ok(()));
+            assert_eq!(contract.launch_limits(charlie), (5, true));
+            set_caller(bob);
+            assert_eq!(contract.transfer(alice, 5), Ok(()));
+            assert_eq!(contract.transfer(alice, 6), Err(Error::LaunchRestricted));
+            set_caller(charlie);
+            assert_eq!(contract.transfer(alice, 10), Ok(()));
+
+            set_caller(alice);
+            assert_eq!(contract.advance_launch_phase(), Ok(LAUNCH_PHASE_OPEN));
+            assert_eq!(
+                contract.advance_launch_phase(),
+                Err(Error::InvalidLaunchPhase)
+            );
+            set_caller(bob);
+            assert_eq!(contract.transfer(alice, 45), Ok(()));
+            assert_eq!(contract.advance_launch_phase(), Err(Error::NotOwner));
+        }
+
+        /// Test if the voting supply leaves out non-voting balances while the total supply stays
+        #[ink::test]
+        fn voting_supply_works() {
+            let escrow = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let treasury = AccountId::from([0x5; 32]);
+            let reserve = AccountId::from([0x6; 32]);
+            assert_eq!(contract.set_treasury(treasury), Ok(()));
+            assert_eq!(contract.transfer(treasury, 10), Ok(()));
+            assert_eq!(contract.transfer(reserve, 20), Ok(()));
+            let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.schedule_transfer(bob, 5, now + 60), Ok(0));
+            assert_eq!(contract.balance_of(escrow), 5);
+            assert_eq!(contract.voting_supply(), 85);
+
+            assert_eq!(contract.set_non_voting(reserve, true), Ok(()));
+            assert_eq!(contract.set_non_voting(reserve, true), Ok(()));
+            assert_eq!(contract.non_voting_accounts(), vec![reserve]);
+            assert_eq!(contract.voting_supply(), 65);
+            assert_eq!(contract.total_supply(), 100);
+
+            assert_eq!(contract.set_non_voting(reserve, false), Ok(()));
+            assert_eq!(contract.voting_supply(), 85);
+
+            set_caller(bob);
+            assert_eq!(contract.set_non_voting(bob, true), Err(Error::NotOwner));
+        }
+
+        /// Test if compact mode emits equal and opposite diffs keyed by account hash
+        #[ink::test]
+        fn compact_events_work() {
+            set_contract_account();
+            let mut contract = Erc20::new(100, None, None, 18, true);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let before = emitted_events().len();
+            assert_eq!(contract.transfer(bob, 30), Ok(()));
+
+            let diffs: Vec<_> = emitted_events()[before..]
+                .iter()
+                .map(|event| match event {
+                    Event::CompactDiff(CompactDiff {
+                        account_hash,
+                        delta,
+                    }) => (*account_hash, *delta),
+                    _ => panic!("expected only CompactDiff events"),
+                })
+                .collect();
+            assert_eq!(
+                diffs,
+                vec![(account_hash(&alice), -30), (account_hash(&bob), 30)]
+            );
+            assert_ne!(account_hash(&alice), account_hash(&bob));
+        }
+
+        /// Test if staked shares redeem for more tokens once rewards raise the share price
+        #[ink::test]
+        fn staking_works() {
+            let pool = set_contract_account();
+            let mut contract = Erc20::new_minimal(1000);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(bob, 100), Ok(()));
+            assert_eq!(contract.transfer(charlie, 100), Ok(()));
+            assert_eq!(
+                contract.add_staking_rewards(10),
+                Err(Error::NoEligibleHolders)
+            );
+
+            set_caller(bob);
+            assert_eq!(contract.stake(100), Ok(100));
+            assert_eq!(contract.shares_of(bob), 100);
+            assert_eq!(contract.balance_of(bob), 0);
+            assert_eq!(contract.balance_of(pool), 100);
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.add_staking_rewards(100), Ok(()));
+            assert_eq!(contract.share_price(), 2 * SHARE_PRICE_PRECISION);
+
+            set_caller(charlie);
+            assert_eq!(contract.stake(100), Ok(50));
+            set_caller(bob);
+            assert_eq!(contract.unstake(101), Err(Error::InsufficientShares));
+            assert_eq!(contract.unstake(100), Ok(200));
+            assert_eq!(contract.balance_of(bob), 200);
+            set_caller(charlie);
+            assert_eq!(contract.unstake(50), Ok(100));
+            assert_eq!(contract.balance_of(pool), 0);
+            assert_eq!(contract.total_supply(), 1000);
+        }
+
+        /// Test if the last activity of each account is its latest balance change
+        #[ink::test]
+        fn last_activity_batch_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let start = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            set_caller(bob);
+            assert_eq!(contract.approve(charlie, 5), Ok(()));
+
+            assert_eq!(
+                contract.last_activity_batch(vec![alice, bob, charlie]),
+                vec![start + 6, start + 6, 0]
+            );
+        }
+
+        /// Test if no fee is charged during a fee holiday and the usual fee outside it
+        #[ink::test]
+        fn fee_holidays_work() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_flat_fee(2), Ok(()));
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, true), Ok(()));
+            let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                contract.set_fee_holidays(vec![(now + 6, now + 6)]),
+                Err(Error::InvalidFeeHoliday)
+            );
+            assert_eq!(contract.set_fee_holidays(vec![(now + 6, now + 12)]), Ok(()));
+
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(bob), 8);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(bob), 18);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(bob), 26);
+        }
+
+        /// Test if proven transfers need a valid Merkle proof while plain transfers stay open
+        #[ink::test]
+        fn transfer_to_proven_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let django = AccountId::from([0x4; 32]);
+            let eve = AccountId::from([0x5; 32]);
+            let leaves = [bob, charlie, django].map(|account| account_hash(&account));
+            let left = merkle_parent(&leaves[0], &leaves[1]);
+            let root = merkle_parent(&left, &leaves[2]);
+            assert_eq!(
+                contract.transfer_to_proven(bob, 10, vec![leaves[1], leaves[2]]),
+                Err(Error::RecipientNotProven)
+            );
+            assert_eq!(contract.set_recipient_root(Some(root)), Ok(()));
+
+            assert_eq!(
+                contract.transfer_to_proven(bob, 10, vec![leaves[1], leaves[2]]),
+                Ok(())
+            );
+            assert_eq!(contract.transfer_to_proven(django, 10, vec![left]), Ok(()));
+            assert_eq!(
+                contract.transfer_to_proven(charlie, 10, vec![leaves[1], leaves[2]]),
+                Err(Error::RecipientNotProven)
+            );
+            assert_eq!(
+                contract.transfer_to_proven(eve, 10, vec![left]),
+                Err(Error::RecipientNotProven)
+            );
+            assert_eq!(contract.transfer(eve, 10), Ok(()));
+            assert_eq!(contract.balance_of(bob), 10);
+            assert_eq!(contract.balance_of(django), 10);
+            assert_eq!(contract.balance_of(charlie), 0);
+        }
+
+        /// Test if an auto-reinvesting holder gets staked shares while another gets tokens
+        #[ink::test]
+        fn auto_reinvest_works() {
+            let pool = set_contract_account();
+            let mut contract = Erc20::new_minimal(300);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(bob, 100), Ok(()));
+            assert_eq!(contract.transfer(charlie, 100), Ok(()));
+            set_caller(bob);
+            assert_eq!(contract.set_auto_reinvest(true), Ok(()));
+            assert!(contract.auto_reinvest(bob));
+
+            set_caller(alice);
+            assert_eq!(contract.deposit_dividends(60), Ok(()));
+            assert_eq!(contract.balance_of(pool), 60);
+
+            set_caller(bob);
+            assert_eq!(contract.claim_dividends(), Ok(25));
+            assert_eq!(contract.balance_of(bob), 100);
+            assert_eq!(contract.shares_of(bob), 25);
+            set_caller(charlie);
+            assert_eq!(contract.claim_dividends(), Ok(25));
+            assert_eq!(contract.balance_of(charlie), 125);
+            assert_eq!(contract.shares_of(charlie), 0);
+
+            set_caller(bob);
+            assert_eq!(contract.unstake(25), Ok(25));
+            assert_eq!(contract.balance_of(bob), 125);
+        }
+
+        /// Test if an account's scheduled transfers and swaps are listed as pending
+        #[ink::test]
+        fn pending_operations_work() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.schedule_transfer(bob, 10, now + 60), Ok(0));
+            assert_eq!(contract.schedule_transfer(charlie, 5, now + 60), Ok(1));
+            assert_eq!(contract.create_swap(bob, 20, charlie, 3), Ok(0));
+            assert_eq!(contract.cancel_scheduled(1), Ok(()));
+
+            let expected = vec![
+                PendingOp {
+                    kind: PendingOpKind::ScheduledTransfer,
+                    id: 0,
+                    amount: 10,
+                    executable_at: Some(now + 60),
+                },
+                PendingOp {
+                    kind: PendingOpKind::Swap,
+                    id: 0,
+                    amount: 20,
+                    executable_at: None,
+                },
+            ];
+            assert_eq!(contract.pending_operations(bob), expected);
+            assert_eq!(contract.pending_operations(alice), expected);
+            assert_eq!(contract.pending_operations(charlie), Vec::new());
+            assert!(!contract.pending_ops.contains(charlie));
+
+            assert_eq!(contract.cancel_swap(0), Ok(()));
+            assert_eq!(contract.pending_operations(bob), expected[..1]);
+        }
+
+        /// Test if a large mint trips the circuit breaker and a small one does not
+        #[cfg(feature = "mintable")]
+        #[ink::test]
+        fn circuit_breaker_works() {
+            let mut contract = Erc20::new_minimal(1000);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(
+                contract.set_circuit_breaker(Some((0, 60))),
+                Err(Error::InvalidCircuitBreaker)
+            );
+            assert_eq!(contract.set_circuit_breaker(Some((1000, 60))), Ok(()));
+            assert_eq!(contract.circuit_breaker(), Some((1000, 60)));
+
+            assert_eq!(contract.mint(bob, 60), Ok(()));
+            assert!(!contract.paused());
+            assert_eq!(contract.mint(bob, 50), Ok(()));
+            assert!(contract.paused());
+            match emitted_events().last() {
+                Some(Event::CircuitBreakerTripped(CircuitBreakerTripped {
+                    supply_at_window_start,
+                    supply,
+                })) => assert_eq!((*supply_at_window_start, *supply), (1000, 1110)),
+                _ => panic!("expected a CircuitBreakerTripped event"),
+            }
+            assert_eq!(contract.mint(bob, 1), Err(Error::ContractPaused));
+
+            assert_eq!(contract.reset_circuit_breaker(), Ok(()));
+            assert!(!contract.paused());
+            assert_eq!(contract.mint(bob, 100), Ok(()));
+            assert!(!contract.paused());
+        }
+
+        /// Test if paying the fee in the fee token fails without one and moves the full value when no fee is due
+        #[ink::test]
+        fn transfer_fee_in_token_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let fee_token = AccountId::from([0x9; 32]);
+            assert_eq!(contract.set_flat_fee(2), Ok(()));
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, true), Ok(()));
+            assert_eq!(
+                contract.transfer_fee_in_token(bob, 10),
+                Err(Error::FeeTokenPaymentFailed)
+            );
+            assert_eq!(contract.balance_of(bob), 0);
+
+            assert_eq!(contract.set_fee_token(Some((fee_token, 3))), Ok(()));
+            assert_eq!(contract.fee_token(), Some((fee_token, 3)));
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, false), Ok(()));
+            assert_eq!(contract.transfer_fee_in_token(bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(bob), 10);
+        }
+
+        /// Test if seized funds can be returned during the appeal window and released after it
+        #[ink::test]
+        fn seize_to_escrow_works() {
+            let escrow = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let recovery = AccountId::from([0x9; 32]);
+            assert_eq!(contract.transfer(bob, 30), Ok(()));
+            assert_eq!(contract.transfer(charlie, 20), Ok(()));
+            assert_eq!(contract.set_appeal_period(12), Ok(()));
+            assert_eq!(contract.seize_to_escrow(bob), Err(Error::NotBlacklisted));
+            assert_eq!(contract.import_denylist(vec![bob, charlie]), Ok(2));
+
+            let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.seize_to_escrow(bob), Ok(30));
+            assert_eq!(contract.seize_to_escrow(charlie), Ok(20));
+            assert_eq!(contract.seized_of(bob), Some((30, now + 12)));
+            assert_eq!(contract.balance_of(escrow), 50);
+            assert_eq!(
+                contract.release_seized(bob, recovery),
+                Err(Error::AppealPending)
+            );
+
+            // Bob's appeal succeeds in time
+            assert_eq!(contract.return_seized(bob), Ok(()));
+            assert_eq!(contract.balance_of(bob), 30);
+            assert_eq!(contract.return_seized(bob), Err(Error::NothingSeized));
+
+            for _ in 0..2 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(
+                contract.return_seized(charlie),
+                Err(Error::AppealWindowClosed)
+            );
+            assert_eq!(contract.release_seized(charlie, recovery), Ok(()));
+            assert_eq!(contract.balance_of(recovery), 20);
+            assert_eq!(contract.balance_of(escrow), 0);
+        }
+
+        /// Test if transfers with provenance form a chain through their parent refs
+        #[ink::test]
+        fn transfer_with_provenance_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(
+                contract.transfer_with_provenance(bob, 30, Some(0)),
+                Err(Error::UnknownProvenanceRef)
+            );
+            assert_eq!(contract.transfer_with_provenance(bob, 30, None), Ok(0));
+            set_caller(bob);
+            assert_eq!(
+                contract.transfer_with_provenance(charlie, 20, Some(0)),
+                Ok(1)
+            );
+            set_caller(charlie);
+            assert_eq!(contract.transfer_with_provenance(alice, 10, Some(1)), Ok(2));
+
+            let links: Vec<_> = emitted_events()
+                .into_iter()
+                .filter_map(|event| match event {
+                    Event::Provenance(Provenance {
+                        id,
+                        parent_ref,
+                        from,
+                        to,
+                        ..
+                    }) => Some((id, parent_ref, from, to)),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(
+                links,
+                vec![
+                    (0, None, alice, bob),
+                    (1, Some(0), bob, charlie),
+                    (2, Some(1), charlie, alice),
+                ]
+            );
+            assert_eq!(
+                contract.provenance_of(2),
+                Some((Some(1), charlie, alice, 10))
+            );
+            assert_eq!(contract.provenance_of(3), None);
+            assert_eq!(contract.balance_of(charlie), 10);
+        }
+
+        /// Test if interest accrues over time and compounds on every balance change
+        #[ink::test]
+        fn interest_accrual_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(1_000_000);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(
+                contract.set_interest_rate(Some(0)),
+                Err(Error::InvalidInterestRate)
+            );
+            // 1% per millisecond
+            assert_eq!(
+                contract.set_interest_rate(Some(INTEREST_RATE_PRECISION / 100)),
+                Ok(())
+            );
+            assert_eq!(contract.accrued_interest(alice), 0);
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.accrued_interest(alice), 60_000);
+            assert_eq!(contract.transfer(bob, 60_000), Ok(()));
+            assert_eq!(contract.balance_of(alice), 1_000_000);
+            assert_eq!(contract.accrued_interest(alice), 0);
+            assert_eq!(contract.total_supply(), 1_060_000);
+
+            // The next accrual compounds on the balance including the minted interest
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.accrued_interest(bob), 3_600);
+            assert_eq!(contract.accrued_interest(alice), 60_000);
+            set_caller(bob);
+            assert_eq!(contract.transfer(bob, 0), Ok(()));
+            assert_eq!(contract.balance_of(bob), 63_600);
+            assert_eq!(contract.total_supply(), 1_063_600);
+
+            assert_eq!(contract.set_interest_rate(None), Err(Error::NotOwner));
+            set_caller(alice);
+            assert_eq!(contract.set_interest_rate(None), Ok(()));
+            assert_eq!(contract.accrued_interest(alice), 0);
+        }
+
+        /// Test if a rebate mints back the configured portion of the fees paid
+        #[cfg(feature = "mintable")]
+        #[ink::test]
+        fn claim_rebate_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let treasury = AccountId::from([0x9; 32]);
+            assert_eq!(contract.set_treasury(treasury), Ok(()));
+            assert_eq!(contract.set_flat_fee(4), Ok(()));
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, true), Ok(()));
+            assert_eq!(
+                contract.set_rebate_rate(10_001),
+                Err(Error::InvalidRebateRate)
+            );
+            assert_eq!(contract.set_rebate_rate(2_500), Ok(()));
+
+            for _ in 0..3 {
+                assert_eq!(contract.transfer(bob, 10), Ok(()));
+            }
+            assert_eq!(contract.fees_paid_of(alice), 12);
+            assert_eq!(contract.fees_paid_of(bob), 0);
+
+            assert_eq!(contract.claim_rebate(), Ok(3));
+            assert_eq!(contract.balance_of(alice), 73);
+            assert_eq!(contract.total_supply(), 103);
+            assert_eq!(contract.fees_paid_of(alice), 0);
+            assert_eq!(contract.claim_rebate(), Ok(0));
+        }
+
+        /// Test if categorized transfers spend independent per-category budgets
+        #[ink::test]
+        fn transfer_categorized_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(
+                contract.transfer_categorized(bob, 1, 0),
+                Err(Error::CategoryBudgetExceeded)
+            );
+            assert_eq!(contract.set_category_budget(alice, 0, 30), Ok(()));
+            assert_eq!(contract.set_category_budget(alice, 1, 10), Ok(()));
+
+            assert_eq!(contract.transfer_categorized(bob, 20, 0), Ok(()));
+            assert_eq!(contract.category_budget(alice, 0), 10);
+            assert_eq!(
+                contract.transfer_categorized(bob, 11, 0),
+                Err(Error::CategoryBudgetExceeded)
+            );
+            assert_eq!(contract.transfer_categorized(bob, 10, 1), Ok(()));
+            assert_eq!(contract.category_budget(alice, 0), 10);
+            assert_eq!(contract.category_budget(alice, 1), 0);
+            assert_eq!(contract.balance_of(bob), 30);
+
+            set_caller(bob);
+            assert_eq!(
+                contract.set_category_budget(bob, 0, 30),
+                Err(Error::NotOwner)
+            );
+        }
+
+        /// Test if a global freeze blocks all movement except by thawed accounts
+        #[ink::test]
+        fn global_freeze_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(bob, 20), Ok(()));
+            assert_eq!(contract.approve(charlie, 10), Ok(()));
+            assert_eq!(contract.set_global_freeze(true), Ok(()));
+
+            assert_eq!(contract.transfer(bob, 1), Err(Error::GloballyFrozen));
+            set_caller(bob);
+            assert_eq!(contract.transfer(alice, 1), Err(Error::GloballyFrozen));
+
+            // A thawed spender still cannot move a frozen owner's tokens
+            set_caller(alice);
+            assert_eq!(contract.thaw(charlie), Ok(()));
+            set_caller(charlie);
+            assert_eq!(
+                contract.transfer_from(alice, charlie, 10),
+                Err(Error::GloballyFrozen)
+            );
+
+            set_caller(alice);
+            assert_eq!(contract.thaw(bob), Ok(()));
+            assert!(contract.is_thawed(bob));
+            set_caller(bob);
+            // Frozen recipients cannot be credited, even by a thawed sender
+            assert_eq!(contract.transfer(alice, 5), Err(Error::GloballyFrozen));
+            assert_eq!(contract.balance_of(alice), 80);
+            assert_eq!(contract.transfer(charlie, 5), Ok(()));
+            assert_eq!(contract.balance_of(charlie), 5);
+            #[cfg(feature = "mintable")]
+            {
+                set_caller(alice);
+                assert_eq!(contract.thaw(alice), Ok(()));
+                assert_eq!(
+                    contract.mint(AccountId::from([0x4; 32]), 5),
+                    Err(Error::GloballyFrozen)
+                );
+                assert_eq!(contract.mint(bob, 5), Ok(()));
+            }
+
+            set_caller(alice);
+            assert_eq!(contract.set_global_freeze(false), Ok(()));
+            assert_eq!(contract.transfer(bob, 1), Ok(()));
+            set_caller(charlie);
+            assert_eq!(contract.transfer_from(alice, charlie, 10), Ok(()));
+        }
+
+        /// Test if balances decay at the demurrage rate into the pool
+        #[ink::test]
+        fn demurrage_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(1_000_000);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let pool = AccountId::from([0x9; 32]);
+            assert_eq!(
+                contract.set_demurrage(Some((0, pool))),
+                Err(Error::InvalidDemurrage)
+            );
+            // 1% per millisecond
+            assert_eq!(
+                contract.set_demurrage(Some((DEMURRAGE_RATE_PRECISION / 100, pool))),
+                Ok(())
+            );
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.decayed_balance(alice), 940_000);
+            assert_eq!(contract.balance_of(alice), 940_000);
+            assert_eq!(
+                contract.transfer(bob, 940_001),
+                Err(Error::InsufficientBalance {
+                    required: 940001,
+                    available: 940000
+                })
+            );
+            assert_eq!(contract.transfer(bob, 40_000), Ok(()));
+            assert_eq!(contract.balance_of(alice), 900_000);
+            assert_eq!(contract.balance_of(bob), 40_000);
+            assert_eq!(contract.balance_of(pool), 60_000);
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.balance_of(bob), 37_600);
+            assert_eq!(contract.balance_of(pool), 60_000);
+            set_caller(bob);
+            assert_eq!(contract.transfer(bob, 0), Ok(()));
+            assert_eq!(contract.balance_of(pool), 62_400);
+            assert_eq!(contract.total_supply(), 1_000_000);
+        }
+
+        /// Test if a disputable transfer stays locked until reversed or finalized
+        #[ink::test]
+        fn transfer_disputable_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer_disputable(bob, 30, 1), Ok(0));
+            assert_eq!(contract.balance_of(bob), 30);
+            set_caller(bob);
+            assert_eq!(contract.transfer(charlie, 1), Err(Error::AmountLocked));
+            assert_eq!(contract.reverse_disputable(0), Err(Error::NotSender));
+            assert_eq!(
+                contract.finalize_disputable(0),
+                Err(Error::DisputeWindowOpen)
+            );
+
+            set_caller(alice);
+            assert_eq!(contract.reverse_disputable(0), Ok(()));
+            assert_eq!(contract.balance_of(alice), 100);
+            assert_eq!(contract.balance_of(bob), 0);
+            assert_eq!(contract.reverse_disputable(0), Err(Error::DisputeNotFound));
+
+            assert_eq!(contract.transfer_disputable(bob, 20, 0), Ok(1));
+            assert_eq!(contract.pending_operations(bob).len(), 1);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                contract.reverse_disputable(1),
+                Err(Error::DisputeWindowClosed)
+            );
+            set_caller(bob);
+            assert_eq!(contract.finalize_disputable(1), Ok(()));
+            assert!(contract.pending_operations(bob).is_empty());
+            assert_eq!(contract.transfer(charlie, 20), Ok(()));
+            assert_eq!(contract.balance_of(charlie), 20);
+        }
 
-            if allowance < value {
-                return Err(Error::InsufficientAllowance);
-            }
+        /// Test if fees fill the raffle pool and a draw pays it out to an entrant
+        #[ink::test]
+        fn draw_winner_works() {
+            let pool = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let treasury = AccountId::from([0x9; 32]);
+            assert_eq!(contract.set_treasury(treasury), Ok(()));
+            assert_eq!(contract.set_flat_fee(10), Ok(()));
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, true), Ok(()));
+            assert_eq!(
+                contract.set_raffle_share(10_001),
+                Err(Error::InvalidRaffleShare)
+            );
+            assert_eq!(contract.set_raffle_share(5_000), Ok(()));
+            assert_eq!(contract.draw_winner(), Err(Error::EmptyPrizePool));
 
-            self.transfer_from_to(&from, &to, value)?;
-            self.allowances
-                .insert((&from, &caller), &(allowance - value));
-            Ok(())
+            assert_eq!(contract.transfer(bob, 30), Ok(()));
+            assert_eq!(contract.transfer(charlie, 30), Ok(()));
+            assert_eq!(contract.raffle(), (5_000, 10));
+            assert_eq!(contract.balance_of(pool), 10);
+            assert_eq!(contract.balance_of(treasury), 10);
+            assert_eq!(contract.draw_winner(), Err(Error::NoEligibleHolders));
+
+            set_caller(bob);
+            assert_eq!(contract.enter_raffle(), Ok(()));
+            assert_eq!(contract.enter_raffle(), Err(Error::RaffleEntryRejected));
+            set_caller(charlie);
+            assert_eq!(contract.enter_raffle(), Ok(()));
+            assert_eq!(contract.draw_winner(), Err(Error::NotOwner));
+
+            set_caller(AccountId::from([0x1; 32]));
+            let winner = contract.draw_winner().unwrap();
+            assert!(winner == bob || winner == charlie);
+            assert_eq!(contract.balance_of(winner), 30);
+            assert_eq!(contract.balance_of(pool), 0);
+            assert_eq!(contract.raffle(), (5_000, 0));
+            assert_eq!(contract.draw_winner(), Err(Error::EmptyPrizePool));
         }
-    }
 
-    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
-    /// module and test functions are marked with a `#[test]` attribute.
-    /// The below code is technically just normal Rust code.
-    #[cfg(test)]
-    mod tests {
-        /// Imports all the definitions from the outer scope so we can use them here.
-        use super::*;
+        /// Test if tokens minted into a non-transferable class cannot be sent
+        #[cfg(feature = "mintable")]
+        #[ink::test]
+        fn share_classes_work() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.mint_with_class(bob, 50, 0), Ok(()));
+            assert_eq!(contract.mint_with_class(charlie, 50, 2), Ok(()));
+            assert_eq!(contract.class_of(charlie), 2);
+            assert_eq!(
+                contract.mint_with_class(charlie, 1, 0),
+                Err(Error::ClassMismatch)
+            );
+            assert_eq!(contract.set_class_transferable(2, false), Ok(()));
 
-        use ink_env::AccountId;
-        /// Imports `ink_lang` so we can use `#[ink::test]`.
-        use ink_lang as ink;
+            set_caller(charlie);
+            assert_eq!(contract.transfer(bob, 10), Err(Error::ClassNotTransferable));
+            set_caller(bob);
+            assert_eq!(contract.transfer(charlie, 10), Ok(()));
+            assert_eq!(contract.balance_of(charlie), 60);
 
-        /// Test if the default constructor does its job.
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.set_class_transferable(2, true), Ok(()));
+            set_caller(charlie);
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+        }
+
+        /// Test if Class A balances vote while Class B balances add no voting power
+        #[cfg(feature = "mintable")]
         #[ink::test]
-        fn new_works() {
-            let contract = Erc20::new(777);
-            assert_eq!(contract.total_supply(), 777);
+        fn non_voting_classes_do_not_vote() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            // Class 1 is A, class 2 is B
+            assert_eq!(contract.set_class_voting(2, false), Ok(()));
+            assert!(!contract.is_class_voting(2));
+            set_caller(bob);
+            assert_eq!(contract.delegate(bob), Ok(()));
+            set_caller(charlie);
+            assert_eq!(contract.delegate(charlie), Ok(()));
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.mint_with_class(bob, 30, 1), Ok(()));
+            assert_eq!(contract.mint_with_class(charlie, 20, 2), Ok(()));
+            assert_eq!(contract.get_votes(bob), 30);
+            assert_eq!(contract.get_votes(charlie), 0);
+            assert_eq!(contract.set_class_voting(2, true), Err(Error::ClassInUse));
+            assert_eq!(contract.set_class_voting(0, false), Err(Error::ClassInUse));
+
+            // Redelegating a Class B balance still adds nothing
+            set_caller(charlie);
+            assert_eq!(contract.delegate(bob), Ok(()));
+            assert_eq!(contract.get_votes(bob), 30);
+            assert_eq!(contract.transfer(bob, 5), Ok(()));
+            assert_eq!(contract.get_votes(bob), 35);
+
+            let block = ink_env::block_number::<ink_env::DefaultEnvironment>();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.get_past_votes(charlie, block), Ok(0));
+            assert_eq!(contract.get_past_votes(bob, block), Ok(35));
         }
 
-        /// We if balance works
+        /// Test if the PSP22 interface moves tokens and maps errors like the inherent messages
         #[ink::test]
-        fn balance_works() {
-            let contract = Erc20::new(100);
-            assert_eq!(contract.total_supply(), 100);
-            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 100);
-            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 0);
+        fn psp22_works() {
+            let pool = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(PSP22::transfer(&mut contract, bob, 10, Vec::new()), Ok(()));
+            assert_eq!(PSP22::balance_of(&contract, bob), 10);
+            assert_eq!(PSP22::total_supply(&contract), 100);
+            assert_eq!(
+                PSP22::transfer(&mut contract, bob, 91, Vec::new()),
+                Err(PSP22Error::InsufficientBalance)
+            );
+            assert_eq!(PSP22::approve(&mut contract, bob, 5), Ok(()));
+            assert_eq!(PSP22::increase_allowance(&mut contract, bob, 5), Ok(()));
+            assert_eq!(PSP22::allowance(&contract, alice, bob), 10);
+
+            set_caller(bob);
+            assert_eq!(
+                PSP22::transfer_from(&mut contract, alice, bob, 11, Vec::new()),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+            assert_eq!(
+                PSP22::transfer_from(&mut contract, alice, bob, 10, Vec::new()),
+                Ok(())
+            );
+            assert_eq!(
+                PSP22::transfer(&mut contract, pool, 1, Vec::new()),
+                Err(PSP22Error::Custom(String::from("TransferToContract")))
+            );
         }
 
-        /// Test if transfer works
+        /// Test if granted roles open their messages to non-owners until revoked
+        #[cfg(all(feature = "mintable", feature = "pausable"))]
         #[ink::test]
-        fn transfer_works() {
-            let mut contract = Erc20::new(100);
+        fn roles_work() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.grant_role(Role::Minter, bob), Ok(()));
+            match emitted_events().last() {
+                Some(Event::RoleGranted(RoleGranted { role, account })) => {
+                    assert_eq!((*role, *account), (Role::Minter, bob))
+                }
+                _ => panic!("expected a RoleGranted event"),
+            }
+            assert!(contract.has_role(Role::Minter, bob));
+            assert!(!contract.has_role(Role::Pauser, bob));
+
+            set_caller(bob);
+            assert_eq!(contract.mint(bob, 10), Ok(()));
+            assert_eq!(contract.pause(), Err(Error::NotAuthorized));
+            assert_eq!(contract.grant_role(Role::Pauser, bob), Err(Error::NotOwner));
+
+            set_caller(alice);
+            assert_eq!(contract.revoke_role(Role::Minter, bob), Ok(()));
+            set_caller(bob);
+            assert_eq!(contract.mint(bob, 10), Err(Error::NotAuthorized));
+            assert_eq!(contract.balance_of(bob), 10);
+        }
+
+        /// Test if transfers and mints to the zero address are rejected
+        #[ink::test]
+        fn zero_address_rejected() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let zero = AccountId::from([0x0; 32]);
+            assert_eq!(contract.transfer(zero, 10), Err(Error::ZeroAddress));
+            assert_eq!(
+                PSP22::transfer(&mut contract, zero, 10, Vec::new()),
+                Err(PSP22Error::ZeroRecipientAddress)
+            );
+            assert_eq!(contract.approve(alice, 10), Ok(()));
+            assert_eq!(
+                contract.transfer_from(alice, zero, 10),
+                Err(Error::ZeroAddress)
+            );
+            assert_eq!(contract.balance_of(alice), 100);
+            assert_eq!(contract.balance_of(zero), 0);
+            #[cfg(feature = "mintable")]
+            assert_eq!(contract.mint(zero, 10), Err(Error::ZeroAddress));
             assert_eq!(contract.total_supply(), 100);
-            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 100);
-            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 0);
-            assert_eq!(contract.transfer(AccountId::from([0x0; 32]), 50), Ok(()));
-            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 50);
-            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 50);
         }
+
+        /// Test if a batch transfer_from spends the allowance once and fails as a whole
         #[ink::test]
-        fn transfer_from_works() {
-            let mut contract = Erc20::new(100);
-            assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 100);
-            contract
-                .approve(AccountId::from([0x1; 32]), 20)
-                .unwrap_or_default();
-            contract
-                .transfer_from(AccountId::from([0x1; 32]), AccountId::from([0x0; 32]), 10)
-                .unwrap_or_default();
-            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 10);
+        fn transfer_from_batch_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let django = AccountId::from([0x4; 32]);
+            assert_eq!(contract.approve(bob, 50), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(
+                contract.transfer_from_batch(alice, vec![(charlie, 30), (django, 30)]),
+                Err(Error::InsufficientAllowance {
+                    required: 60,
+                    available: 50
+                })
+            );
+            assert_eq!(
+                contract.transfer_from_batch(alice, vec![(charlie, 20), (alice, 0)]),
+                Ok(())
+            );
+            assert_eq!(
+                contract.transfer_from_batch(alice, vec![(charlie, 10), (django, 20)]),
+                Ok(())
+            );
+            let transfers = emitted_events()
+                .iter()
+                .filter(|event| matches!(event, Event::Transfer(_)))
+                .count();
+            // The initial mint, then one per recipient
+            assert_eq!(transfers, 5);
+            assert_eq!(contract.balance_of(charlie), 30);
+            assert_eq!(contract.balance_of(django), 20);
+            assert_eq!(contract.allowance(alice, bob), 0);
+            assert_eq!(
+                contract.transfer_from_batch(alice, vec![(charlie, 1)]),
+                Err(Error::InsufficientAllowance {
+                    required: 1,
+                    available: 0
+                })
+            );
+            assert_eq!(contract.balance_of(alice), 50);
         }
 
+        /// Test if permit digests are bound to the deployment through the domain separator
         #[ink::test]
-        fn allowance_works() {
-            let mut contract = Erc20::new(100);
+        fn domain_separator_works() {
+            let contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let separator = contract.domain_separator();
+            let digest = contract.permit_digest(alice, bob, 10, 100);
+            let mut expected = [0u8; 32];
+            ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(
+                &(separator, alice, bob, 10 as Balance, 100 as Timestamp, 0u64),
+                &mut expected,
+            );
+            assert_eq!(digest, expected);
+
+            set_contract_account();
+            assert_ne!(contract.domain_separator(), separator);
+            assert_ne!(contract.permit_digest(alice, bob, 10, 100), digest);
+        }
+
+        /// Test if a transfer with data is validated before the receiver is consulted
+        #[ink::test]
+        fn transfer_with_data_validates_first() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let zero = AccountId::from([0x0; 32]);
+            // The off-chain environment cannot tell contracts apart, so only the
+            // checks ahead of the receiver callback are exercised here
+            assert_eq!(
+                contract.transfer_with_data(bob, 101, vec![1]),
+                Err(Error::InsufficientBalance {
+                    required: 101,
+                    available: 100
+                })
+            );
+            assert_eq!(
+                contract.transfer_with_data(zero, 10, vec![1]),
+                Err(Error::ZeroAddress)
+            );
+            assert_eq!(contract.balance_of(bob), 0);
+        }
+
+        /// Test if the total supply is recorded lazily for each snapshot
+        #[cfg(all(feature = "mintable", feature = "burnable"))]
+        #[ink::test]
+        fn total_supply_at_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.snapshot(), Ok(1));
+            assert_eq!(contract.total_supply_at(1), 100);
+            assert_eq!(contract.mint(bob, 50), Ok(()));
+            assert_eq!(contract.snapshot(), Ok(2));
+            assert_eq!(contract.snapshot(), Ok(3));
+            assert_eq!(contract.burn(30), Ok(()));
+            assert_eq!(contract.mint(bob, 5), Ok(()));
+
+            assert_eq!(contract.total_supply_at(1), 100);
+            assert_eq!(contract.total_supply_at(2), 150);
+            assert_eq!(contract.total_supply_at(3), 150);
+            assert_eq!(contract.total_supply_at(4), 125);
+            assert_eq!(contract.total_supply(), 125);
+        }
+
+        /// Test if delegated votes follow transfers and are looked up per block
+        #[ink::test]
+        fn delegation_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            // Undelegated tokens carry no votes
+            assert_eq!(contract.get_votes(accounts.alice), 0);
+            assert_eq!(contract.delegate(accounts.alice), Ok(()));
+            assert_eq!(contract.delegates(accounts.alice), Some(accounts.alice));
+            assert_eq!(contract.get_votes(accounts.alice), 100);
+            let block = ink_env::block_number::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                contract.get_past_votes(accounts.alice, block),
+                Err(Error::FutureLookup)
+            );
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer(accounts.bob, 30), Ok(()));
+            assert_eq!(contract.get_votes(accounts.alice), 70);
+            // Bob has not delegated, so the transferred votes are gone
+            assert_eq!(contract.get_votes(accounts.bob), 0);
+            assert_eq!(contract.delegate(accounts.charlie), Ok(()));
+            assert_eq!(contract.get_votes(accounts.alice), 0);
+            assert_eq!(contract.get_votes(accounts.charlie), 70);
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.get_past_votes(accounts.alice, block), Ok(100));
+            assert_eq!(contract.get_past_votes(accounts.alice, block + 1), Ok(0));
+            assert_eq!(contract.get_past_votes(accounts.charlie, block), Ok(0));
+            assert_eq!(contract.get_past_votes(accounts.charlie, block + 1), Ok(70));
+        }
+
+        /// Test if burning lowers the delegate's votes and checkpoints the drop
+        #[cfg(feature = "burnable")]
+        #[ink::test]
+        fn burn_reduces_votes() {
+            let mut contract = Erc20::new_minimal(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.delegate(accounts.charlie), Ok(()));
+            assert_eq!(contract.get_votes(accounts.charlie), 100);
+            let block = ink_env::block_number::<ink_env::DefaultEnvironment>();
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.burn(30), Ok(()));
+            assert_eq!(contract.get_votes(accounts.charlie), 70);
+            assert_eq!(contract.approve(accounts.bob, 20), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(contract.burn_from(accounts.alice, 20), Ok(()));
+            assert_eq!(contract.get_votes(accounts.charlie), 50);
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.get_past_votes(accounts.charlie, block), Ok(100));
+            assert_eq!(contract.get_past_votes(accounts.charlie, block + 1), Ok(50));
+        }
+
+        /// Test if vesting schedules release nothing before their cliff and then vest linearly
+        #[ink::test]
+        fn vesting_works() {
+            let escrow = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                contract.create_vesting_schedule(bob, now, 61, 60, 60),
+                Err(Error::InvalidVestingSchedule)
+            );
+            assert_eq!(
+                contract.create_vesting_schedule(bob, now, 12, 60, 60),
+                Ok(0)
+            );
+            assert_eq!(contract.create_vesting_schedule(bob, now, 0, 6, 10), Ok(1));
+            assert_eq!(contract.balance_of(alice), 30);
+            assert_eq!(contract.balance_of(escrow), 70);
+
+            set_caller(bob);
+            assert_eq!(
+                contract.create_vesting_schedule(bob, now, 0, 6, 10),
+                Err(Error::NotOwner)
+            );
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            // Only the schedule without a cliff has vested yet
+            assert_eq!(contract.releasable_amount(bob), 10);
+            assert_eq!(contract.release(), Ok(10));
+            assert_eq!(contract.release(), Err(Error::NothingVested));
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.releasable_amount(bob), 12);
+            assert_eq!(contract.release(), Ok(12));
+            for _ in 0..10 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(contract.release(), Ok(48));
+            assert_eq!(
+                contract.vesting_schedule(bob, 0),
+                Some((now, 12, 60, 60, 60))
+            );
+            assert_eq!(contract.balance_of(bob), 70);
+            assert_eq!(contract.balance_of(escrow), 0);
+        }
+
+        /// Test if the percentage fee adds to the flat fee and exempt senders pay neither
+        #[ink::test]
+        fn fee_bps_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(1000);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let treasury = AccountId::from([0x9; 32]);
+            assert_eq!(contract.set_treasury(treasury), Ok(()));
+            assert_eq!(contract.set_flat_fee(1), Ok(()));
+            assert_eq!(contract.set_fee_bps(10_001), Err(Error::InvalidFeeRate));
+            assert_eq!(contract.set_fee_bps(500), Ok(()));
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, true), Ok(()));
+
+            assert_eq!(contract.transfer(bob, 100), Ok(()));
+            assert_eq!(contract.balance_of(bob), 94);
+            assert_eq!(contract.balance_of(treasury), 6);
+
+            assert_eq!(contract.set_fee_exempt(alice, true), Ok(()));
+            assert!(contract.is_fee_exempt(alice));
+            assert_eq!(contract.transfer(bob, 100), Ok(()));
+            assert_eq!(contract.balance_of(bob), 194);
+            assert_eq!(contract.balance_of(treasury), 6);
+
+            set_caller(bob);
+            assert_eq!(contract.set_fee_exempt(bob, true), Err(Error::NotOwner));
+        }
+
+        /// Test if a fee too large to compute fails the transfer instead of wrapping
+        #[ink::test]
+        fn fee_overflow_is_rejected() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(1000);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_fee_bps(500), Ok(()));
+            assert_eq!(contract.set_feature_flag(FEATURE_FEES, true), Ok(()));
+
+            seed_balance(&mut contract, bob, Balance::MAX);
+            set_caller(bob);
+            assert_eq!(
+                contract.transfer(charlie, Balance::MAX),
+                Err(Error::Overflow)
+            );
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.set_flat_fee(Balance::MAX), Ok(()));
+            assert_eq!(contract.transfer(charlie, 100), Err(Error::Overflow));
+            assert_eq!(contract.balance_of(charlie), 0);
+        }
+
+        /// Test if blacklisted accounts can neither be granted allowances nor spend them
+        #[ink::test]
+        fn blacklist_blocks_allowances() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.approve(bob, 10), Ok(()));
+            assert_eq!(contract.set_blacklisted_until(bob, Timestamp::MAX), Ok(()));
+            assert_eq!(contract.approve(bob, 20), Err(Error::Blacklisted));
+            assert_eq!(contract.increase_allowance(bob, 5), Err(Error::Blacklisted));
+
+            set_caller(bob);
+            assert_eq!(
+                contract.transfer_from(alice, charlie, 5),
+                Err(Error::Blacklisted)
+            );
+            assert_eq!(contract.balance_of(charlie), 0);
+
+            // The owner of the allowance can still revoke it
+            set_caller(alice);
+            assert_eq!(contract.approve(bob, 0), Ok(()));
+            assert_eq!(contract.allowance(alice, bob), 0);
+        }
+
+        /// Test if only the owner can upgrade or migrate, and migrating is a no-op on the current layout
+        #[ink::test]
+        fn upgrade_is_restricted() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.storage_version(), STORAGE_VERSION);
+            assert_eq!(contract.migrate(), Err(Error::AlreadyMigrated));
+
+            // The off-chain environment cannot swap code, so only the guard is exercised
+            set_caller(bob);
+            assert_eq!(
+                contract.upgrade(Hash::from([0x1; 32])),
+                Err(Error::NotOwner)
+            );
+            assert_eq!(contract.migrate(), Err(Error::NotOwner));
+        }
+
+        /// Test if native currency wraps into tokens 1:1 and unwraps back
+        #[ink::test]
+        fn deposit_withdraw_works() {
+            let contract_account = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let native_balance = |account| {
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(account)
+                    .unwrap_or_default()
+            };
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract_account, 0);
+
+            set_caller(bob);
+            // Simulate the attached value arriving at the contract
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract_account, 40);
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(40);
+            assert_eq!(contract.deposit(), Ok(()));
+            assert_eq!(contract.balance_of(bob), 40);
+            assert_eq!(contract.total_supply(), 140);
+
+            let bob_native = native_balance(bob);
+            assert_eq!(
+                contract.withdraw(50),
+                Err(Error::InsufficientBalance {
+                    required: 50,
+                    available: 40
+                })
+            );
+            assert_eq!(contract.withdraw(15), Ok(()));
+            assert_eq!(contract.balance_of(bob), 25);
+            assert_eq!(contract.total_supply(), 125);
+            assert_eq!(native_balance(bob), bob_native + 15);
+            assert_eq!(native_balance(contract_account), 25);
+            assert_eq!(contract.wrapped_supply(), 25);
+        }
+
+        /// Test if tokens not minted by a deposit cannot withdraw other reserves
+        #[ink::test]
+        fn withdraw_requires_wrapped_backing() {
+            let contract_account = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            // Reserves held for something other than wrapping, e.g. redemptions
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(
+                contract_account,
+                500,
+            );
+            assert_eq!(contract.withdraw(10), Err(Error::InsufficientReserves));
+
+            set_caller(bob);
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(
+                contract_account,
+                540,
+            );
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(40);
+            assert_eq!(contract.deposit(), Ok(()));
+            assert_eq!(contract.wrapped_supply(), 40);
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.withdraw(41), Err(Error::InsufficientReserves));
             assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 100);
-            contract
-                .approve(AccountId::from([0x1; 32]), 200)
-                .unwrap_or_default();
+        }
+
+        /// Test if Merkle airdrop claims verify their proof against a fixture tree and pay out once
+        #[ink::test]
+        fn merkle_airdrop_works() {
+            let escrow = set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let django = AccountId::from([0x4; 32]);
+            let leaves = [
+                airdrop_leaf(0, &bob, 10),
+                airdrop_leaf(1, &charlie, 20),
+                airdrop_leaf(2, &django, 5),
+            ];
+            let left = merkle_parent(&leaves[0], &leaves[1]);
+            let root = merkle_parent(&left, &leaves[2]);
+            assert_eq!(contract.start_merkle_airdrop(root, 40), Ok(()));
+            assert_eq!(contract.merkle_airdrop(), Some((root, 40)));
+            assert_eq!(contract.balance_of(escrow), 40);
+
+            set_caller(bob);
+            let bob_proof = vec![leaves[1], leaves[2]];
             assert_eq!(
-                contract.allowance(AccountId::from([0x1; 32]), AccountId::from([0x1; 32])),
-                200
+                contract.claim_airdrop(0, 20, bob_proof.clone()),
+                Err(Error::InvalidAirdropProof)
             );
-            contract
-                .transfer_from(AccountId::from([0x1; 32]), AccountId::from([0x0; 32]), 50)
-                .unwrap_or_default();
-            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 50);
             assert_eq!(
-                contract.allowance(AccountId::from([0x1; 32]), AccountId::from([0x1; 32])),
-                150
+                contract.claim_airdrop(1, 20, vec![leaves[0], leaves[2]]),
+                Err(Error::InvalidAirdropProof)
             );
+            assert_eq!(contract.claim_airdrop(0, 10, bob_proof.clone()), Ok(()));
+            assert!(contract.is_airdrop_claimed(0));
+            assert_eq!(
+                contract.claim_airdrop(0, 10, bob_proof),
+                Err(Error::AlreadyClaimed)
+            );
+            set_caller(django);
+            assert_eq!(contract.claim_airdrop(2, 5, vec![left]), Ok(()));
+            assert_eq!(contract.balance_of(bob), 10);
+            assert_eq!(contract.balance_of(django), 5);
 
-            contract
-                .transfer_from(AccountId::from([0x1; 32]), AccountId::from([0x0; 32]), 100)
-                .unwrap_or_default();
-            assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 50);
+            set_caller(alice);
+            assert_eq!(contract.end_merkle_airdrop(), Ok(25));
+            assert_eq!(contract.merkle_airdrop(), None);
+            assert_eq!(contract.balance_of(alice), 85);
+            assert_eq!(contract.balance_of(escrow), 0);
+        }
+
+        /// Test if the staking reward rate mints rewards per block into the share price
+        #[ink::test]
+        fn staking_reward_rate_works() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
             assert_eq!(
-                contract.allowance(AccountId::from([0x1; 32]), AccountId::from([0x1; 32])),
-                150
+                contract.set_staking_reward_rate(Some(0)),
+                Err(Error::InvalidRewardRate)
+            );
+            assert_eq!(contract.set_staking_reward_rate(Some(5)), Ok(()));
+            assert_eq!(contract.lower_cap(120), Ok(()));
+            assert_eq!(contract.transfer(bob, 20), Ok(()));
+            assert_eq!(contract.stake(20), Ok(20));
+            set_caller(bob);
+            assert_eq!(contract.stake(20), Ok(20));
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.pending_rewards(bob), 5);
+            assert_eq!(contract.share_price(), 1_250_000_000_000);
+            assert_eq!(contract.unstake(20), Ok(25));
+            assert_eq!(contract.total_supply(), 110);
+
+            // Minting stops at the supply cap
+            for _ in 0..5 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(contract.pending_rewards(alice), 10);
+            set_caller(alice);
+            assert_eq!(contract.unstake(20), Ok(35));
+            assert_eq!(contract.total_supply(), 120);
+        }
+
+        /// Test if staking is refused to accounts that could not transfer into the pool
+        #[ink::test]
+        fn stake_respects_restrictions() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(bob, 30), Ok(()));
+            assert_eq!(contract.transfer(charlie, 30), Ok(()));
+            assert_eq!(contract.set_tainted(bob, true), Ok(()));
+            assert_eq!(contract.set_daily_activity_cap(Some(20)), Ok(()));
+
+            set_caller(bob);
+            assert_eq!(contract.stake(10), Err(Error::TaintedFunds));
+            set_caller(charlie);
+            assert_eq!(contract.stake(30), Err(Error::DailyActivityCapExceeded));
+            assert_eq!(contract.stake(10), Ok(10));
+            assert_eq!(contract.balance_of(bob), 30);
+        }
+
+        /// Test if multisig actions only execute once enough admins confirmed them in time
+        #[cfg(all(feature = "mintable", feature = "pausable"))]
+        #[ink::test]
+        fn multisig_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let django = AccountId::from([0x4; 32]);
+            assert_eq!(
+                contract.set_multisig_config(0, 60),
+                Err(Error::InvalidThreshold)
+            );
+            assert_eq!(contract.set_multisig_config(2, 60), Ok(()));
+            assert_eq!(contract.grant_role(Role::Admin, bob), Ok(()));
+
+            assert_eq!(contract.propose_action(Action::Mint(charlie, 10)), Ok(0));
+            assert_eq!(contract.confirm(0), Err(Error::AlreadyConfirmed));
+            assert_eq!(contract.balance_of(charlie), 0);
+            set_caller(django);
+            assert_eq!(contract.confirm(0), Err(Error::NotAuthorized));
+            set_caller(bob);
+            assert_eq!(contract.confirm(0), Ok(()));
+            assert_eq!(contract.balance_of(charlie), 10);
+            assert_eq!(contract.proposal(0), None);
+            assert_eq!(contract.confirm(0), Err(Error::UnknownProposal));
+
+            // Expired proposals can no longer be confirmed
+            assert_eq!(contract.propose_action(Action::Pause), Ok(1));
+            for _ in 0..11 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            set_caller(alice);
+            assert_eq!(contract.confirm(1), Err(Error::ProposalExpired));
+            assert!(!contract.paused());
+        }
+
+        /// Test if privileged calls only go through the multisig once it needs confirmations
+        #[cfg(all(feature = "mintable", feature = "pausable"))]
+        #[ink::test]
+        fn multisig_replaces_direct_calls() {
+            let mut contract = Erc20::new_minimal(100);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_max_lifetime_mint(Some(15)), Ok(()));
+            assert_eq!(contract.set_multisig_config(2, 60), Ok(()));
+            assert_eq!(contract.grant_role(Role::Admin, bob), Ok(()));
+            assert_eq!(contract.mint(charlie, 1), Err(Error::MultisigRequired));
+            assert_eq!(contract.pause(), Err(Error::MultisigRequired));
+            assert_eq!(contract.unpause(), Err(Error::MultisigRequired));
+            assert_eq!(
+                contract.upgrade(Hash::from([0x1; 32])),
+                Err(Error::MultisigRequired)
+            );
+            assert_eq!(
+                contract.set_multisig_config(1, 60),
+                Err(Error::MultisigRequired)
+            );
+
+            // Multisig mints count toward the lifetime cap like any other
+            assert_eq!(contract.propose_action(Action::Mint(charlie, 10)), Ok(0));
+            assert_eq!(contract.propose_action(Action::Mint(charlie, 10)), Ok(1));
+            set_caller(bob);
+            assert_eq!(contract.confirm(0), Ok(()));
+            assert_eq!(contract.confirm(1), Err(Error::LifetimeMintCapExceeded));
+            assert_eq!(contract.balance_of(charlie), 10);
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(
+                contract.propose_action(Action::SetMultisigConfig(1, 60)),
+                Ok(2)
+            );
+            set_caller(bob);
+            assert_eq!(contract.confirm(2), Ok(()));
+            assert_eq!(contract.multisig_config(), (1, 60));
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.pause(), Ok(()));
+        }
+
+        /// Test if guarded messages are rejected while the reentrancy lock is held and release it afterwards
+        #[ink::test]
+        fn reentrancy_guard_works() {
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            assert_eq!(contract.approve(bob, 10), Ok(()));
+            // The off-chain environment cannot run an attacker contract, so take
+            // the lock the way a call still in progress would hold it
+            contract.reentrancy_lock.insert((), &());
+            assert_eq!(contract.transfer(bob, 10), Err(Error::ReentrantCall));
+            set_caller(bob);
+            assert_eq!(
+                contract.transfer_from(alice, bob, 10),
+                Err(Error::ReentrantCall)
+            );
+            assert_eq!(contract.withdraw(10), Err(Error::ReentrantCall));
+            assert_eq!(contract.balance_of(bob), 0);
+
+            contract.reentrancy_lock.remove(());
+            assert_eq!(contract.transfer_from(alice, bob, 10), Ok(()));
+            assert_eq!(contract.transfer(charlie, 5), Ok(()));
+            assert_eq!(contract.balance_of(bob), 5);
+            assert!(!contract.reentrancy_lock.contains(()));
+        }
+
+        /// Test if messages called back while a guarded message runs are rejected
+        #[ink::test]
+        fn reentrant_messages_are_rejected() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            // Stands in for a hook or mirror contract calling back mid-transfer
+            let reentered = contract.non_reentrant(|contract| {
+                contract.transfer_from_to(&alice, &bob, 10)?;
+                Ok(vec![
+                    contract.transfer(bob, 1),
+                    contract.transfer_batch(vec![bob], vec![1]),
+                    contract.stake(1).map(|_| ()),
+                    contract.claim_dividends().map(|_| ()),
+                    contract.release().map(|_| ()),
+                ])
+            });
+            assert_eq!(
+                reentered.map(|results| results
+                    .into_iter()
+                    .all(|result| result == Err(Error::ReentrantCall))),
+                Ok(true)
+            );
+            assert_eq!(contract.balance_of(bob), 10);
+            assert_eq!(contract.balance_of(alice), 90);
+
+            // The lock is released even when the guarded message fails
+            assert_eq!(
+                contract.transfer(bob, 1000),
+                Err(Error::InsufficientBalance {
+                    required: 1000,
+                    available: 90
+                })
+            );
+            assert_eq!(contract.stake(10), Ok(10));
+            assert_eq!(contract.transfer(bob, 10), Ok(()));
+        }
+
+        /// Test if dividends can still be read and claimed while balances decay
+        #[ink::test]
+        fn dividends_with_demurrage_work() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(1_000_000);
+            let bob = AccountId::from([0x2; 32]);
+            let decay_pool = AccountId::from([0x9; 32]);
+            assert_eq!(contract.transfer(bob, 400_000), Ok(()));
+            assert_eq!(contract.deposit_dividends(200_000), Ok(()));
+            set_caller(bob);
+            assert_eq!(
+                contract.transfer(AccountId::from([0x3; 32]), 100_000),
+                Ok(())
+            );
+            assert_eq!(contract.dividends_of(bob), 100_000);
+
+            set_caller(AccountId::from([0x1; 32]));
+            // 1% per millisecond
+            assert_eq!(
+                contract.set_demurrage(Some((DEMURRAGE_RATE_PRECISION / 100, decay_pool))),
+                Ok(())
+            );
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.balance_of(bob), 282_000);
+            assert_eq!(contract.dividends_of(bob), 100_000);
+
+            set_caller(bob);
+            assert_eq!(contract.claim_dividends(), Ok(100_000));
+            assert_eq!(contract.dividends_of(bob), 0);
+            assert_eq!(contract.balance_of(bob), 382_000);
+        }
+
+        /// Test if locked tokens cannot be escrowed or burned
+        #[ink::test]
+        fn escrow_and_burn_respect_locks() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.set_locked_amount(alice, 95), Ok(()));
+
+            assert_eq!(
+                contract.schedule_transfer(bob, 10, now + 60),
+                Err(Error::AmountLocked)
+            );
+            assert_eq!(
+                contract.conditional_transfer(bob, 10, 1),
+                Err(Error::AmountLocked)
+            );
+            assert_eq!(
+                contract.create_swap(bob, 10, AccountId::from([0x9; 32]), 1),
+                Err(Error::AmountLocked)
+            );
+            assert_eq!(
+                contract.create_vesting_schedule(bob, now, 0, 60, 10),
+                Err(Error::AmountLocked)
             );
+            #[cfg(feature = "burnable")]
+            {
+                assert_eq!(contract.burn(10), Err(Error::AmountLocked));
+                assert_eq!(contract.approve(bob, 10), Ok(()));
+                set_caller(bob);
+                assert_eq!(contract.burn_from(alice, 10), Err(Error::AmountLocked));
+                set_caller(alice);
+            }
+            assert_eq!(contract.balance_of(alice), 100);
+            assert_eq!(contract.schedule_transfer(bob, 5, now + 60), Ok(0));
+        }
+
+        /// Test if a recipient cannot move disputed tokens away before the sender reverses
+        #[ink::test]
+        fn disputed_tokens_cannot_escape() {
+            set_contract_account();
+            let mut contract = Erc20::new_minimal(100);
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let charlie = AccountId::from([0x3; 32]);
+            let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.transfer_disputable(bob, 30, 1), Ok(0));
+
+            set_caller(bob);
+            assert_eq!(
+                contract.schedule_transfer(charlie, 30, now + 60),
+                Err(Error::AmountLocked)
+            );
+            #[cfg(feature = "burnable")]
+            assert_eq!(contract.burn(30), Err(Error::AmountLocked));
+            assert_eq!(contract.balance_of(bob), 30);
+
+            set_caller(alice);
+            assert_eq!(contract.reverse_disputable(0), Ok(()));
+            assert_eq!(contract.balance_of(alice), 100);
+            assert_eq!(contract.balance_of(bob), 0);
         }
     }
 }